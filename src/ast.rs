@@ -6,12 +6,16 @@ pub enum CType {
     Char,
     Float,
     Double,
+    LongDouble,
     Void,
+    Bool,
     Long,
+    LongLong,
     Short,
     UnsignedInt,
     UnsignedChar,
     UnsignedLong,
+    UnsignedLongLong,
     UnsignedShort,
     SignedInt,
     SignedChar,
@@ -21,10 +25,14 @@ pub enum CType {
     Array {
         element_type: Box<CType>,
         size: Option<usize>,
+        // 大小不是整数字面量时保留原表达式（如 [SIZE]、[2 * 4]），输出 C 时原样写回
+        size_expr: Option<Box<Expr>>,
     },
     Function {
         return_type: Box<CType>,
         params: Vec<CType>,
+        // 参数列表以 ... 结尾
+        variadic: bool,
     },
 
     // 用户定义类型
@@ -32,10 +40,17 @@ pub enum CType {
     Union(String),
     Enum(String),
     Typedef(String),
+    // 带定义体的 struct/union/enum，如 struct Point { int x; } p; 中的类型
+    StructDef(Box<StructDef>),
+    UnionDef(Box<UnionDef>),
+    EnumDef(Box<EnumDef>),
+    // typeof(expr)；typeof(type) 直接解析为其中的类型
+    TypeOf(Box<Expr>),
 
     // 类型修饰符
     Const(Box<CType>),
     Volatile(Box<CType>),
+    Restrict(Box<CType>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -82,6 +97,7 @@ pub enum BinaryOp {
 #[derive(Debug, Clone, PartialEq)]
 pub enum UnaryOp {
     Neg,           // -x
+    Plus,          // +x
     Not,           // !x
     BitNot,        // ~x
     Deref,         // *x
@@ -94,9 +110,22 @@ pub enum UnaryOp {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
-    IntLiteral(i32),
+    IntLiteral(i128),
+    // 带后缀的整数字面量，如 10U、100ULL
+    IntLiteralSuffixed {
+        value: i128,
+        unsigned: bool,
+        long_count: u8,
+    },
     FloatLiteral(f64),
+    // 带后缀的浮点字面量，suffix 为 'f'（float）或 'l'（long double）
+    FloatLiteralSuffixed {
+        value: f64,
+        suffix: char,
+    },
     CharLiteral(char),
+    // 字符串中 \xHH 或八进制转义的高位字节（0x80..=0xFF）不是 Unicode 字符，
+    // 以 raw_byte_char 编码存放，与源码中直接写出的非 ASCII 字符区分
     StringLiteral(String),
     Identifier(String),
     Binary {
@@ -112,6 +141,11 @@ pub enum Expr {
         func: String,
         args: Vec<Expr>,
     },
+    // 被调用者不是函数名的调用，如 (*fp)(a, b)、tbl[i](x)
+    CallExpr {
+        callee: Box<Expr>,
+        args: Vec<Expr>,
+    },
     Assignment {
         target: Box<Expr>,
         value: Box<Expr>,
@@ -138,12 +172,50 @@ pub enum Expr {
         else_expr: Box<Expr>,
     },
     SizeOf(CType),
+    // sizeof 作用于表达式，如 sizeof arr、sizeof(x + 1)
+    SizeOfExpr(Box<Expr>),
+    // 逗号表达式 (a, b, c)，值为最后一项
+    Comma(Vec<Expr>),
+    // 初始化列表 {1, 2, .x = 3, [4] = 5}
+    InitList(Vec<InitItem>),
+    // 复合字面量 (struct Point){ .x = 1 }
+    CompoundLiteral {
+        typ: CType,
+        init: Vec<InitItem>,
+    },
+    // GNU 语句表达式 ({ ... })，值为最后一条表达式语句
+    StmtExpr(Vec<Stmt>),
     Null,
 }
 
+// 初始化列表中的一项；designators 为空表示按位置初始化
+#[derive(Debug, Clone, PartialEq)]
+pub struct InitItem {
+    pub designators: Vec<Designator>,
+    pub value: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Designator {
+    Field(String), // .field
+    Index(Expr),   // [index]
+    // GNU 扩展的下标范围 [lo ... hi]，范围内的元素都初始化为同一个值
+    Range(Expr, Expr),
+}
+
+// 存储类说明符
+#[derive(Debug, Clone, PartialEq)]
+pub enum StorageClass {
+    Static,
+    Extern,
+    Auto,
+    Register,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Stmt {
     VarDecl {
+        storage: Option<StorageClass>,
         typ: CType,
         name: String,
         init: Option<Expr>,
@@ -173,17 +245,40 @@ pub enum Stmt {
         expr: Expr,
         cases: Vec<SwitchCase>,
     },
-    Break,
-    Continue,
+    // break/continue 记录所在位置，用于报告不在循环或 switch 内的用法
+    Break(SourcePos),
+    Continue(SourcePos),
     Goto(String),
     Label(String),
     Block(Vec<Stmt>),
+    // 一条声明中的多个声明符，如 int i = 0, *p;，元素均为 VarDecl，共享同一基础类型
+    DeclList(Vec<Stmt>),
     Empty,
+    // 内联汇编，不解析其结构，保存整条语句的源码文本（不含结尾分号），如 asm volatile("nop")
+    InlineAsm(String),
+    // 保留注释时，位于下一条语句之前的注释原文
+    Comment(String),
+}
+
+// 语句在源码中的位置，行号与列号从 1 开始。手工构造的 AST 使用 SourcePos::default()。
+// 位置不参与相等比较，排版不同的同一段代码解析出的 AST 仍然相等
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SourcePos {
+    pub line: usize,
+    pub col: usize,
+}
+
+impl PartialEq for SourcePos {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct SwitchCase {
     pub value: Option<Expr>, // None表示default
+    // GNU 范围标签 case lo ... hi: 的上界，value 为下界
+    pub range_end: Option<Expr>,
     pub stmts: Vec<Stmt>,
 }
 
@@ -195,9 +290,17 @@ pub struct Param {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Function {
+    pub storage: Option<StorageClass>,
+    // 是否带 inline 函数说明符
+    pub is_inline: bool,
     pub return_type: CType,
     pub name: String,
     pub params: Vec<Param>,
+    // 参数列表写作 (void)：明确没有参数，此时 params 为空；
+    // 空的 () 在 C 中表示参数未指定，两者输出时需要区分
+    pub explicit_void: bool,
+    // 参数列表以 ... 结尾，如 int printf(const char *fmt, ...)
+    pub variadic: bool,
     pub body: Vec<Stmt>,
 }
 
@@ -212,6 +315,8 @@ pub struct StructDef {
 pub struct StructField {
     pub typ: CType,
     pub name: String,
+    // 位域宽度，如 unsigned int flag : 1;
+    pub bit_width: Option<u32>,
 }
 
 // 联合体定义
@@ -231,7 +336,8 @@ pub struct EnumDef {
 #[derive(Debug, Clone, PartialEq)]
 pub struct EnumVariant {
     pub name: String,
-    pub value: Option<i32>,
+    // 显式给出的常量表达式；None 表示在前一个值的基础上自增
+    pub value: Option<Expr>,
 }
 
 // Typedef定义
@@ -250,6 +356,7 @@ pub enum Declaration {
     Enum(EnumDef),
     Typedef(TypedefDef),
     GlobalVar {
+        storage: Option<StorageClass>,
         typ: CType,
         name: String,
         init: Option<Expr>,
@@ -259,9 +366,221 @@ pub enum Declaration {
         name: String,
         value: String,
     },
+    // 不完整类型的前置声明 struct Node; 或 union Value;，类型为 Struct 或 Union
+    ForwardDecl(CType),
+    // #pragma 指令之后的内容，如 once、pack(1)
+    Pragma(String),
+    // 保留注释时，位于下一个声明之前的注释原文（含 // 或 /* */）
+    Comment(String),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Program {
     pub declarations: Vec<Declaration>,
 }
+
+// 目标平台的数据模型，决定 long 与指针的宽度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DataModel {
+    // long 与指针为 64 位（64 位 Linux、macOS）
+    #[default]
+    LP64,
+    // int、long 与指针均为 32 位（32 位 x86）
+    ILP32,
+}
+
+impl DataModel {
+    fn pointer_size(self) -> usize {
+        match self {
+            DataModel::LP64 => 8,
+            DataModel::ILP32 => 4,
+        }
+    }
+
+    fn long_size(self) -> usize {
+        self.pointer_size()
+    }
+}
+
+impl CType {
+    // 类型在给定数据模型下占用的字节数；结构体、联合体、typedef 等缺少布局信息的类型，
+    // 以及 void、函数类型和未指定大小的数组返回 None
+    pub fn size_of(&self, model: DataModel) -> Option<usize> {
+        match self {
+            CType::Char | CType::SignedChar | CType::UnsignedChar | CType::Bool => Some(1),
+            CType::Short | CType::UnsignedShort => Some(2),
+            CType::Int | CType::SignedInt | CType::UnsignedInt | CType::Float => Some(4),
+            // 枚举按 int 存放
+            CType::Enum(_) | CType::EnumDef(_) => Some(4),
+            CType::Long | CType::UnsignedLong => Some(model.long_size()),
+            CType::LongLong | CType::UnsignedLongLong | CType::Double => Some(8),
+            // x86 上的 80 位扩展精度，按对齐补齐
+            CType::LongDouble => Some(match model {
+                DataModel::LP64 => 16,
+                DataModel::ILP32 => 12,
+            }),
+            CType::Pointer(_) => Some(model.pointer_size()),
+            CType::Array {
+                element_type,
+                size: Some(n),
+                ..
+            } => element_type.size_of(model)?.checked_mul(*n),
+            CType::Const(inner) | CType::Volatile(inner) | CType::Restrict(inner) => {
+                inner.size_of(model)
+            }
+            _ => None,
+        }
+    }
+
+    // 类型在给定数据模型下的对齐字节数；无法求出大小的标量类型同样返回 None
+    pub fn align_of(&self, model: DataModel) -> Option<usize> {
+        match (self, model) {
+            (CType::LongDouble, DataModel::LP64) => Some(16),
+            // i386 System V ABI 中 8 字节以上的标量只按 4 字节对齐
+            (
+                CType::LongDouble | CType::Double | CType::LongLong | CType::UnsignedLongLong,
+                DataModel::ILP32,
+            ) => Some(4),
+            // 数组按元素对齐，未指定大小的数组也有确定的对齐
+            (CType::Array { element_type, .. }, _) => element_type.align_of(model),
+            (CType::Const(inner) | CType::Volatile(inner) | CType::Restrict(inner), _) => {
+                inner.align_of(model)
+            }
+            _ => self.size_of(model),
+        }
+    }
+}
+
+// 字符串字面量中单个原始字节的表示，占用私有区 U+10FF80..=U+10FFFF
+const RAW_BYTE_BASE: u32 = 0x10FF00;
+
+pub fn raw_byte_char(byte: u8) -> char {
+    char::from_u32(RAW_BYTE_BASE + byte as u32).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+// raw_byte_char 的逆映射，普通字符返回 None
+pub fn char_raw_byte(ch: char) -> Option<u8> {
+    (ch as u32)
+        .checked_sub(RAW_BYTE_BASE)
+        .and_then(|b| u8::try_from(b).ok())
+        .filter(|&b| b >= 0x80)
+}
+
+// 把字面量内容重新转义为 C 源码形式，quote 为所在字面量的引号。
+// 其余控制字符统一输出为三位八进制转义，避免与后续数字连在一起被误读
+pub fn escape_c_string(s: &str, quote: char) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if let Some(byte) = char_raw_byte(ch) {
+            // 原始字节还原为 \xHH；后面紧跟十六进制数字时会被 \x 吞进去，改用八进制
+            if chars.peek().is_some_and(|next| next.is_ascii_hexdigit()) {
+                result.push_str(&format!("\\{:03o}", byte));
+            } else {
+                result.push_str(&format!("\\x{:02x}", byte));
+            }
+            continue;
+        }
+        match ch {
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            '\x0b' => result.push_str("\\v"),
+            '\x0c' => result.push_str("\\f"),
+            '\x07' => result.push_str("\\a"),
+            '\x08' => result.push_str("\\b"),
+            c if c == quote => {
+                result.push('\\');
+                result.push(c);
+            }
+            '\0' if quote == '\'' => result.push_str("\\0"),
+            // 字符常量只能容纳一个字节，\xHH 形式的高位字节也按八进制输出
+            c if (c as u32) < 0x20
+                || c == '\x7f'
+                || (quote == '\'' && (0x80..=0xff).contains(&(c as u32))) =>
+            {
+                result.push_str(&format!("\\{:03o}", c as u32));
+            }
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+// 整数常量表达式求值，标识符通过 lookup 查找已知的枚举项或宏常量。
+// 不知道目标数据模型，sizeof 不参与求值
+pub fn const_int_value(expr: &Expr, lookup: &dyn Fn(&str) -> Option<i128>) -> Option<i128> {
+    eval_const(expr, lookup, None)
+}
+
+// 同 const_int_value，sizeof(类型) 按数据模型 model 求值
+pub fn const_int_value_in(
+    expr: &Expr,
+    model: DataModel,
+    lookup: &dyn Fn(&str) -> Option<i128>,
+) -> Option<i128> {
+    eval_const(expr, lookup, Some(model))
+}
+
+fn eval_const(
+    expr: &Expr,
+    lookup: &dyn Fn(&str) -> Option<i128>,
+    model: Option<DataModel>,
+) -> Option<i128> {
+    match expr {
+        Expr::IntLiteral(n) | Expr::IntLiteralSuffixed { value: n, .. } => Some(*n),
+        Expr::CharLiteral(c) => Some(*c as i128),
+        Expr::Identifier(name) => lookup(name),
+        Expr::SizeOf(typ) => model
+            .and_then(|model| typ.size_of(model))
+            .and_then(|n| i128::try_from(n).ok()),
+        Expr::Unary { op, operand } => {
+            let v = eval_const(operand, lookup, model)?;
+            match op {
+                UnaryOp::Neg => v.checked_neg(),
+                UnaryOp::Plus => Some(v),
+                UnaryOp::BitNot => Some(!v),
+                UnaryOp::Not => Some((v == 0) as i128),
+                _ => None,
+            }
+        }
+        Expr::Binary { op, left, right } => {
+            let l = eval_const(left, lookup, model)?;
+            let r = eval_const(right, lookup, model)?;
+            match op {
+                BinaryOp::Add => l.checked_add(r),
+                BinaryOp::Sub => l.checked_sub(r),
+                BinaryOp::Mul => l.checked_mul(r),
+                BinaryOp::Div => l.checked_div(r),
+                BinaryOp::Mod => l.checked_rem(r),
+                BinaryOp::BitAnd => Some(l & r),
+                BinaryOp::BitOr => Some(l | r),
+                BinaryOp::BitXor => Some(l ^ r),
+                BinaryOp::LeftShift => l.checked_shl(u32::try_from(r).ok()?),
+                BinaryOp::RightShift => l.checked_shr(u32::try_from(r).ok()?),
+                BinaryOp::Lt => Some((l < r) as i128),
+                BinaryOp::Gt => Some((l > r) as i128),
+                BinaryOp::Le => Some((l <= r) as i128),
+                BinaryOp::Ge => Some((l >= r) as i128),
+                BinaryOp::Eq => Some((l == r) as i128),
+                BinaryOp::Ne => Some((l != r) as i128),
+                BinaryOp::And => Some((l != 0 && r != 0) as i128),
+                BinaryOp::Or => Some((l != 0 || r != 0) as i128),
+                _ => None,
+            }
+        }
+        Expr::Ternary {
+            cond,
+            then_expr,
+            else_expr,
+        } => {
+            if eval_const(cond, lookup, model)? != 0 {
+                eval_const(then_expr, lookup, model)
+            } else {
+                eval_const(else_expr, lookup, model)
+            }
+        }
+        _ => None,
+    }
+}
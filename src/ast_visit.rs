@@ -0,0 +1,454 @@
+use crate::ast::*;
+use crate::parser::ParseError;
+use std::collections::HashSet;
+
+// AST 遍历接口。默认实现调用对应的 walk_* 函数递归访问子节点，
+// 分析 pass 只需覆盖关心的方法，需要继续深入时在其中再调用 walk_*
+pub trait Visitor {
+    fn visit_decl(&mut self, decl: &Declaration) {
+        walk_decl(self, decl);
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+
+    fn visit_type(&mut self, typ: &CType) {
+        walk_type(self, typ);
+    }
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    for decl in &program.declarations {
+        visitor.visit_decl(decl);
+    }
+}
+
+pub fn walk_decl<V: Visitor + ?Sized>(visitor: &mut V, decl: &Declaration) {
+    match decl {
+        Declaration::Function(func) => {
+            visitor.visit_type(&func.return_type);
+            for param in &func.params {
+                visitor.visit_type(&param.typ);
+            }
+            walk_stmts(visitor, &func.body);
+        }
+        Declaration::Struct(def) => walk_fields(visitor, &def.fields),
+        Declaration::Union(def) => walk_fields(visitor, &def.fields),
+        Declaration::Enum(def) => walk_variants(visitor, &def.variants),
+        Declaration::Typedef(def) => visitor.visit_type(&def.target_type),
+        Declaration::ForwardDecl(typ) => visitor.visit_type(typ),
+        Declaration::GlobalVar { typ, init, .. } => {
+            visitor.visit_type(typ);
+            if let Some(init) = init {
+                visitor.visit_expr(init);
+            }
+        }
+        Declaration::Include(_)
+        | Declaration::Define { .. }
+        | Declaration::Pragma(_)
+        | Declaration::Comment(_) => {}
+    }
+}
+
+pub fn walk_stmts<V: Visitor + ?Sized>(visitor: &mut V, stmts: &[Stmt]) {
+    for stmt in stmts {
+        visitor.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &Stmt) {
+    match stmt {
+        Stmt::VarDecl { typ, init, .. } => {
+            visitor.visit_type(typ);
+            if let Some(init) = init {
+                visitor.visit_expr(init);
+            }
+        }
+        Stmt::Return(expr) => {
+            if let Some(expr) = expr {
+                visitor.visit_expr(expr);
+            }
+        }
+        Stmt::Expr(expr) => visitor.visit_expr(expr),
+        Stmt::If {
+            cond,
+            then_block,
+            else_block,
+        } => {
+            visitor.visit_expr(cond);
+            walk_stmts(visitor, then_block);
+            if let Some(else_block) = else_block {
+                walk_stmts(visitor, else_block);
+            }
+        }
+        Stmt::While { cond, body } => {
+            visitor.visit_expr(cond);
+            walk_stmts(visitor, body);
+        }
+        Stmt::DoWhile { body, cond } => {
+            walk_stmts(visitor, body);
+            visitor.visit_expr(cond);
+        }
+        Stmt::For {
+            init,
+            cond,
+            update,
+            body,
+        } => {
+            if let Some(init) = init {
+                visitor.visit_stmt(init);
+            }
+            if let Some(cond) = cond {
+                visitor.visit_expr(cond);
+            }
+            if let Some(update) = update {
+                visitor.visit_expr(update);
+            }
+            walk_stmts(visitor, body);
+        }
+        Stmt::Switch { expr, cases } => {
+            visitor.visit_expr(expr);
+            for case in cases {
+                if let Some(value) = &case.value {
+                    visitor.visit_expr(value);
+                }
+                if let Some(end) = &case.range_end {
+                    visitor.visit_expr(end);
+                }
+                walk_stmts(visitor, &case.stmts);
+            }
+        }
+        Stmt::Block(stmts) | Stmt::DeclList(stmts) => walk_stmts(visitor, stmts),
+        Stmt::Break(_)
+        | Stmt::Continue(_)
+        | Stmt::Goto(_)
+        | Stmt::Label(_)
+        | Stmt::Empty
+        | Stmt::InlineAsm(_)
+        | Stmt::Comment(_) => {}
+    }
+}
+
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &Expr) {
+    match expr {
+        Expr::Binary { left, right, .. } => {
+            visitor.visit_expr(left);
+            visitor.visit_expr(right);
+        }
+        Expr::Assignment { target, value } => {
+            visitor.visit_expr(target);
+            visitor.visit_expr(value);
+        }
+        Expr::Unary { operand, .. } => visitor.visit_expr(operand),
+        Expr::Call { args, .. } | Expr::Comma(args) => {
+            for arg in args {
+                visitor.visit_expr(arg);
+            }
+        }
+        Expr::CallExpr { callee, args } => {
+            visitor.visit_expr(callee);
+            for arg in args {
+                visitor.visit_expr(arg);
+            }
+        }
+        Expr::Cast { typ, expr } => {
+            visitor.visit_type(typ);
+            visitor.visit_expr(expr);
+        }
+        Expr::ArrayAccess { array, index } => {
+            visitor.visit_expr(array);
+            visitor.visit_expr(index);
+        }
+        Expr::MemberAccess { object, .. } | Expr::PointerMemberAccess { object, .. } => {
+            visitor.visit_expr(object);
+        }
+        Expr::Ternary {
+            cond,
+            then_expr,
+            else_expr,
+        } => {
+            visitor.visit_expr(cond);
+            visitor.visit_expr(then_expr);
+            visitor.visit_expr(else_expr);
+        }
+        Expr::SizeOf(typ) => visitor.visit_type(typ),
+        Expr::SizeOfExpr(expr) => visitor.visit_expr(expr),
+        Expr::InitList(items) => walk_init_items(visitor, items),
+        Expr::CompoundLiteral { typ, init } => {
+            visitor.visit_type(typ);
+            walk_init_items(visitor, init);
+        }
+        Expr::StmtExpr(stmts) => walk_stmts(visitor, stmts),
+        Expr::IntLiteral(_)
+        | Expr::IntLiteralSuffixed { .. }
+        | Expr::FloatLiteral(_)
+        | Expr::FloatLiteralSuffixed { .. }
+        | Expr::CharLiteral(_)
+        | Expr::StringLiteral(_)
+        | Expr::Identifier(_)
+        | Expr::Null => {}
+    }
+}
+
+pub fn walk_type<V: Visitor + ?Sized>(visitor: &mut V, typ: &CType) {
+    match typ {
+        CType::Pointer(inner)
+        | CType::Const(inner)
+        | CType::Volatile(inner)
+        | CType::Restrict(inner) => visitor.visit_type(inner),
+        CType::Array {
+            element_type,
+            size_expr,
+            ..
+        } => {
+            visitor.visit_type(element_type);
+            if let Some(size_expr) = size_expr {
+                visitor.visit_expr(size_expr);
+            }
+        }
+        CType::Function {
+            return_type,
+            params,
+            ..
+        } => {
+            visitor.visit_type(return_type);
+            for param in params {
+                visitor.visit_type(param);
+            }
+        }
+        CType::StructDef(def) => walk_fields(visitor, &def.fields),
+        CType::UnionDef(def) => walk_fields(visitor, &def.fields),
+        CType::EnumDef(def) => walk_variants(visitor, &def.variants),
+        CType::TypeOf(expr) => visitor.visit_expr(expr),
+        _ => {}
+    }
+}
+
+fn walk_fields<V: Visitor + ?Sized>(visitor: &mut V, fields: &[StructField]) {
+    for field in fields {
+        visitor.visit_type(&field.typ);
+    }
+}
+
+fn walk_variants<V: Visitor + ?Sized>(visitor: &mut V, variants: &[EnumVariant]) {
+    for variant in variants {
+        if let Some(value) = &variant.value {
+            visitor.visit_expr(value);
+        }
+    }
+}
+
+fn walk_init_items<V: Visitor + ?Sized>(visitor: &mut V, items: &[InitItem]) {
+    for item in items {
+        for designator in &item.designators {
+            match designator {
+                Designator::Index(index) => visitor.visit_expr(index),
+                Designator::Range(lo, hi) => {
+                    visitor.visit_expr(lo);
+                    visitor.visit_expr(hi);
+                }
+                Designator::Field(_) => {}
+            }
+        }
+        visitor.visit_expr(&item.value);
+    }
+}
+
+// 示例 pass：按首次出现的顺序收集被调用的函数名
+#[derive(Debug, Default)]
+pub struct CallCollector {
+    pub names: Vec<String>,
+}
+
+impl Visitor for CallCollector {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let Expr::Call { func, .. } = expr {
+            if !self.names.contains(func) {
+                self.names.push(func.clone());
+            }
+        }
+        walk_expr(self, expr);
+    }
+}
+
+// 程序中调用过的所有函数名（去重）
+pub fn called_functions(program: &Program) -> Vec<String> {
+    let mut collector = CallCollector::default();
+    walk_program(&mut collector, program);
+    collector.names
+}
+
+// 按声明顺序记录已定义的结构体，收集在定义之前（或从未定义）就被引用的结构体名
+#[derive(Debug, Default)]
+struct ForwardRefCollector {
+    defined: HashSet<String>,
+    types: Vec<CType>,
+}
+
+impl ForwardRefCollector {
+    fn define(&mut self, name: &str) {
+        if !name.is_empty() {
+            self.defined.insert(name.to_string());
+        }
+    }
+}
+
+impl Visitor for ForwardRefCollector {
+    fn visit_decl(&mut self, decl: &Declaration) {
+        match decl {
+            // 结构体在自己的定义体中引用自身（如 struct Node *next）不需要前置声明
+            Declaration::Struct(def) => self.define(&def.name),
+            Declaration::Union(def) => self.define(&def.name),
+            // 源码中已有的前置声明
+            Declaration::ForwardDecl(CType::Struct(name) | CType::Union(name)) => {
+                self.define(name);
+                return;
+            }
+            _ => {}
+        }
+        walk_decl(self, decl);
+    }
+
+    fn visit_type(&mut self, typ: &CType) {
+        match typ {
+            CType::Struct(name) | CType::Union(name)
+                if !self.defined.contains(name) && !self.types.contains(typ) =>
+            {
+                self.types.push(typ.clone());
+            }
+            CType::StructDef(def) => self.define(&def.name),
+            CType::UnionDef(def) => self.define(&def.name),
+            _ => {}
+        }
+        walk_type(self, typ);
+    }
+}
+
+// 在定义或前置声明之前就被引用的结构体与联合体（CType::Struct / CType::Union），按首次引用的顺序
+pub fn forward_declared_structs(program: &Program) -> Vec<CType> {
+    let mut collector = ForwardRefCollector::default();
+    walk_program(&mut collector, program);
+    collector.types
+}
+
+// 收集被引用的 typedef 名与程序中定义的 typedef 名
+#[derive(Debug, Default)]
+struct TypedefCollector {
+    defined: HashSet<String>,
+    used: Vec<String>,
+}
+
+impl Visitor for TypedefCollector {
+    fn visit_decl(&mut self, decl: &Declaration) {
+        if let Declaration::Typedef(def) = decl {
+            self.defined.insert(def.name.clone());
+        }
+        walk_decl(self, decl);
+    }
+
+    fn visit_type(&mut self, typ: &CType) {
+        if let CType::Typedef(name) = typ {
+            if !self.used.contains(name) {
+                self.used.push(name.clone());
+            }
+        }
+        walk_type(self, typ);
+    }
+}
+
+// 被引用但程序中没有定义的 typedef 名（如来自标准库头文件的 size_t），按首次引用的顺序
+pub fn undefined_typedefs(program: &Program) -> Vec<String> {
+    let mut collector = TypedefCollector::default();
+    walk_program(&mut collector, program);
+    let TypedefCollector { defined, used } = collector;
+    used.into_iter()
+        .filter(|name| !defined.contains(name))
+        .collect()
+}
+
+// 收集函数体中定义的标签与 goto 的目标
+#[derive(Debug, Default)]
+struct LabelCollector {
+    labels: Vec<String>,
+    gotos: Vec<String>,
+}
+
+impl Visitor for LabelCollector {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Label(label) => self.labels.push(label.clone()),
+            Stmt::Goto(label) => self.gotos.push(label.clone()),
+            _ => {}
+        }
+        walk_stmt(self, stmt);
+    }
+}
+
+// 检查函数中的 goto：返回没有对应标签的目标名（按出现顺序，去重）。
+// 解析时不检查标签是否存在，因此向后跳转的 goto 可以正常解析
+pub fn validate_labels(func: &Function) -> Vec<String> {
+    let mut collector = LabelCollector::default();
+    walk_stmts(&mut collector, &func.body);
+    let mut dangling: Vec<String> = Vec::new();
+    for target in collector.gotos {
+        if !collector.labels.contains(&target) && !dangling.contains(&target) {
+            dangling.push(target);
+        }
+    }
+    dangling
+}
+
+// 记录当前所处的循环与 switch 嵌套层数，检查 break/continue 是否有合法的目标
+#[derive(Debug, Default)]
+struct LoopControlChecker {
+    loop_depth: usize,
+    switch_depth: usize,
+    errors: Vec<ParseError>,
+}
+
+impl LoopControlChecker {
+    fn report(&mut self, message: &str, pos: SourcePos) {
+        self.errors.push(ParseError {
+            message: message.to_string(),
+            line: pos.line,
+            col: pos.col,
+        });
+    }
+}
+
+impl Visitor for LoopControlChecker {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::While { .. } | Stmt::DoWhile { .. } | Stmt::For { .. } => {
+                self.loop_depth += 1;
+                walk_stmt(self, stmt);
+                self.loop_depth -= 1;
+            }
+            Stmt::Switch { .. } => {
+                self.switch_depth += 1;
+                walk_stmt(self, stmt);
+                self.switch_depth -= 1;
+            }
+            Stmt::Break(pos) if self.loop_depth == 0 && self.switch_depth == 0 => {
+                self.report("'break' statement not in loop or switch statement", *pos);
+            }
+            Stmt::Continue(pos) if self.loop_depth == 0 => {
+                self.report("'continue' statement not in loop statement", *pos);
+            }
+            _ => walk_stmt(self, stmt),
+        }
+    }
+}
+
+// 检查函数中不在循环内的 continue，以及既不在循环也不在 switch 内的 break，
+// 按出现顺序返回，错误位置指向对应的关键字。解析器接受任意位置的 break/continue，这一检查需要单独调用
+pub fn validate_loop_control(func: &Function) -> Vec<ParseError> {
+    let mut checker = LoopControlChecker::default();
+    walk_stmts(&mut checker, &func.body);
+    checker.errors
+}
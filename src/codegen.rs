@@ -1,16 +1,210 @@
 use crate::ast::*;
+use crate::ast_visit::{
+    forward_declared_structs, undefined_typedefs, walk_expr, walk_stmt, walk_stmts, Visitor,
+};
+use std::cell::Cell;
+use std::collections::HashSet;
+
+// 代码生成的目标语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetLang {
+    C,
+    Rust,
+}
+
+// 每层缩进使用的字符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentStyle {
+    Spaces(usize),
+    Tabs,
+}
+
+// C 代码中块的左大括号位置：SameLine 为 K&R 风格，NextLine 为 Allman 风格。
+// Rust 输出始终使用同行大括号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BraceStyle {
+    SameLine,
+    NextLine,
+}
+
+// 可以按生成器的目标语言和当前缩进渲染自身的 AST 节点
+pub trait Emit {
+    fn emit(&self, generator: &mut CodeGenerator) -> String;
+}
+
+impl Emit for Expr {
+    fn emit(&self, generator: &mut CodeGenerator) -> String {
+        generator.generate_expr(self)
+    }
+}
+
+// 语句按生成器当前的缩进输出，包含结尾换行
+impl Emit for Stmt {
+    fn emit(&self, generator: &mut CodeGenerator) -> String {
+        generator.generate_stmt(self)
+    }
+}
+
+impl Emit for CType {
+    fn emit(&self, generator: &mut CodeGenerator) -> String {
+        match generator.target {
+            TargetLang::C => generator.generate_type(self),
+            TargetLang::Rust => generator.generate_rust_type(self),
+        }
+    }
+}
+
+impl Emit for Function {
+    fn emit(&self, generator: &mut CodeGenerator) -> String {
+        generator.generate_function(self)
+    }
+}
+
+impl Emit for Declaration {
+    fn emit(&self, generator: &mut CodeGenerator) -> String {
+        generator.generate_declaration(self)
+    }
+}
+
+impl Emit for Program {
+    fn emit(&self, generator: &mut CodeGenerator) -> String {
+        generator.generate_program(self)
+    }
+}
 
 pub struct CodeGenerator {
     indent: usize,
+    target: TargetLang,
+    // 为匿名结构体/联合体生成占位名时使用的计数器
+    anon_count: Cell<usize>,
+    // 是否把 const char* 参数输出为 &str（需要调用方自行保证是合法 UTF-8）
+    str_params: bool,
+    indent_style: IndentStyle,
+    brace_style: BraceStyle,
+    // 是否为定义之前就被引用的结构体输出 struct Name; 前置声明（仅 C）
+    forward_decls: bool,
+    // 是否在 Rust 输出开头加上 use libc::*; 与未解析类型名的别名
+    rust_prelude: bool,
+    // 生成过程中是否输出了来自 libc 的函数或类型名
+    uses_libc: Cell<bool>,
+    // 最近一条 #pragma pack 设置的对齐字节数，None 表示默认对齐
+    pack: Cell<Option<u32>>,
+    // 当前循环中的 continue 跳转前需要补做的事（仅 Rust）
+    continue_action: Option<ContinueAction>,
+    // 已知为指针类型的变量（全局变量、当前函数的参数与局部变量），条件中改写为 is_null()（仅 Rust）
+    pointer_vars: HashSet<String>,
+    // 当前 switch 翻译为带标签的块时，其中的 break 跳出的标签（仅 Rust）
+    break_label: Option<String>,
+    // 当前循环的标签，没有标签时为 None（仅 Rust）
+    loop_label: Option<String>,
+    // 外层已有的标签数，用于给嵌套的标签取不同的名字（仅 Rust）
+    label_depth: usize,
+}
+
+// Rust 的 continue 直接回到循环开头，翻译后的循环在 continue 之前还要补做 C 语义中的步骤
+#[derive(Debug, Clone)]
+enum ContinueAction {
+    // for 循环翻译为 while：先执行 update
+    Update(Expr),
+    // do-while 翻译为 loop：先检查条件，不满足时退出循环
+    CheckCond(Expr),
+}
+
+impl Default for CodeGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CodeGenerator {
     pub fn new() -> Self {
-        CodeGenerator { indent: 0 }
+        Self::with_target(TargetLang::C)
+    }
+
+    pub fn with_target(target: TargetLang) -> Self {
+        CodeGenerator {
+            indent: 0,
+            target,
+            anon_count: Cell::new(0),
+            str_params: false,
+            indent_style: IndentStyle::Spaces(4),
+            brace_style: BraceStyle::SameLine,
+            forward_decls: false,
+            rust_prelude: false,
+            uses_libc: Cell::new(false),
+            pack: Cell::new(None),
+            continue_action: None,
+            pointer_vars: HashSet::new(),
+            break_label: None,
+            loop_label: None,
+            label_depth: 0,
+        }
+    }
+
+    // 设置起始缩进层级，用于单独输出嵌套位置的语句
+    pub fn with_indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    // 按当前目标语言渲染任意节点
+    pub fn emit<T: Emit + ?Sized>(&mut self, node: &T) -> String {
+        node.emit(self)
+    }
+
+    // 开启后 const char* 参数输出为 &str，而不是原始指针
+    pub fn with_str_params(mut self, enabled: bool) -> Self {
+        self.str_params = enabled;
+        self
+    }
+
+    // 缩进单位，默认 4 个空格
+    pub fn with_indent_style(mut self, style: IndentStyle) -> Self {
+        self.indent_style = style;
+        self
+    }
+
+    // 左大括号位置，默认与语句头同行
+    pub fn with_brace_style(mut self, style: BraceStyle) -> Self {
+        self.brace_style = style;
+        self
+    }
+
+    // 开启后在文件开头（#include 等预处理指令之后）输出结构体与联合体的前置声明
+    pub fn with_forward_decls(mut self, enabled: bool) -> Self {
+        self.forward_decls = enabled;
+        self
+    }
+
+    // 开启后 Rust 输出只在用到 libc 的函数或类型时以 use libc::*; 开头，
+    // 程序中没有定义的其他 typedef 名输出为不透明类型的别名
+    pub fn with_rust_prelude(mut self, enabled: bool) -> Self {
+        self.rust_prelude = enabled;
+        self
+    }
+
+    fn indent_unit(&self) -> String {
+        match self.indent_style {
+            IndentStyle::Spaces(n) => " ".repeat(n),
+            IndentStyle::Tabs => "\t".to_string(),
+        }
     }
 
     fn indent_str(&self) -> String {
-        "    ".repeat(self.indent)
+        self.indent_unit().repeat(self.indent)
+    }
+
+    // C 代码块的左大括号（含换行），接在 if (...)、函数签名等块头之后
+    fn open_brace(&self) -> String {
+        match self.brace_style {
+            BraceStyle::SameLine => " {\n".to_string(),
+            BraceStyle::NextLine => format!("\n{}{{\n", self.indent_str()),
+        }
+    }
+
+    // 当前缩进下以 head 开头的 C 代码块首行
+    fn open_block(&self, head: &str) -> String {
+        format!("{}{}{}", self.indent_str(), head, self.open_brace())
     }
 
     fn generate_type(&self, typ: &CType) -> String {
@@ -19,30 +213,236 @@ impl CodeGenerator {
             CType::Char => "char".to_string(),
             CType::Float => "float".to_string(),
             CType::Double => "double".to_string(),
+            CType::LongDouble => "long double".to_string(),
             CType::Void => "void".to_string(),
+            CType::Bool => "_Bool".to_string(),
             CType::Long => "long".to_string(),
+            CType::LongLong => "long long".to_string(),
             CType::Short => "short".to_string(),
             CType::UnsignedInt => "unsigned int".to_string(),
             CType::UnsignedChar => "unsigned char".to_string(),
             CType::UnsignedLong => "unsigned long".to_string(),
+            CType::UnsignedLongLong => "unsigned long long".to_string(),
             CType::UnsignedShort => "unsigned short".to_string(),
             CType::SignedInt => "signed int".to_string(),
             CType::SignedChar => "signed char".to_string(),
-            CType::Pointer(inner) => format!("{}*", self.generate_type(inner)),
-            CType::Array { element_type, size } => {
-                if let Some(s) = size {
-                    format!("{}[{}]", self.generate_type(element_type), s)
-                } else {
-                    format!("{}[]", self.generate_type(element_type))
-                }
+            // 指向数组或函数的指针需要括号，交给声明符输出
+            CType::Pointer(inner)
+                if matches!(**inner, CType::Array { .. } | CType::Function { .. }) =>
+            {
+                self.generate_declarator(typ, "")
             }
+            CType::Pointer(inner) => format!("{}*", self.generate_type(inner)),
+            CType::Array {
+                element_type,
+                size,
+                size_expr,
+            } => format!(
+                "{}[{}]",
+                self.generate_type(element_type),
+                self.generate_array_size(*size, size_expr)
+            ),
             CType::Struct(name) => format!("struct {}", name),
             CType::Union(name) => format!("union {}", name),
             CType::Enum(name) => format!("enum {}", name),
             CType::Typedef(name) => name.clone(),
+            CType::StructDef(def) => self.generate_inline_fields("struct", &def.name, &def.fields),
+            CType::UnionDef(def) => self.generate_inline_fields("union", &def.name, &def.fields),
+            CType::EnumDef(def) => self.generate_inline_enum(def),
+            CType::TypeOf(expr) => format!("typeof({})", self.generate_full_expr(expr)),
+            // 修饰指针本身的限定符写在 '*' 之后，交给声明符输出
+            CType::Const(inner) | CType::Volatile(inner) | CType::Restrict(inner)
+                if matches!(**inner, CType::Pointer(_)) =>
+            {
+                self.generate_declarator(typ, "")
+            }
             CType::Const(inner) => format!("const {}", self.generate_type(inner)),
             CType::Volatile(inner) => format!("volatile {}", self.generate_type(inner)),
-            CType::Function { .. } => "/* function pointer */".to_string(),
+            CType::Restrict(inner) => format!("restrict {}", self.generate_type(inner)),
+            CType::Function { .. } => self.generate_declarator(typ, ""),
+        }
+    }
+
+    // 数组大小优先按源码中的表达式输出，如 [SIZE]
+    fn generate_array_size(&self, size: Option<usize>, size_expr: &Option<Box<Expr>>) -> String {
+        match (size_expr, size) {
+            (Some(expr), _) => self.generate_full_expr(expr),
+            (None, Some(n)) => n.to_string(),
+            (None, None) => String::new(),
+        }
+    }
+
+    // 把类型拆成 (基础类型, 声明符)，如 int (*fp)(int) 拆成 ("int", "(*fp)(int)")。
+    // 类型从外到内展开，每一层把声明符包在名字外侧；
+    // 指针之后再接数组或函数后缀时需要加括号
+    fn split_declarator(&self, typ: &CType, name: &str) -> (String, String) {
+        let mut decl = name.to_string();
+        let mut ty = typ;
+        loop {
+            match ty {
+                CType::Pointer(inner) => {
+                    decl = format!("*{}", decl);
+                    ty = inner;
+                }
+                // 指针自身的限定符，如 char *const p
+                CType::Const(inner) | CType::Volatile(inner) | CType::Restrict(inner)
+                    if matches!(**inner, CType::Pointer(_)) =>
+                {
+                    let qualifier = match ty {
+                        CType::Const(_) => "const",
+                        CType::Volatile(_) => "volatile",
+                        _ => "restrict",
+                    };
+                    decl = if decl.is_empty() {
+                        qualifier.to_string()
+                    } else {
+                        format!("{} {}", qualifier, decl)
+                    };
+                    ty = inner;
+                }
+                CType::Array {
+                    element_type,
+                    size,
+                    size_expr,
+                } => {
+                    if decl.starts_with('*') {
+                        decl = format!("({})", decl);
+                    }
+                    decl.push_str(&format!("[{}]", self.generate_array_size(*size, size_expr)));
+                    ty = element_type;
+                }
+                CType::Function {
+                    return_type,
+                    params,
+                    variadic,
+                } => {
+                    if decl.starts_with('*') {
+                        decl = format!("({})", decl);
+                    }
+                    let params_str = join_params(
+                        params
+                            .iter()
+                            .map(|p| self.generate_declarator(p, ""))
+                            .collect(),
+                        variadic.then_some("..."),
+                    );
+                    decl.push_str(&format!("({})", params_str));
+                    ty = return_type;
+                }
+                _ => break,
+            }
+        }
+
+        (self.generate_type(ty), decl)
+    }
+
+    // 按 C 的声明语法输出 "类型 声明符"，如 int *p、int a[3][4]、int (*fp)(int)
+    fn generate_declarator(&self, typ: &CType, name: &str) -> String {
+        let (base, decl) = self.split_declarator(typ, name);
+        if decl.is_empty() {
+            base
+        } else if name.is_empty() && decl.chars().all(|c| c == '*') {
+            // 抽象声明符，如参数类型 int*
+            format!("{}{}", base, decl)
+        } else {
+            format!("{} {}", base, decl)
+        }
+    }
+
+    // 将 C 类型映射为对应的 Rust 类型
+    pub fn generate_rust_type(&self, typ: &CType) -> String {
+        match typ {
+            CType::Int | CType::SignedInt => "i32".to_string(),
+            CType::Char | CType::SignedChar => "i8".to_string(),
+            CType::Float => "f32".to_string(),
+            CType::Double => "f64".to_string(),
+            // 稳定版 Rust 没有 f128，long double 退化为 f64（会丢失精度）
+            CType::LongDouble => "f64".to_string(),
+            CType::Void => "()".to_string(),
+            CType::Bool => "bool".to_string(),
+            CType::Long | CType::LongLong => "i64".to_string(),
+            CType::Short => "i16".to_string(),
+            CType::UnsignedInt => "u32".to_string(),
+            CType::UnsignedChar => "u8".to_string(),
+            CType::UnsignedLong | CType::UnsignedLongLong => "u64".to_string(),
+            CType::UnsignedShort => "u16".to_string(),
+            // 指向 const 的指针对应 *const T，其余为 *mut T；指针自身的 const 在下面的
+            // Const 分支中丢弃（Rust 用 let / let mut 区分）
+            CType::Pointer(inner) => {
+                let (is_const, target) = strip_qualifiers(inner);
+                let mutability = if is_const { "const" } else { "mut" };
+                match target {
+                    // void* 没有对应的 Rust 指针目标类型，使用 c_void
+                    CType::Void => format!("*{} std::ffi::c_void", mutability),
+                    // 函数指针本身就是 Rust 的 fn 类型
+                    CType::Function { .. } => self.generate_rust_type(target),
+                    _ => format!("*{} {}", mutability, self.generate_rust_type(target)),
+                }
+            }
+            CType::Array {
+                element_type,
+                size,
+                size_expr,
+            } => match (size, size_expr) {
+                (Some(n), _) => format!("[{}; {}]", self.generate_rust_type(element_type), n),
+                // 无法在解析时求值的大小交给 Rust 的常量求值
+                (None, Some(expr)) => format!(
+                    "[{}; {} as usize]",
+                    self.generate_rust_type(element_type),
+                    self.generate_rust_expr(expr)
+                ),
+                // 未指定大小的数组退化为指针
+                (None, None) => format!("*mut {}", self.generate_rust_type(element_type)),
+            },
+            CType::Typedef(name) => {
+                if LIBC_TYPES.contains(&name.as_str()) {
+                    self.uses_libc.set(true);
+                }
+                name.clone()
+            }
+            CType::Struct(name) | CType::Union(name) | CType::Enum(name) => name.clone(),
+            // Rust 不支持内联定义，只引用类型名，定义本身由 typedef 等处单独输出
+            CType::StructDef(def) => self.rust_type_name(&def.name, "AnonStruct"),
+            CType::UnionDef(def) => self.rust_type_name(&def.name, "AnonUnion"),
+            CType::EnumDef(def) => self.rust_type_name(&def.name, "AnonEnum"),
+            // 只在表达式类型一目了然时（字面量、类型转换、sizeof）给出具体类型，否则交给 Rust 推导
+            CType::TypeOf(expr) => match obvious_type(expr) {
+                Some(typ) => self.generate_rust_type(&typ),
+                None => "_".to_string(),
+            },
+            CType::Const(inner) | CType::Volatile(inner) | CType::Restrict(inner) => {
+                self.generate_rust_type(inner)
+            }
+            CType::Function {
+                return_type,
+                params,
+                variadic,
+            } => {
+                let params: &[CType] = match params.as_slice() {
+                    [CType::Void] => &[],
+                    params => params,
+                };
+                let params_str = join_params(
+                    params.iter().map(|p| self.generate_rust_type(p)).collect(),
+                    variadic.then_some("..."),
+                );
+                // 可变参数只能出现在 extern "C" 函数指针类型中
+                let fn_kw = if *variadic {
+                    "unsafe extern \"C\" fn"
+                } else {
+                    "fn"
+                };
+                if **return_type == CType::Void {
+                    format!("{}({})", fn_kw, params_str)
+                } else {
+                    format!(
+                        "{}({}) -> {}",
+                        fn_kw,
+                        params_str,
+                        self.generate_rust_type(return_type)
+                    )
+                }
+            }
         }
     }
 
@@ -82,6 +482,7 @@ impl CodeGenerator {
     fn generate_unary_op(&self, op: &UnaryOp) -> &str {
         match op {
             UnaryOp::Neg => "-",
+            UnaryOp::Plus => "+",
             UnaryOp::Not => "!",
             UnaryOp::BitNot => "~",
             UnaryOp::Deref => "*",
@@ -93,73 +494,211 @@ impl CodeGenerator {
         }
     }
 
-    fn generate_expr(&self, expr: &Expr) -> String {
+    // 超出 long long 范围的常量在 C 中只能以十六进制形式表示为无符号数
+    fn generate_int(&self, n: i128) -> String {
+        if n > i64::MAX as i128 {
+            format!("0x{:X}", n)
+        } else {
+            n.to_string()
+        }
+    }
+
+    // 保证浮点常量带有小数点或指数，避免 2.0 被输出成整数 2
+    fn generate_float(&self, f: f64) -> String {
+        format!("{:?}", f)
+    }
+
+    fn generate_init_list(&self, items: &[InitItem]) -> String {
+        let items_str = items
+            .iter()
+            .map(|item| {
+                if item.designators.is_empty() {
+                    return self.generate_expr(&item.value);
+                }
+                let mut designators = String::new();
+                for designator in &item.designators {
+                    match designator {
+                        Designator::Field(name) => {
+                            designators.push('.');
+                            designators.push_str(name);
+                        }
+                        Designator::Index(index) => {
+                            designators.push_str(&format!("[{}]", self.generate_expr(index)))
+                        }
+                        Designator::Range(lo, hi) => designators.push_str(&format!(
+                            "[{} ... {}]",
+                            self.generate_expr(lo),
+                            self.generate_expr(hi)
+                        )),
+                    }
+                }
+                format!("{} = {}", designators, self.generate_expr(&item.value))
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{{{}}}", items_str)
+    }
+
+    // 语句表达式的块内内容，各语句输出在同一行；Rust 中最后一条表达式语句不加分号，作为块的值
+    fn generate_stmt_expr_body(&self, stmts: &[Stmt]) -> String {
+        let mut inner = CodeGenerator {
+            indent: 0,
+            target: self.target,
+            anon_count: Cell::new(self.anon_count.get()),
+            str_params: self.str_params,
+            indent_style: self.indent_style,
+            brace_style: self.brace_style,
+            forward_decls: self.forward_decls,
+            rust_prelude: self.rust_prelude,
+            uses_libc: Cell::new(false),
+            pack: Cell::new(self.pack.get()),
+            continue_action: self.continue_action.clone(),
+            pointer_vars: self.pointer_vars.clone(),
+            break_label: self.break_label.clone(),
+            loop_label: self.loop_label.clone(),
+            label_depth: self.label_depth,
+        };
+        let mut parts = Vec::new();
+        for (i, stmt) in stmts.iter().enumerate() {
+            match stmt {
+                Stmt::Expr(expr) if self.target == TargetLang::Rust && i + 1 == stmts.len() => {
+                    parts.push(self.generate_rust_expr(expr))
+                }
+                _ => parts.push(inner.generate_stmt(stmt).trim().to_string()),
+            }
+        }
+        self.anon_count.set(inner.anon_count.get());
+        if inner.uses_libc.get() {
+            self.uses_libc.set(true);
+        }
+        parts.join(" ")
+    }
+
+    pub fn generate_expr(&self, expr: &Expr) -> String {
+        if self.target == TargetLang::Rust {
+            return self.generate_rust_expr(expr);
+        }
+        // 顶层按赋值表达式输出，逗号表达式出现在初始化、参数等位置时需要括号
+        self.generate_operand(expr, PREC_ASSIGN)
+    }
+
+    // 输出语句中的完整表达式（表达式语句、条件、return 等），逗号表达式无需括号
+    fn generate_full_expr(&self, expr: &Expr) -> String {
+        if self.target == TargetLang::Rust {
+            return self.generate_rust_expr(expr);
+        }
+        self.generate_operand(expr, PREC_COMMA)
+    }
+
+    // 输出子表达式，其优先级低于所在位置要求的 min_prec 时加括号
+    fn generate_operand(&self, expr: &Expr, min_prec: u8) -> String {
+        let code = self.generate_c_expr(expr);
+        if c_precedence(expr) < min_prec {
+            format!("({})", code)
+        } else {
+            code
+        }
+    }
+
+    fn generate_c_expr(&self, expr: &Expr) -> String {
         match expr {
-            Expr::IntLiteral(n) => n.to_string(),
-            Expr::FloatLiteral(f) => f.to_string(),
-            Expr::CharLiteral(c) => format!("'{}'", c),
-            Expr::StringLiteral(s) => format!("\"{}\"", s),
+            Expr::IntLiteral(n) => self.generate_int(*n),
+            Expr::IntLiteralSuffixed {
+                value,
+                unsigned,
+                long_count,
+            } => {
+                let mut result = self.generate_int(*value);
+                if *unsigned {
+                    result.push('U');
+                }
+                result.push_str(&"L".repeat(*long_count as usize));
+                result
+            }
+            Expr::FloatLiteral(f) => self.generate_float(*f),
+            Expr::FloatLiteralSuffixed { value, suffix } => {
+                let suffix = if *suffix == 'l' { 'L' } else { *suffix };
+                format!("{}{}", self.generate_float(*value), suffix)
+            }
+            Expr::CharLiteral(c) => format!("'{}'", escape_c_string(&c.to_string(), '\'')),
+            Expr::StringLiteral(s) => format!("\"{}\"", escape_c_string(s, '"')),
             Expr::Identifier(name) => name.clone(),
             Expr::Binary { op, left, right } => {
+                // 二元运算符都是左结合的：右操作数优先级相同时也要加括号
+                let prec = binary_precedence(op);
                 format!(
-                    "({} {} {})",
-                    self.generate_expr(left),
+                    "{} {} {}",
+                    self.generate_operand(left, prec),
                     self.generate_binary_op(op),
-                    self.generate_expr(right)
+                    self.generate_operand(right, prec + 1)
                 )
             }
             Expr::Unary { op, operand } => {
                 // 处理前缀和后缀运算符
                 match op {
                     UnaryOp::PostIncrement => {
-                        format!("({}++)", self.generate_expr(operand))
+                        format!("{}++", self.generate_operand(operand, PREC_POSTFIX))
                     }
                     UnaryOp::PostDecrement => {
-                        format!("({}--)", self.generate_expr(operand))
-                    }
-                    _ => {
-                        format!(
-                            "({}{})",
-                            self.generate_unary_op(op),
-                            self.generate_expr(operand)
-                        )
+                        format!("{}--", self.generate_operand(operand, PREC_POSTFIX))
                     }
+                    _ => join_prefix_op(
+                        self.generate_unary_op(op),
+                        &self.generate_operand(operand, PREC_UNARY),
+                    ),
                 }
             }
             Expr::Call { func, args } => {
                 let args_str = args
                     .iter()
-                    .map(|arg| self.generate_expr(arg))
+                    .map(|arg| self.generate_operand(arg, PREC_ASSIGN))
                     .collect::<Vec<_>>()
                     .join(", ");
                 format!("{}({})", func, args_str)
             }
+            Expr::CallExpr { callee, args } => {
+                let args_str = args
+                    .iter()
+                    .map(|arg| self.generate_operand(arg, PREC_ASSIGN))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!(
+                    "{}({})",
+                    self.generate_operand(callee, PREC_POSTFIX),
+                    args_str
+                )
+            }
             Expr::Assignment { target, value } => {
+                // 赋值是右结合的
                 format!(
                     "{} = {}",
-                    self.generate_expr(target),
-                    self.generate_expr(value)
+                    self.generate_operand(target, PREC_UNARY),
+                    self.generate_operand(value, PREC_ASSIGN)
                 )
             }
             Expr::Cast { typ, expr } => {
                 format!(
-                    "(({}){})",
+                    "({}){}",
                     self.generate_type(typ),
-                    self.generate_expr(expr)
+                    self.generate_operand(expr, PREC_UNARY)
                 )
             }
             Expr::ArrayAccess { array, index } => {
                 format!(
                     "{}[{}]",
-                    self.generate_expr(array),
-                    self.generate_expr(index)
+                    self.generate_operand(array, PREC_POSTFIX),
+                    self.generate_operand(index, PREC_COMMA)
                 )
             }
             Expr::MemberAccess { object, member } => {
-                format!("{}.{}", self.generate_expr(object), member)
+                format!("{}.{}", self.generate_operand(object, PREC_POSTFIX), member)
             }
             Expr::PointerMemberAccess { object, member } => {
-                format!("{}->{}", self.generate_expr(object), member)
+                format!(
+                    "{}->{}",
+                    self.generate_operand(object, PREC_POSTFIX),
+                    member
+                )
             }
             Expr::Ternary {
                 cond,
@@ -167,38 +706,61 @@ impl CodeGenerator {
                 else_expr,
             } => {
                 format!(
-                    "({} ? {} : {})",
-                    self.generate_expr(cond),
-                    self.generate_expr(then_expr),
-                    self.generate_expr(else_expr)
+                    "{} ? {} : {}",
+                    self.generate_operand(cond, PREC_TERNARY + 1),
+                    self.generate_operand(then_expr, PREC_COMMA),
+                    self.generate_operand(else_expr, PREC_TERNARY)
                 )
             }
             Expr::SizeOf(typ) => {
                 format!("sizeof({})", self.generate_type(typ))
             }
+            Expr::SizeOfExpr(expr) => {
+                format!("sizeof({})", self.generate_operand(expr, PREC_COMMA))
+            }
+            Expr::StmtExpr(stmts) => format!("({{ {} }})", self.generate_stmt_expr_body(stmts)),
+            Expr::Comma(exprs) => exprs
+                .iter()
+                .map(|e| self.generate_operand(e, PREC_ASSIGN))
+                .collect::<Vec<_>>()
+                .join(", "),
+            Expr::InitList(items) => self.generate_init_list(items),
+            Expr::CompoundLiteral { typ, init } => {
+                format!(
+                    "({}){}",
+                    self.generate_type(typ),
+                    self.generate_init_list(init)
+                )
+            }
             Expr::Null => "NULL".to_string(),
         }
     }
 
+    // 输出 if/while/for 等语句的主体，缩进加一层
+    fn generate_c_block(&mut self, stmts: &[Stmt]) -> String {
+        let mut result = String::new();
+        self.indent += 1;
+        for stmt in unwrap_block(stmts) {
+            result.push_str(&self.generate_stmt(stmt));
+        }
+        self.indent -= 1;
+        result
+    }
+
     fn generate_stmt(&mut self, stmt: &Stmt) -> String {
+        if self.target == TargetLang::Rust {
+            return self.generate_rust_stmt(stmt);
+        }
         match stmt {
-            Stmt::VarDecl { typ, name, init } => {
-                let mut result = format!("{}", self.indent_str());
-
-                // 特殊处理数组类型的声明
-                match typ {
-                    CType::Array { element_type, size } => {
-                        result.push_str(&format!("{} {}", self.generate_type(element_type), name));
-                        if let Some(s) = size {
-                            result.push_str(&format!("[{}]", s));
-                        } else {
-                            result.push_str("[]");
-                        }
-                    }
-                    _ => {
-                        result.push_str(&format!("{} {}", self.generate_type(typ), name));
-                    }
-                }
+            Stmt::VarDecl {
+                storage,
+                typ,
+                name,
+                init,
+            } => {
+                let mut result = self.indent_str();
+                result.push_str(storage_prefix(storage));
+                result.push_str(&self.generate_declarator(typ, name));
 
                 if let Some(expr) = init {
                     result.push_str(&format!(" = {}", self.generate_expr(expr)));
@@ -209,54 +771,51 @@ impl CodeGenerator {
             Stmt::Return(expr) => {
                 let mut result = format!("{}return", self.indent_str());
                 if let Some(e) = expr {
-                    result.push_str(&format!(" {}", self.generate_expr(e)));
+                    result.push_str(&format!(" {}", self.generate_full_expr(e)));
                 }
                 result.push_str(";\n");
                 result
             }
             Stmt::Expr(expr) => {
-                format!("{}{};\n", self.indent_str(), self.generate_expr(expr))
+                format!("{}{};\n", self.indent_str(), self.generate_full_expr(expr))
             }
             Stmt::If {
                 cond,
                 then_block,
                 else_block,
             } => {
-                let mut result = format!(
-                    "{}if ({}) {{\n",
-                    self.indent_str(),
-                    self.generate_expr(cond)
-                );
-                self.indent += 1;
-                for stmt in then_block {
-                    result.push_str(&self.generate_stmt(stmt));
-                }
-                self.indent -= 1;
+                let mut result =
+                    self.open_block(&format!("if ({})", self.generate_full_expr(cond)));
+                result.push_str(&self.generate_c_block(then_block));
                 result.push_str(&format!("{}}}", self.indent_str()));
 
-                if let Some(else_stmts) = else_block {
-                    result.push_str(" else {\n");
-                    self.indent += 1;
-                    for stmt in else_stmts {
-                        result.push_str(&self.generate_stmt(stmt));
+                // Allman 风格中 else 另起一行
+                let else_kw = match self.brace_style {
+                    BraceStyle::SameLine => " else".to_string(),
+                    BraceStyle::NextLine => format!("\n{}else", self.indent_str()),
+                };
+                match else_block.as_deref() {
+                    // else 中只有一个 if 时输出为 else if 链
+                    Some([else_if @ Stmt::If { .. }]) => {
+                        result.push_str(&else_kw);
+                        result.push(' ');
+                        result.push_str(self.generate_stmt(else_if).trim());
                     }
-                    self.indent -= 1;
-                    result.push_str(&format!("{}}}", self.indent_str()));
+                    Some(else_stmts) => {
+                        result.push_str(&else_kw);
+                        result.push_str(&self.open_brace());
+                        result.push_str(&self.generate_c_block(else_stmts));
+                        result.push_str(&format!("{}}}", self.indent_str()));
+                    }
+                    None => {}
                 }
                 result.push('\n');
                 result
             }
             Stmt::While { cond, body } => {
-                let mut result = format!(
-                    "{}while ({}) {{\n",
-                    self.indent_str(),
-                    self.generate_expr(cond)
-                );
-                self.indent += 1;
-                for stmt in body {
-                    result.push_str(&self.generate_stmt(stmt));
-                }
-                self.indent -= 1;
+                let mut result =
+                    self.open_block(&format!("while ({})", self.generate_full_expr(cond)));
+                result.push_str(&self.generate_c_block(body));
                 result.push_str(&format!("{}}}\n", self.indent_str()));
                 result
             }
@@ -269,9 +828,9 @@ impl CodeGenerator {
                 let mut result = format!("{}for (", self.indent_str());
 
                 if let Some(init_stmt) = init {
-                    // 特殊处理 init 语句，移除缩进和换行
+                    // 特殊处理 init 语句，移除缩进和换行，保留结尾的分号
                     let init_str = self.generate_stmt(init_stmt).trim().to_string();
-                    result.push_str(&init_str.trim_end_matches(';').to_string());
+                    result.push_str(&init_str);
                 } else {
                     result.push(';');
                 }
@@ -279,20 +838,17 @@ impl CodeGenerator {
                 result.push(' ');
 
                 if let Some(cond_expr) = cond {
-                    result.push_str(&self.generate_expr(cond_expr));
+                    result.push_str(&self.generate_full_expr(cond_expr));
                 }
                 result.push_str("; ");
 
                 if let Some(update_expr) = update {
-                    result.push_str(&self.generate_expr(update_expr));
+                    result.push_str(&self.generate_full_expr(update_expr));
                 }
 
-                result.push_str(") {\n");
-                self.indent += 1;
-                for stmt in body {
-                    result.push_str(&self.generate_stmt(stmt));
-                }
-                self.indent -= 1;
+                result.push(')');
+                result.push_str(&self.open_brace());
+                result.push_str(&self.generate_c_block(body));
                 result.push_str(&format!("{}}}\n", self.indent_str()));
                 result
             }
@@ -306,34 +862,63 @@ impl CodeGenerator {
                 result.push_str(&format!("{}}}\n", self.indent_str()));
                 result
             }
-            Stmt::DoWhile { body, cond } => {
-                let mut result = format!("{}do {{\n", self.indent_str());
-                self.indent += 1;
-                for stmt in body {
-                    result.push_str(&self.generate_stmt(stmt));
+            // 共享基础类型，按一条声明输出：int i = 0, *p;
+            Stmt::DeclList(decls) => {
+                let mut base = String::new();
+                let mut prefix = "";
+                let mut declarators = Vec::new();
+                for decl in decls {
+                    if let Stmt::VarDecl {
+                        storage,
+                        typ,
+                        name,
+                        init,
+                    } = decl
+                    {
+                        let (decl_base, mut declarator) = self.split_declarator(typ, name);
+                        if let Some(expr) = init {
+                            declarator.push_str(&format!(" = {}", self.generate_expr(expr)));
+                        }
+                        if declarators.is_empty() {
+                            base = decl_base;
+                            prefix = storage_prefix(storage);
+                        }
+                        declarators.push(declarator);
+                    }
                 }
-                self.indent -= 1;
+                format!(
+                    "{}{}{} {};\n",
+                    self.indent_str(),
+                    prefix,
+                    base,
+                    declarators.join(", ")
+                )
+            }
+            Stmt::DoWhile { body, cond } => {
+                let mut result = self.open_block("do");
+                result.push_str(&self.generate_c_block(body));
                 result.push_str(&format!(
                     "{}}} while ({});\n",
                     self.indent_str(),
-                    self.generate_expr(cond)
+                    self.generate_full_expr(cond)
                 ));
                 result
             }
             Stmt::Switch { expr, cases } => {
-                let mut result = format!(
-                    "{}switch ({}) {{\n",
-                    self.indent_str(),
-                    self.generate_expr(expr)
-                );
+                let mut result =
+                    self.open_block(&format!("switch ({})", self.generate_full_expr(expr)));
                 self.indent += 1;
                 for case in cases {
                     if let Some(value) = &case.value {
-                        result.push_str(&format!(
-                            "{}case {}:\n",
-                            self.indent_str(),
-                            self.generate_expr(value)
-                        ));
+                        let label = match &case.range_end {
+                            Some(end) => format!(
+                                "{} ... {}",
+                                self.generate_expr(value),
+                                self.generate_expr(end)
+                            ),
+                            None => self.generate_expr(value),
+                        };
+                        result.push_str(&format!("{}case {}:\n", self.indent_str(), label));
                     } else {
                         result.push_str(&format!("{}default:\n", self.indent_str()));
                     }
@@ -347,26 +932,45 @@ impl CodeGenerator {
                 result.push_str(&format!("{}}}\n", self.indent_str()));
                 result
             }
-            Stmt::Break => format!("{}break;\n", self.indent_str()),
-            Stmt::Continue => format!("{}continue;\n", self.indent_str()),
+            Stmt::Break(_) => format!("{}break;\n", self.indent_str()),
+            Stmt::Continue(_) => format!("{}continue;\n", self.indent_str()),
             Stmt::Goto(label) => format!("{}goto {};\n", self.indent_str(), label),
             Stmt::Label(label) => format!("{}{}:\n", self.indent_str(), label),
-            Stmt::Empty => ";\n".to_string(),
+            Stmt::Empty => format!("{};\n", self.indent_str()),
+            Stmt::InlineAsm(text) => format!("{}{};\n", self.indent_str(), text),
+            Stmt::Comment(text) => format!("{}{}\n", self.indent_str(), text),
         }
     }
 
     pub fn generate_function(&mut self, func: &Function) -> String {
-        let mut result = format!("{} {}(", self.generate_type(&func.return_type), func.name);
-
-        let params_str = func
-            .params
-            .iter()
-            .map(|p| format!("{} {}", self.generate_type(&p.typ), p.name))
-            .collect::<Vec<_>>()
-            .join(", ");
-
-        result.push_str(&params_str);
-        result.push_str(") {\n");
+        if self.target == TargetLang::Rust {
+            return self.generate_rust_function(func);
+        }
+        let params_str = if func.explicit_void {
+            "void".to_string()
+        } else {
+            join_params(
+                func.params
+                    .iter()
+                    .map(|p| self.generate_declarator(&p.typ, &p.name))
+                    .collect(),
+                func.variadic.then_some("..."),
+            )
+        };
+        let signature = format!("{}({})", func.name, params_str);
+        // 返回函数指针或数组指针时，函数名和参数列表要写在返回类型的声明符内部，
+        // 如 int (*pick(int n))(int)；其余情况 '*' 跟在返回类型后面，如 int* get(void)
+        let signature = if returns_nested_declarator(&func.return_type) {
+            self.generate_declarator(&func.return_type, &signature)
+        } else {
+            format!("{} {}", self.generate_type(&func.return_type), signature)
+        };
+        let mut result = self.open_block(&format!(
+            "{}{}{}",
+            storage_prefix(&func.storage),
+            if func.is_inline { "inline " } else { "" },
+            signature
+        ));
 
         self.indent += 1;
         for stmt in &func.body {
@@ -378,104 +982,1971 @@ impl CodeGenerator {
         result
     }
 
+    // 输出单个成员（不含缩进和换行），如 int x; 或 unsigned int flag : 1;
+    fn generate_field(&self, field: &StructField) -> String {
+        // 数组和函数指针成员的名字要写在声明符内部，如 int matrix[4][3]; int (*cb)(int);
+        let mut result =
+            if matches!(field.typ, CType::Array { .. }) || returns_nested_declarator(&field.typ) {
+                self.generate_declarator(&field.typ, &field.name)
+            } else {
+                let mut result = self.generate_type(&field.typ);
+                if !field.name.is_empty() {
+                    result.push(' ');
+                    result.push_str(&field.name);
+                }
+                result
+            };
+        if let Some(width) = field.bit_width {
+            result.push_str(&format!(" : {}", width));
+        }
+        result.push(';');
+        result
+    }
+
+    // 类型内联定义时输出在一行内，如 struct Point { int x; int y; }
+    fn generate_inline_fields(&self, keyword: &str, name: &str, fields: &[StructField]) -> String {
+        let mut result = keyword.to_string();
+        if !name.is_empty() {
+            result.push(' ');
+            result.push_str(name);
+        }
+        result.push_str(" {");
+        for field in fields {
+            result.push(' ');
+            result.push_str(&self.generate_field(field));
+        }
+        result.push_str(" }");
+        result
+    }
+
+    fn generate_inline_enum(&self, enum_def: &EnumDef) -> String {
+        let mut result = "enum".to_string();
+        if !enum_def.name.is_empty() {
+            result.push(' ');
+            result.push_str(&enum_def.name);
+        }
+        let variants = enum_def
+            .variants
+            .iter()
+            .map(|variant| match &variant.value {
+                Some(value) => format!("{} = {}", variant.name, self.generate_expr(value)),
+                None => variant.name.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        result.push_str(&format!(" {{ {} }}", variants));
+        result
+    }
+
     pub fn generate_struct(&self, struct_def: &StructDef) -> String {
-        let mut result = format!("struct {} {{\n", struct_def.name);
+        if self.target == TargetLang::Rust {
+            return self.generate_rust_struct(struct_def);
+        }
+        let mut result = self.open_block(&format!("struct {}", struct_def.name));
         for field in &struct_def.fields {
             result.push_str(&format!(
-                "    {} {};\n",
-                self.generate_type(&field.typ),
-                field.name
+                "{}{}\n",
+                self.indent_unit(),
+                self.generate_field(field)
             ));
         }
-        result.push_str("}");
+        result.push('}');
         result
     }
 
     pub fn generate_union(&self, union_def: &UnionDef) -> String {
-        let mut result = format!("union {} {{\n", union_def.name);
+        if self.target == TargetLang::Rust {
+            return self.generate_rust_union(union_def);
+        }
+        let mut result = self.open_block(&format!("union {}", union_def.name));
         for field in &union_def.fields {
             result.push_str(&format!(
-                "    {} {};\n",
-                self.generate_type(&field.typ),
-                field.name
+                "{}{}\n",
+                self.indent_unit(),
+                self.generate_field(field)
             ));
         }
-        result.push_str("}");
+        result.push('}');
         result
     }
 
     pub fn generate_enum(&self, enum_def: &EnumDef) -> String {
-        let mut result = format!("enum {} {{\n", enum_def.name);
+        if self.target == TargetLang::Rust {
+            return self.generate_rust_enum(enum_def);
+        }
+        let mut result = self.open_block(&format!("enum {}", enum_def.name));
         for (i, variant) in enum_def.variants.iter().enumerate() {
-            result.push_str("    ");
+            result.push_str(&self.indent_unit());
             result.push_str(&variant.name);
-            if let Some(value) = variant.value {
-                result.push_str(&format!(" = {}", value));
+            if let Some(value) = &variant.value {
+                result.push_str(&format!(" = {}", self.generate_expr(value)));
             }
             if i < enum_def.variants.len() - 1 {
                 result.push(',');
             }
             result.push('\n');
         }
-        result.push_str("}");
+        result.push('}');
         result
     }
 
     pub fn generate_typedef(&self, typedef_def: &TypedefDef) -> String {
+        if self.target == TargetLang::Rust {
+            return self.generate_rust_typedef(typedef_def);
+        }
         format!(
-            "typedef {} {};",
-            self.generate_type(&typedef_def.target_type),
-            typedef_def.name
+            "typedef {};",
+            self.generate_declarator(&typedef_def.target_type, &typedef_def.name)
         )
     }
 
     pub fn generate_program(&mut self, program: &Program) -> String {
-        let mut result = String::new();
-
-        for decl in &program.declarations {
-            match decl {
-                Declaration::Function(func) => {
-                    // 只生成有函数体的函数
-                    if !func.body.is_empty() {
-                        result.push_str(&self.generate_function(func));
-                        result.push('\n');
-                    }
-                }
-                Declaration::Struct(struct_def) => {
-                    result.push_str(&self.generate_struct(struct_def));
-                    result.push_str(";\n\n");
-                }
-                Declaration::Union(union_def) => {
-                    result.push_str(&self.generate_union(union_def));
-                    result.push_str(";\n\n");
-                }
-                Declaration::Enum(enum_def) => {
-                    result.push_str(&self.generate_enum(enum_def));
-                    result.push_str(";\n\n");
-                }
-                Declaration::Typedef(typedef_def) => {
-                    result.push_str(&self.generate_typedef(typedef_def));
-                    result.push_str("\n\n");
-                }
-                Declaration::GlobalVar { typ, name, init } => {
-                    result.push_str(&self.generate_type(typ));
-                    result.push(' ');
-                    result.push_str(name);
-                    if let Some(expr) = init {
-                        result.push_str(" = ");
-                        result.push_str(&self.generate_expr(expr));
-                    }
-                    result.push_str(";\n\n");
-                }
-                Declaration::Include(path) => {
-                    result.push_str(&format!("#include {}\n", path));
-                }
-                Declaration::Define { name, value } => {
-                    result.push_str(&format!("#define {} {}\n", name, value));
+        self.pack.set(None);
+        if self.target == TargetLang::Rust && self.rust_prelude {
+            self.uses_libc.set(false);
+            let body: String = program
+                .declarations
+                .iter()
+                .map(|decl| decl.emit(self))
+                .collect();
+            let mut prelude = String::new();
+            if self.uses_libc.get() {
+                prelude.push_str("use libc::*;\n");
+            }
+            for name in undefined_typedefs(program) {
+                if !LIBC_TYPES.contains(&name.as_str()) {
+                    prelude.push_str(&format!("pub type {} = std::ffi::c_void;\n", name));
                 }
             }
+            if !prelude.is_empty() {
+                prelude.push('\n');
+            }
+            return prelude + &body;
         }
-
+        let forward = if self.forward_decls && self.target == TargetLang::C {
+            forward_declared_structs(program)
+        } else {
+            Vec::new()
+        };
+        // 前置声明放在开头的预处理指令之后；紧贴在第一个声明前的注释仍与该声明相邻
+        let split = program
+            .declarations
+            .iter()
+            .take_while(|decl| {
+                matches!(
+                    decl,
+                    Declaration::Include(_)
+                        | Declaration::Define { .. }
+                        | Declaration::Pragma(_)
+                        | Declaration::Comment(_)
+                )
+            })
+            .enumerate()
+            .filter(|(_, decl)| !matches!(decl, Declaration::Comment(_)))
+            .map(|(i, _)| i + 1)
+            .last()
+            .unwrap_or(0);
+        let (head, rest) = program.declarations.split_at(split);
+        let mut result: String = head.iter().map(|decl| decl.emit(self)).collect();
+        if !forward.is_empty() {
+            for typ in &forward {
+                result.push_str(&format!("{};\n", self.generate_type(typ)));
+            }
+            result.push('\n');
+        }
+        result.extend(rest.iter().map(|decl| decl.emit(self)));
         result
     }
+
+    // 输出一条全局声明（含结尾的空行）
+    pub fn generate_declaration(&mut self, decl: &Declaration) -> String {
+        if self.target == TargetLang::Rust {
+            return self.generate_rust_declaration(decl);
+        }
+        let mut result = String::new();
+        match decl {
+            Declaration::Function(func) => {
+                // 只生成有函数体的函数
+                if !func.body.is_empty() {
+                    result.push_str(&self.generate_function(func));
+                    result.push('\n');
+                }
+            }
+            Declaration::Struct(struct_def) => {
+                result.push_str(&self.generate_struct(struct_def));
+                result.push_str(";\n\n");
+            }
+            Declaration::Union(union_def) => {
+                result.push_str(&self.generate_union(union_def));
+                result.push_str(";\n\n");
+            }
+            Declaration::Enum(enum_def) => {
+                result.push_str(&self.generate_enum(enum_def));
+                result.push_str(";\n\n");
+            }
+            Declaration::Typedef(typedef_def) => {
+                result.push_str(&self.generate_typedef(typedef_def));
+                result.push_str("\n\n");
+            }
+            Declaration::GlobalVar {
+                storage,
+                typ,
+                name,
+                init,
+            } => {
+                result.push_str(storage_prefix(storage));
+                result.push_str(&self.generate_declarator(typ, name));
+                if let Some(expr) = init {
+                    result.push_str(" = ");
+                    result.push_str(&self.generate_expr(expr));
+                }
+                result.push_str(";\n\n");
+            }
+            Declaration::Include(path) => {
+                result.push_str(&format!("#include {}\n", path));
+            }
+            Declaration::Define { name, value } if value.is_empty() => {
+                result.push_str(&format!("#define {}\n", name));
+            }
+            Declaration::Define { name, value } => {
+                result.push_str(&format!("#define {} {}\n", name, value));
+            }
+            Declaration::ForwardDecl(typ) => {
+                result.push_str(&format!("{};\n\n", self.generate_type(typ)));
+            }
+            Declaration::Pragma(text) => {
+                result.push_str(&format!("#pragma {}\n", text));
+            }
+            // 注释紧贴其后的声明，不额外空行
+            Declaration::Comment(text) => {
+                result.push_str(text);
+                result.push('\n');
+            }
+        }
+        result
+    }
+
+    // 按位置初始化对应 Rust 的数组字面量；按字段初始化输出结构体字面量的字段部分，
+    // 类型名由上下文决定。数组下标指定的项按出现顺序排列
+    fn generate_rust_init_list(&self, items: &[InitItem]) -> String {
+        let by_field = items
+            .iter()
+            .any(|item| matches!(item.designators.first(), Some(Designator::Field(_))));
+        let items_str = items
+            .iter()
+            .map(|item| match item.designators.first() {
+                Some(Designator::Field(name)) if by_field => {
+                    format!("{}: {}", name, self.generate_rust_expr(&item.value))
+                }
+                _ => self.generate_rust_expr(&item.value),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        if by_field {
+            format!("{{ {} }}", items_str)
+        } else {
+            format!("[{}]", items_str)
+        }
+    }
+
+    fn generate_rust_expr(&self, expr: &Expr) -> String {
+        match expr {
+            Expr::IntLiteral(n) => self.generate_int(*n),
+            // Rust 的字面量类型由上下文推断，不需要保留 C 的后缀
+            Expr::IntLiteralSuffixed { value, .. } => self.generate_int(*value),
+            Expr::FloatLiteral(f) => self.generate_float(*f),
+            Expr::FloatLiteralSuffixed { value, .. } => self.generate_float(*value),
+            // C 的 char 是整数类型，对应 Rust 的 i8
+            Expr::CharLiteral(c) => {
+                format!("('{}' as i8)", escape_rust_string(&c.to_string(), '\''))
+            }
+            Expr::StringLiteral(s) => format!("\"{}\"", escape_rust_string(s, '"')),
+            Expr::Identifier(name) => name.clone(),
+            // 与 NULL 比较改写为 is_null()；已知是指针时与 0 比较同样处理
+            Expr::Binary {
+                op: op @ (BinaryOp::Eq | BinaryOp::Ne),
+                left,
+                right,
+            } if self.is_null_operand(left, right) || self.is_null_operand(right, left) => {
+                let ptr = if self.is_null_operand(left, right) {
+                    right
+                } else {
+                    left
+                };
+                let negate = if *op == BinaryOp::Ne { "!" } else { "" };
+                format!("{}{}.is_null()", negate, self.generate_rust_expr(ptr))
+            }
+            Expr::Binary { op, left, right } => {
+                format!(
+                    "({} {} {})",
+                    self.generate_rust_expr(left),
+                    self.generate_binary_op(op),
+                    self.generate_rust_expr(right)
+                )
+            }
+            // 作为值使用的逻辑非：操作数已经是 bool 时直接用 !，
+            // 否则 Rust 的 ! 作用于整数是按位取反，改为与 0 比较（指针为 is_null()）后转成 i32
+            Expr::Unary {
+                op: UnaryOp::Not,
+                operand,
+            } if !is_rust_bool(operand) => {
+                format!("({} as i32)", self.generate_rust_truth(operand, true))
+            }
+            Expr::Unary { op, operand } => {
+                let operand = self.generate_rust_expr(operand);
+                // Rust 没有自增自减运算符，用块表达式模拟其求值结果
+                match op {
+                    UnaryOp::PreIncrement => format!("{{ {0} += 1; {0} }}", operand),
+                    UnaryOp::PreDecrement => format!("{{ {0} -= 1; {0} }}", operand),
+                    UnaryOp::PostIncrement => {
+                        format!("{{ let tmp = {0}; {0} += 1; tmp }}", operand)
+                    }
+                    UnaryOp::PostDecrement => {
+                        format!("{{ let tmp = {0}; {0} -= 1; tmp }}", operand)
+                    }
+                    UnaryOp::Neg => format!("({})", join_prefix_op("-", &operand)),
+                    // Rust 没有一元加号，直接使用操作数
+                    UnaryOp::Plus => operand,
+                    UnaryOp::Not | UnaryOp::BitNot => format!("(!{})", operand),
+                    UnaryOp::Deref => format!("(*{})", operand),
+                    UnaryOp::AddressOf => format!("(&mut {})", operand),
+                }
+            }
+            Expr::Call { func, args } => {
+                let args_str = args
+                    .iter()
+                    .map(|arg| self.generate_rust_expr(arg))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                if let Some(print) = self.generate_rust_print(func, args) {
+                    return print;
+                }
+                if LIBC_FUNCTIONS.contains(&func.as_str()) {
+                    self.uses_libc.set(true);
+                }
+                // malloc 无法识别为 Box 分配时，按字面翻译为 libc 调用
+                if func == "malloc" {
+                    format!("libc::malloc({})", args_str)
+                } else {
+                    format!("{}({})", func, args_str)
+                }
+            }
+            // Rust 的函数指针直接调用，(*fp)(x) 与 fp(x) 都翻译为 fp(x)
+            Expr::CallExpr { callee, args } => {
+                let args_str = args
+                    .iter()
+                    .map(|arg| self.generate_rust_expr(arg))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let callee = match &**callee {
+                    Expr::Unary {
+                        op: UnaryOp::Deref,
+                        operand,
+                    } => operand,
+                    _ => callee,
+                };
+                let callee_str = self.generate_rust_expr(callee);
+                if c_precedence(callee) < PREC_POSTFIX {
+                    format!("({})({})", callee_str, args_str)
+                } else {
+                    format!("{}({})", callee_str, args_str)
+                }
+            }
+            // Rust 的赋值表达式的值是 ()，作为子表达式时用块表达式返回赋值后的目标
+            Expr::Assignment { target, value } => {
+                let target = self.generate_rust_expr(target);
+                format!(
+                    "{{ {} = {}; {} }}",
+                    target,
+                    self.generate_rust_expr(value),
+                    target
+                )
+            }
+            Expr::Cast { typ, expr } => {
+                if let Some(alloc) = self.generate_rust_box_alloc(typ, expr) {
+                    return alloc;
+                }
+                format!(
+                    "({} as {})",
+                    self.generate_rust_expr(expr),
+                    self.generate_rust_type(typ)
+                )
+            }
+            Expr::ArrayAccess { array, index } => {
+                format!(
+                    "{}[{} as usize]",
+                    self.generate_rust_expr(array),
+                    self.generate_rust_expr(index)
+                )
+            }
+            Expr::MemberAccess { object, member } => {
+                format!("{}.{}", self.generate_rust_expr(object), member)
+            }
+            Expr::PointerMemberAccess { object, member } => {
+                format!("(*{}).{}", self.generate_rust_expr(object), member)
+            }
+            Expr::Ternary {
+                cond,
+                then_expr,
+                else_expr,
+            } => {
+                format!(
+                    "if {} {{ {} }} else {{ {} }}",
+                    self.generate_rust_cond(cond),
+                    self.generate_rust_expr(then_expr),
+                    self.generate_rust_expr(else_expr)
+                )
+            }
+            Expr::SizeOf(typ) => {
+                format!("std::mem::size_of::<{}>()", self.generate_rust_type(typ))
+            }
+            Expr::SizeOfExpr(expr) => {
+                format!("std::mem::size_of_val(&{})", self.generate_rust_expr(expr))
+            }
+            Expr::StmtExpr(stmts) => format!("{{ {} }}", self.generate_stmt_expr_body(stmts)),
+            // Rust 没有逗号运算符，用块表达式依次求值并取最后一项
+            Expr::Comma(exprs) => {
+                let mut parts = exprs
+                    .iter()
+                    .map(|e| self.generate_rust_expr(e))
+                    .collect::<Vec<_>>();
+                let last = parts.pop().unwrap_or_default();
+                let mut result = "{ ".to_string();
+                for part in parts {
+                    result.push_str(&part);
+                    result.push_str("; ");
+                }
+                result.push_str(&last);
+                result.push_str(" }");
+                result
+            }
+            Expr::InitList(items) => self.generate_rust_init_list(items),
+            Expr::CompoundLiteral { typ, init } => {
+                let value = self.generate_rust_init_list(init);
+                if value.starts_with('{') {
+                    format!("{} {}", self.generate_rust_type(typ), value)
+                } else {
+                    value
+                }
+            }
+            Expr::Null => "std::ptr::null_mut()".to_string(),
+        }
+    }
+
+    // 识别 (T*)malloc(sizeof(T))，翻译为单个 T 的堆分配。
+    // 使用零初始化而不是 T::default()，这样不要求 T 实现 Default；
+    // 得到的裸指针与 malloc 的返回值用法一致，需要配合 Box::from_raw 释放。
+    fn generate_rust_box_alloc(&self, typ: &CType, expr: &Expr) -> Option<String> {
+        let CType::Pointer(pointee) = typ else {
+            return None;
+        };
+        let Expr::Call { func, args } = expr else {
+            return None;
+        };
+        if func != "malloc" || **pointee == CType::Void {
+            return None;
+        }
+        // 通过 Rust 类型名比较，使 sizeof(struct Node) 与 typedef 的 Node 也能匹配
+        let pointee_type = self.generate_rust_type(pointee);
+        match args.as_slice() {
+            [Expr::SizeOf(size_type)] if self.generate_rust_type(size_type) == pointee_type => {
+                Some(format!(
+                    "Box::into_raw(Box::new(unsafe {{ std::mem::zeroed::<{}>() }}))",
+                    pointee_type
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    // 将 printf/puts 翻译为 print!/println!，格式串以换行结尾时选用 println!。
+    // 格式串不是字面量或包含无法翻译的转换说明时返回 None，按普通调用输出。
+    fn generate_rust_print(&self, func: &str, args: &[Expr]) -> Option<String> {
+        let (format, rest) = match (func, args) {
+            ("printf", [Expr::StringLiteral(format), rest @ ..]) => {
+                (self.convert_printf_format(format, rest.len())?, rest)
+            }
+            // puts 会自动追加换行
+            ("puts", [Expr::StringLiteral(text)]) => {
+                (text.replace('{', "{{").replace('}', "}}") + "\n", &[][..])
+            }
+            _ => return None,
+        };
+        let (mac, format) = match format.strip_suffix('\n') {
+            Some(line) => ("println!", line.to_string()),
+            None => ("print!", format),
+        };
+        let mut result = format!("{}(\"{}\"", mac, escape_rust_string(&format, '"'));
+        for arg in rest {
+            result.push_str(", ");
+            result.push_str(&self.generate_rust_expr(arg));
+        }
+        result.push(')');
+        Some(result)
+    }
+
+    // 把 printf 格式串转换为 Rust 的格式串，支持标志、宽度和精度
+    fn convert_printf_format(&self, format: &str, arg_count: usize) -> Option<String> {
+        let mut result = String::new();
+        let mut placeholders = 0;
+        let mut chars = format.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '{' => result.push_str("{{"),
+                '}' => result.push_str("}}"),
+                '%' if chars.peek() == Some(&'%') => {
+                    chars.next();
+                    result.push('%');
+                }
+                '%' => {
+                    // 标志，按 Rust 要求的 对齐、符号、补零 顺序拼接
+                    let (mut align, mut sign, mut zero) = ("", "", "");
+                    while let Some(&flag) = chars.peek() {
+                        match flag {
+                            '-' => align = "<",
+                            '+' => sign = "+",
+                            '0' => zero = "0",
+                            ' ' | '#' => {}
+                            _ => break,
+                        }
+                        chars.next();
+                    }
+                    let mut spec = format!("{}{}{}", align, sign, zero);
+                    // 宽度和精度
+                    while let Some(&c) = chars.peek() {
+                        if !(c.is_ascii_digit() || c == '.') {
+                            break;
+                        }
+                        spec.push(c);
+                        chars.next();
+                    }
+                    // 长度修饰符对 Rust 没有意义
+                    while matches!(chars.peek(), Some('h' | 'l' | 'z' | 'j' | 't' | 'L')) {
+                        chars.next();
+                    }
+                    let trait_spec = match chars.next()? {
+                        'd' | 'i' | 'u' | 's' | 'c' | 'f' | 'F' | 'g' | 'G' => "",
+                        'e' => "e",
+                        'E' => "E",
+                        'x' => "x",
+                        'X' => "X",
+                        'o' => "o",
+                        'p' => "p",
+                        _ => return None,
+                    };
+                    placeholders += 1;
+                    if spec.is_empty() && trait_spec.is_empty() {
+                        result.push_str("{}");
+                    } else {
+                        result.push_str(&format!("{{:{}{}}}", spec, trait_spec));
+                    }
+                }
+                _ => result.push(ch),
+            }
+        }
+        if placeholders != arg_count {
+            return None;
+        }
+        Some(result)
+    }
+
+    // match 的模式只接受字面量和常量，'a' as i8 之类的表达式不是合法模式，
+    // 能求值的 case 标签折叠为整数字面量
+    fn generate_rust_case_label(&self, value: &Expr) -> String {
+        match const_int_value(value, &|_| None) {
+            Some(n) => n.to_string(),
+            None => self.generate_rust_expr(value),
+        }
+    }
+
+    // 记录变量是否为指针，同名的后一个声明覆盖前一个
+    fn record_var_type(&mut self, name: &str, typ: &CType) {
+        if is_pointer_type(typ) {
+            self.pointer_vars.insert(name.to_string());
+        } else {
+            self.pointer_vars.remove(name);
+        }
+    }
+
+    fn is_rust_pointer_expr(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Identifier(name) => self.pointer_vars.contains(name),
+            _ => obvious_type(expr).is_some_and(|typ| is_pointer_type(&typ)),
+        }
+    }
+
+    // operand 是否表示空指针：NULL，或与指针比较的字面量 0
+    fn is_null_operand(&self, operand: &Expr, other: &Expr) -> bool {
+        match operand {
+            Expr::Null => true,
+            Expr::IntLiteral(0) => self.is_rust_pointer_expr(other),
+            _ => false,
+        }
+    }
+
+    // 条件位置（if/while/for/do-while/三目运算）需要 bool：比较与逻辑运算直接使用，
+    // 指针改写为 is_null()，其余整数与 0 比较
+    fn generate_rust_cond(&self, expr: &Expr) -> String {
+        self.generate_rust_truth(expr, false)
+    }
+
+    // 表达式作为条件的真值，negate 为 true 时取反
+    fn generate_rust_truth(&self, expr: &Expr, negate: bool) -> String {
+        match expr {
+            Expr::Unary {
+                op: UnaryOp::Not,
+                operand,
+            } => self.generate_rust_truth(operand, !negate),
+            Expr::Binary {
+                op: op @ (BinaryOp::And | BinaryOp::Or),
+                left,
+                right,
+            } => {
+                let result = format!(
+                    "({} {} {})",
+                    self.generate_rust_cond(left),
+                    self.generate_binary_op(op),
+                    self.generate_rust_cond(right)
+                );
+                if negate {
+                    format!("(!{})", result)
+                } else {
+                    result
+                }
+            }
+            expr if is_rust_bool(expr) => {
+                let result = self.generate_rust_expr(expr);
+                if negate {
+                    format!("(!{})", result)
+                } else {
+                    result
+                }
+            }
+            expr if self.is_rust_pointer_expr(expr) => format!(
+                "{}{}.is_null()",
+                if negate { "" } else { "!" },
+                self.generate_rust_expr(expr)
+            ),
+            expr => format!(
+                "({} {} 0)",
+                self.generate_rust_expr(expr),
+                if negate { "==" } else { "!=" }
+            ),
+        }
+    }
+
+    // do-while 的条件检查：条件不成立时退出循环，label 为循环的标签
+    fn generate_rust_loop_exit(&self, cond: &Expr, label: Option<&str>) -> String {
+        format!(
+            "{}if !({}) {{\n{}{}break{};\n{}}}\n",
+            self.indent_str(),
+            self.generate_rust_cond(cond),
+            self.indent_str(),
+            self.indent_unit(),
+            label_suffix(label),
+            self.indent_str()
+        )
+    }
+
+    // 新标签的名字，嵌套时加上层数以免与外层同名
+    fn new_label(&self, base: &str) -> String {
+        match self.label_depth {
+            0 => format!("'{}", base),
+            depth => format!("'{}{}", base, depth + 1),
+        }
+    }
+
+    // 循环中的 continue 需要跳出带标签的 switch 块时，循环本身也要带标签
+    fn rust_loop_label(&self, body: &[Stmt]) -> Option<String> {
+        loop_needs_label(body).then(|| self.new_label("outer"))
+    }
+
+    // 生成循环体；其中的 continue 属于这个循环，跳转前先执行 action。
+    // 循环体中的 break 退出的是这个循环，不再是外层的 switch
+    fn generate_rust_loop_body(
+        &mut self,
+        body: &[Stmt],
+        action: Option<ContinueAction>,
+        label: Option<String>,
+    ) -> String {
+        let labeled = usize::from(label.is_some());
+        self.label_depth += labeled;
+        let outer_action = std::mem::replace(&mut self.continue_action, action);
+        let outer_label = std::mem::replace(&mut self.loop_label, label);
+        let outer_break = self.break_label.take();
+        let result = self.generate_rust_block(body);
+        self.continue_action = outer_action;
+        self.loop_label = outer_label;
+        self.break_label = outer_break;
+        self.label_depth -= labeled;
+        result
+    }
+
+    fn generate_rust_block(&mut self, stmts: &[Stmt]) -> String {
+        let stmts = unwrap_block(stmts);
+        let mut result = String::new();
+        self.indent += 1;
+        for stmt in stmts {
+            result.push_str(&self.generate_rust_stmt(stmt));
+        }
+        self.indent -= 1;
+        result
+    }
+
+    fn generate_rust_stmt(&mut self, stmt: &Stmt) -> String {
+        // 作为值使用的自增自减按块表达式近似翻译，提醒人工检查求值顺序
+        let todo = if has_value_increment(stmt) {
+            format!(
+                "{}// TODO: side-effecting ++ in expression\n",
+                self.indent_str()
+            )
+        } else {
+            String::new()
+        };
+        todo + &self.generate_rust_stmt_only(stmt)
+    }
+
+    // 翻译单条语句，不附加 TODO 提示；用于拆开后的语句片段，避免提示重复
+    fn generate_rust_stmt_only(&mut self, stmt: &Stmt) -> String {
+        match stmt {
+            Stmt::VarDecl {
+                typ, name, init, ..
+            } => {
+                self.record_var_type(name, typ);
+                let rust_type = self.generate_rust_type(typ);
+                let mut result = format!("{}let mut {}: {}", self.indent_str(), name, rust_type);
+                if let Some(expr) = init {
+                    let mut value = self.generate_rust_expr(expr);
+                    // 按字段初始化的列表需要补上结构体类型名
+                    if matches!(expr, Expr::InitList(_)) && value.starts_with('{') {
+                        value = format!("{} {}", rust_type, value);
+                    }
+                    result.push_str(&format!(" = {}", value));
+                }
+                result.push_str(";\n");
+                result
+            }
+            Stmt::Return(expr) => {
+                let mut result = format!("{}return", self.indent_str());
+                if let Some(e) = expr {
+                    result.push_str(&format!(" {}", self.generate_rust_expr(e)));
+                }
+                result.push_str(";\n");
+                result
+            }
+            // 语句位置的 i++ / i-- 直接写成复合赋值
+            Stmt::Expr(Expr::Unary {
+                op: UnaryOp::PreIncrement | UnaryOp::PostIncrement,
+                operand,
+            }) => format!(
+                "{}{} += 1;\n",
+                self.indent_str(),
+                self.generate_rust_expr(operand)
+            ),
+            Stmt::Expr(Expr::Unary {
+                op: UnaryOp::PreDecrement | UnaryOp::PostDecrement,
+                operand,
+            }) => format!(
+                "{}{} -= 1;\n",
+                self.indent_str(),
+                self.generate_rust_expr(operand)
+            ),
+            // 语句位置的赋值直接输出；连续赋值 a = b = c 先赋值内层，再把内层目标赋给外层
+            Stmt::Expr(Expr::Assignment { target, value }) => {
+                let target = self.generate_rust_expr(target);
+                match &**value {
+                    Expr::Assignment { target: inner, .. } => format!(
+                        "{}{}{} = {};\n",
+                        self.generate_rust_stmt_only(&Stmt::Expr((**value).clone())),
+                        self.indent_str(),
+                        target,
+                        self.generate_rust_expr(inner)
+                    ),
+                    value => format!(
+                        "{}{} = {};\n",
+                        self.indent_str(),
+                        target,
+                        self.generate_rust_expr(value)
+                    ),
+                }
+            }
+            // 语句位置的逗号表达式拆成多条语句
+            Stmt::Expr(Expr::Comma(exprs)) => exprs
+                .iter()
+                .map(|e| self.generate_rust_stmt_only(&Stmt::Expr(e.clone())))
+                .collect(),
+            Stmt::Expr(expr) => {
+                format!("{}{};\n", self.indent_str(), self.generate_rust_expr(expr))
+            }
+            Stmt::If {
+                cond,
+                then_block,
+                else_block,
+            } => {
+                let mut result = format!(
+                    "{}if {} {{\n",
+                    self.indent_str(),
+                    self.generate_rust_cond(cond)
+                );
+                result.push_str(&self.generate_rust_block(then_block));
+                result.push_str(&format!("{}}}", self.indent_str()));
+                match else_block.as_deref() {
+                    Some([else_if @ Stmt::If { .. }]) => {
+                        result.push_str(" else ");
+                        result.push_str(self.generate_rust_stmt_only(else_if).trim());
+                    }
+                    Some(else_stmts) => {
+                        result.push_str(" else {\n");
+                        result.push_str(&self.generate_rust_block(else_stmts));
+                        result.push_str(&format!("{}}}", self.indent_str()));
+                    }
+                    None => {}
+                }
+                result.push('\n');
+                result
+            }
+            Stmt::While { cond, body } => {
+                let label = self.rust_loop_label(body);
+                let mut result = format!(
+                    "{}{}while {} {{\n",
+                    self.indent_str(),
+                    label_prefix(label.as_deref()),
+                    self.generate_rust_cond(cond)
+                );
+                result.push_str(&self.generate_rust_loop_body(body, None, label));
+                result.push_str(&format!("{}}}\n", self.indent_str()));
+                result
+            }
+            // do-while 翻译为 loop，循环体末尾和每个 continue 之前检查条件
+            Stmt::DoWhile { body, cond } => {
+                let label = self.rust_loop_label(body);
+                let mut result = format!(
+                    "{}{}loop {{\n",
+                    self.indent_str(),
+                    label_prefix(label.as_deref())
+                );
+                result.push_str(&self.generate_rust_loop_body(
+                    body,
+                    Some(ContinueAction::CheckCond(cond.clone())),
+                    label,
+                ));
+                self.indent += 1;
+                result.push_str(&self.generate_rust_loop_exit(cond, None));
+                self.indent -= 1;
+                result.push_str(&format!("{}}}\n", self.indent_str()));
+                result
+            }
+            // for 翻译为外层块中的 while 循环，update 放在循环体末尾和每个 continue 之前
+            Stmt::For {
+                init,
+                cond,
+                update,
+                body,
+            } => self.generate_for_rust(init.as_deref(), cond.as_ref(), update.as_ref(), body),
+            // switch 翻译为 match，每个 case 末尾的 break 在 Rust 中是多余的。
+            // 连续的空 case（fall-through）合并为 a | b 模式，default 分支放到最后。
+            // 非空 case 之间没有 break 的 fall-through 无法用 match 表达，
+            // 该分支末尾输出 TODO 注释提示手工处理。
+            // 分支中间还有 break 时，match 外包一层带标签的块，break 改写为 break 'switch
+            Stmt::Switch { expr, cases } => {
+                let label = switch_needs_label(cases).then(|| self.new_label("switch"));
+                let mut result = String::new();
+                if let Some(label) = &label {
+                    result.push_str(&format!("{}{}: {{\n", self.indent_str(), label));
+                    self.indent += 1;
+                }
+                result.push_str(&format!(
+                    "{}match {} {{\n",
+                    self.indent_str(),
+                    self.generate_rust_expr(expr)
+                ));
+                self.indent += 1;
+                let mut arms: Vec<(Vec<String>, &[Stmt], bool)> = Vec::new();
+                let mut default_arm: Option<(&[Stmt], bool)> = None;
+                let mut patterns: Vec<String> = Vec::new();
+                let mut has_default = false;
+                for (i, case) in cases.iter().enumerate() {
+                    match &case.value {
+                        Some(value) => {
+                            let pattern = match &case.range_end {
+                                Some(end) => format!(
+                                    "{}..={}",
+                                    self.generate_rust_case_label(value),
+                                    self.generate_rust_case_label(end)
+                                ),
+                                None => self.generate_rust_case_label(value),
+                            };
+                            patterns.push(pattern);
+                        }
+                        None => has_default = true,
+                    }
+                    if case.stmts.is_empty() && i + 1 < cases.len() {
+                        continue;
+                    }
+                    let stmts = case_body(&case.stmts);
+                    let falls_through = i + 1 < cases.len()
+                        && !matches!(
+                            case.stmts.last(),
+                            Some(
+                                Stmt::Break(_)
+                                    | Stmt::Return(_)
+                                    | Stmt::Continue(_)
+                                    | Stmt::Goto(_)
+                            )
+                        );
+                    if has_default {
+                        default_arm = Some((stmts, falls_through));
+                    } else {
+                        arms.push((std::mem::take(&mut patterns), stmts, falls_through));
+                    }
+                    patterns.clear();
+                    has_default = false;
+                }
+                let outer_break = std::mem::replace(&mut self.break_label, label.clone());
+                self.label_depth += usize::from(label.is_some());
+                for (patterns, stmts, falls_through) in arms {
+                    result.push_str(&format!(
+                        "{}{} => {{\n",
+                        self.indent_str(),
+                        patterns.join(" | ")
+                    ));
+                    result.push_str(&self.generate_rust_block(stmts));
+                    if falls_through {
+                        result.push_str(&format!(
+                            "{}{}// TODO: fallthrough\n",
+                            self.indent_str(),
+                            self.indent_unit()
+                        ));
+                    }
+                    result.push_str(&format!("{}}}\n", self.indent_str()));
+                }
+                match default_arm {
+                    Some((stmts, falls_through)) => {
+                        result.push_str(&format!("{}_ => {{\n", self.indent_str()));
+                        result.push_str(&self.generate_rust_block(stmts));
+                        if falls_through {
+                            result.push_str(&format!(
+                                "{}{}// TODO: fallthrough\n",
+                                self.indent_str(),
+                                self.indent_unit()
+                            ));
+                        }
+                        result.push_str(&format!("{}}}\n", self.indent_str()));
+                    }
+                    None => result.push_str(&format!("{}_ => {{}}\n", self.indent_str())),
+                }
+                self.break_label = outer_break;
+                self.label_depth -= usize::from(label.is_some());
+                self.indent -= 1;
+                result.push_str(&format!("{}}}\n", self.indent_str()));
+                if label.is_some() {
+                    self.indent -= 1;
+                    result.push_str(&format!("{}}}\n", self.indent_str()));
+                }
+                result
+            }
+            Stmt::Break(_) => match &self.break_label {
+                Some(label) => format!("{}break {};\n", self.indent_str(), label),
+                None => format!("{}break;\n", self.indent_str()),
+            },
+            // 翻译为 while 的 for 循环中，continue 前先执行 update；
+            // 翻译为 loop 的 do-while 中，continue 前先检查条件
+            // 带标签的循环中 continue 可能要跳出带标签的 switch 块，需要写明循环标签
+            Stmt::Continue(_) => {
+                let label = self.loop_label.clone();
+                let prefix = match self.continue_action.clone() {
+                    Some(ContinueAction::Update(update)) => {
+                        self.generate_rust_stmt_only(&Stmt::Expr(update))
+                    }
+                    Some(ContinueAction::CheckCond(cond)) => {
+                        self.generate_rust_loop_exit(&cond, label.as_deref())
+                    }
+                    None => String::new(),
+                };
+                format!(
+                    "{}{}continue{};\n",
+                    prefix,
+                    self.indent_str(),
+                    label_suffix(label.as_deref())
+                )
+            }
+            // Rust 没有 goto，保留为注释
+            Stmt::Goto(label) => format!("{}// goto {};\n", self.indent_str(), label),
+            Stmt::Label(label) => format!("{}// {}:\n", self.indent_str(), label),
+            // C 的内联汇编语法与 Rust 的 asm! 不兼容，保留为注释
+            Stmt::InlineAsm(text) => format!("{}// {};\n", self.indent_str(), text),
+            Stmt::Comment(text) => format!(
+                "{}{}\n",
+                self.indent_str(),
+                rust_comment(text, &self.indent_str())
+            ),
+            Stmt::Block(stmts) => {
+                let mut result = format!("{}{{\n", self.indent_str());
+                result.push_str(&self.generate_rust_block(stmts));
+                result.push_str(&format!("{}}}\n", self.indent_str()));
+                result
+            }
+            // 多个声明符依次输出为独立的 let 语句
+            Stmt::DeclList(decls) => decls
+                .iter()
+                .map(|decl| self.generate_rust_stmt(decl))
+                .collect(),
+            Stmt::Empty => String::new(),
+        }
+    }
+
+    // 计数循环 for (int i = a; i < b; i++) 翻译为 for i in a..b，
+    // 其他形式展开为 { init; while cond { body; update; } }
+    fn generate_for_rust(
+        &mut self,
+        init: Option<&Stmt>,
+        cond: Option<&Expr>,
+        update: Option<&Expr>,
+        body: &[Stmt],
+    ) -> String {
+        let label = self.rust_loop_label(body);
+        if let Some(range) = self.rust_range_for(init, cond, update, body) {
+            let mut result = format!(
+                "{}{}for {} {{\n",
+                self.indent_str(),
+                label_prefix(label.as_deref()),
+                range
+            );
+            result.push_str(&self.generate_rust_loop_body(body, None, label));
+            result.push_str(&format!("{}}}\n", self.indent_str()));
+            return result;
+        }
+
+        let mut result = format!("{}{{\n", self.indent_str());
+        self.indent += 1;
+        if let Some(init_stmt) = init {
+            result.push_str(&self.generate_rust_stmt(init_stmt));
+        }
+        let cond_str = match cond {
+            Some(c) => self.generate_rust_cond(c),
+            None => "true".to_string(),
+        };
+        result.push_str(&format!(
+            "{}{}while {} {{\n",
+            self.indent_str(),
+            label_prefix(label.as_deref()),
+            cond_str
+        ));
+        result.push_str(&self.generate_rust_loop_body(
+            body,
+            update.cloned().map(ContinueAction::Update),
+            label,
+        ));
+        if let Some(update_expr) = update {
+            self.indent += 1;
+            result.push_str(&self.generate_rust_stmt_only(&Stmt::Expr(update_expr.clone())));
+            self.indent -= 1;
+        }
+        result.push_str(&format!("{}}}\n", self.indent_str()));
+        self.indent -= 1;
+        result.push_str(&format!("{}}}\n", self.indent_str()));
+        result
+    }
+
+    // 识别计数循环，返回 "i in a..b" 形式的循环头；
+    // 要求循环变量在 init 中声明，且循环体内不会修改它
+    fn rust_range_for(
+        &self,
+        init: Option<&Stmt>,
+        cond: Option<&Expr>,
+        update: Option<&Expr>,
+        body: &[Stmt],
+    ) -> Option<String> {
+        let Some(Stmt::VarDecl {
+            name,
+            init: Some(start),
+            ..
+        }) = init
+        else {
+            return None;
+        };
+        let is_var = |e: &Expr| matches!(e, Expr::Identifier(n) if n == name);
+
+        let Some(Expr::Binary { op, left, right }) = cond else {
+            return None;
+        };
+        let range_op = match op {
+            BinaryOp::Lt => "..",
+            BinaryOp::Le => "..=",
+            _ => return None,
+        };
+        if !is_var(left) {
+            return None;
+        }
+
+        // 步长：i++ / ++i 为 1，i += k 为 k。
+        // step_by 遇到 0 会 panic，负数转为 usize 后也不对，只接受正的字面量，
+        // 或循环体内不被修改的变量
+        let step = match update? {
+            Expr::Unary {
+                op: UnaryOp::PostIncrement | UnaryOp::PreIncrement,
+                operand,
+            } if is_var(operand) => None,
+            Expr::Assignment { target, value } if is_var(target) => match &**value {
+                Expr::Binary {
+                    op: BinaryOp::Add,
+                    left,
+                    right,
+                } if is_var(left) => match &**right {
+                    Expr::IntLiteral(1) => None,
+                    Expr::IntLiteral(k) if *k > 0 => Some(&**right),
+                    Expr::Identifier(k) if k != name && !stmts_modify(body, k) => Some(&**right),
+                    _ => return None,
+                },
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        // Rust 的区间只在循环开始前求值一次，上界必须在循环体内保持不变
+        let bound_fixed = match &**right {
+            Expr::IntLiteral(_) => true,
+            Expr::Identifier(bound) => !stmts_modify(body, bound),
+            _ => false,
+        };
+        if !bound_fixed || stmts_modify(body, name) {
+            return None;
+        }
+
+        let range = format!(
+            "{}{}{}",
+            self.generate_rust_expr(start),
+            range_op,
+            self.generate_rust_expr(right)
+        );
+        Some(match step {
+            None => format!("{} in {}", name, range),
+            Some(k) => format!(
+                "{} in ({}).step_by({} as usize)",
+                name,
+                range,
+                self.generate_rust_expr(k)
+            ),
+        })
+    }
+
+    fn generate_rust_function(&mut self, func: &Function) -> String {
+        // 参数与局部变量只在函数内可见
+        let globals = self.pointer_vars.clone();
+        let result = self.generate_rust_function_body(func);
+        self.pointer_vars = globals;
+        result
+    }
+
+    fn generate_rust_function_body(&mut self, func: &Function) -> String {
+        let params = &func.params;
+        for param in params {
+            self.record_var_type(&param.name, &param.typ);
+        }
+        let param_types: Vec<String> = params
+            .iter()
+            .map(|p| self.rust_param_type(p, &func.body))
+            .collect();
+        // C 风格的可变参数函数需要 nightly 的 c_variadic 特性，写作 unsafe extern "C" fn
+        let params_str = join_params(
+            params
+                .iter()
+                .zip(&param_types)
+                .map(|(p, typ)| {
+                    let name = if p.name.is_empty() { "_" } else { &p.name };
+                    format!("{}: {}", name, typ)
+                })
+                .collect(),
+            func.variadic.then_some("mut args: ..."),
+        );
+        // 已改为引用的参数解引用是安全的，其余指针的 *p、p->x 按原始指针处理
+        let ref_params: Vec<&str> = params
+            .iter()
+            .zip(&param_types)
+            .filter(|(_, typ)| typ.starts_with('&'))
+            .map(|(p, _)| p.name.as_str())
+            .collect();
+
+        // inline 只是优化提示，对应 Rust 的 #[inline]
+        let mut result = String::new();
+        if func.is_inline {
+            result.push_str("#[inline]\n");
+        }
+        if func.variadic {
+            result.push_str("unsafe extern \"C\" ");
+        }
+        result.push_str(&format!("fn {}({})", func.name, params_str));
+        if func.return_type != CType::Void {
+            result.push_str(&format!(
+                " -> {}",
+                self.generate_rust_type(&func.return_type)
+            ));
+        }
+        result.push_str(" {\n");
+        if derefs_raw_pointer(&func.body, &ref_params) {
+            // 尽力而为：只要函数体解引用了原始指针，就把整个函数体包在一个 unsafe 块中，
+            // 不区分具体是哪条语句需要 unsafe
+            self.indent += 1;
+            result.push_str(&format!("{}unsafe {{\n", self.indent_str()));
+            result.push_str(&self.generate_rust_block(&func.body));
+            result.push_str(&format!("{}}}\n", self.indent_str()));
+            self.indent -= 1;
+        } else {
+            result.push_str(&self.generate_rust_block(&func.body));
+        }
+        result.push_str("}\n");
+        result
+    }
+
+    // 指针参数在函数体中只被解引用时改用引用：const T* 对应 &T，T* 对应 &mut T；
+    // void/char/函数指针以及有指针运算、比较或传递的参数仍保留原始指针
+    fn rust_param_type(&self, param: &Param, body: &[Stmt]) -> String {
+        let pointee = match &param.typ {
+            CType::Pointer(inner) => inner.as_ref(),
+            // restrict 保证指针不与其他指针重叠，不影响能否改用引用
+            CType::Const(inner) | CType::Restrict(inner) => match inner.as_ref() {
+                CType::Pointer(inner) => inner.as_ref(),
+                _ => return self.generate_rust_type(&param.typ),
+            },
+            _ => return self.generate_rust_type(&param.typ),
+        };
+        let (is_const, target) = match pointee {
+            CType::Const(inner) => (true, inner.as_ref()),
+            other => (false, other),
+        };
+        let is_char = matches!(
+            target,
+            CType::Char | CType::SignedChar | CType::UnsignedChar
+        );
+        if is_const && is_char && self.str_params {
+            return "&str".to_string();
+        }
+        let referable = !is_char
+            && !matches!(
+                target,
+                CType::Void | CType::Function { .. } | CType::Volatile(_)
+            );
+        if !referable || param.name.is_empty() || !only_dereferenced(body, &param.name) {
+            return self.generate_rust_type(&param.typ);
+        }
+        let target = self.generate_rust_type(target);
+        if is_const {
+            format!("&{}", target)
+        } else {
+            format!("&mut {}", target)
+        }
+    }
+
+    fn generate_rust_fields(&self, fields: &[StructField]) -> String {
+        let mut result = String::new();
+        for field in fields {
+            // 无名位域只用于填充，直接省略
+            if field.name.is_empty() && field.bit_width.is_some() {
+                continue;
+            }
+            // Rust 没有位域，按完整的底层类型输出并注明原宽度
+            result.push_str(&format!(
+                "{}pub {}: {},",
+                self.indent_unit(),
+                field.name,
+                self.generate_rust_type(&field.typ)
+            ));
+            if let Some(width) = field.bit_width {
+                result.push_str(&format!(" // bit-field width {}", width));
+            }
+            result.push('\n');
+        }
+        result
+    }
+
+    // 匿名类型按出现顺序编号，保证同一次生成的结果是确定的
+    fn rust_type_name(&self, name: &str, prefix: &str) -> String {
+        if !name.is_empty() {
+            return name.to_string();
+        }
+        let n = self.anon_count.get();
+        self.anon_count.set(n + 1);
+        format!("{}{}", prefix, n)
+    }
+
+    // 当前 #pragma pack 设置下结构体与联合体的 repr 属性
+    fn rust_repr(&self) -> String {
+        match self.pack.get() {
+            Some(n) => format!("#[repr(C, packed({}))]", n),
+            None => "#[repr(C)]".to_string(),
+        }
+    }
+
+    fn generate_rust_struct(&self, struct_def: &StructDef) -> String {
+        format!(
+            "{}\npub struct {} {{\n{}}}",
+            self.rust_repr(),
+            self.rust_type_name(&struct_def.name, "AnonStruct"),
+            self.generate_rust_fields(&struct_def.fields)
+        )
+    }
+
+    fn generate_rust_union(&self, union_def: &UnionDef) -> String {
+        format!(
+            "{}\npub union {} {{\n{}}}",
+            self.rust_repr(),
+            self.rust_type_name(&union_def.name, "AnonUnion"),
+            self.generate_rust_fields(&union_def.fields)
+        )
+    }
+
+    // 具名且各项取值都能算出、互不重复的枚举输出为 #[repr(C)] enum；
+    // 否则（匿名枚举、取值重复或无法求值）输出为一组 i32 常量
+    fn generate_rust_enum(&self, enum_def: &EnumDef) -> String {
+        let values = enum_values(enum_def);
+        let distinct = values.as_ref().is_some_and(|values| {
+            let mut sorted = values.clone();
+            sorted.sort_unstable();
+            sorted.windows(2).all(|w| w[0] != w[1])
+        });
+
+        if !enum_def.name.is_empty() && distinct {
+            let mut result = format!("#[repr(C)]\npub enum {} {{\n", enum_def.name);
+            for (variant, value) in enum_def.variants.iter().zip(values.unwrap_or_default()) {
+                result.push_str(&format!(
+                    "{}{} = {},\n",
+                    self.indent_unit(),
+                    variant.name,
+                    value
+                ));
+            }
+            result.push('}');
+            return result;
+        }
+
+        let mut lines = Vec::new();
+        if !enum_def.name.is_empty() {
+            lines.push(format!("pub type {} = i32;", enum_def.name));
+        }
+        let mut prev: Option<&str> = None;
+        for (i, variant) in enum_def.variants.iter().enumerate() {
+            let value = match (&values, &variant.value, prev) {
+                (Some(values), _, _) => values[i].to_string(),
+                (None, Some(expr), _) => self.generate_rust_expr(expr),
+                (None, None, Some(prev)) => format!("{} + 1", prev),
+                (None, None, None) => "0".to_string(),
+            };
+            lines.push(format!("pub const {}: i32 = {};", variant.name, value));
+            prev = Some(&variant.name);
+        }
+        lines.join("\n")
+    }
+
+    fn generate_rust_typedef(&self, typedef_def: &TypedefDef) -> String {
+        // typedef struct [Tag] { ... } Name; 先输出类型定义，匿名类型直接使用 typedef 名
+        let (definition, type_name) = match &typedef_def.target_type {
+            CType::StructDef(def) => {
+                let mut def = (**def).clone();
+                if def.name.is_empty() {
+                    def.name = typedef_def.name.clone();
+                }
+                (self.generate_rust_struct(&def), def.name)
+            }
+            CType::UnionDef(def) => {
+                let mut def = (**def).clone();
+                if def.name.is_empty() {
+                    def.name = typedef_def.name.clone();
+                }
+                (self.generate_rust_union(&def), def.name)
+            }
+            CType::EnumDef(def) => {
+                let mut def = (**def).clone();
+                if def.name.is_empty() {
+                    def.name = typedef_def.name.clone();
+                }
+                (self.generate_rust_enum(&def), def.name)
+            }
+            target => {
+                return format!(
+                    "type {} = {};",
+                    typedef_def.name,
+                    self.generate_rust_type(target)
+                )
+            }
+        };
+        if type_name == typedef_def.name {
+            definition
+        } else {
+            format!(
+                "{}\n\ntype {} = {};",
+                definition, typedef_def.name, type_name
+            )
+        }
+    }
+
+    fn generate_rust_declaration(&mut self, decl: &Declaration) -> String {
+        let mut result = String::new();
+        match decl {
+            Declaration::Function(func) => {
+                // 只生成有函数体的函数
+                if !func.body.is_empty() {
+                    result.push_str(&self.generate_rust_function(func));
+                    result.push('\n');
+                }
+            }
+            Declaration::Struct(struct_def) => {
+                result.push_str(&self.generate_rust_struct(struct_def));
+                result.push_str("\n\n");
+            }
+            Declaration::Union(union_def) => {
+                result.push_str(&self.generate_rust_union(union_def));
+                result.push_str("\n\n");
+            }
+            Declaration::Enum(enum_def) => {
+                result.push_str(&self.generate_rust_enum(enum_def));
+                result.push_str("\n\n");
+            }
+            Declaration::Typedef(typedef_def) => {
+                result.push_str(&self.generate_rust_typedef(typedef_def));
+                result.push_str("\n\n");
+            }
+            // 全局变量对应 static mut，没有初始值时按 C 语义零初始化
+            Declaration::GlobalVar {
+                typ, name, init, ..
+            } => {
+                self.record_var_type(name, typ);
+                let init_str = match init {
+                    Some(expr) => self.generate_rust_expr(expr),
+                    None => "unsafe { std::mem::zeroed() }".to_string(),
+                };
+                result.push_str(&format!(
+                    "static mut {}: {} = {};\n\n",
+                    name,
+                    self.generate_rust_type(typ),
+                    init_str
+                ));
+            }
+            // 预处理指令在 Rust 中没有直接对应，保留为注释
+            Declaration::Include(path) => {
+                result.push_str(&format!("// #include {}\n", path));
+            }
+            Declaration::Define { name, value } if value.is_empty() => {
+                result.push_str(&format!("// #define {}\n", name));
+            }
+            Declaration::Define { name, value } => {
+                result.push_str(&format!("// #define {} {}\n", name, value));
+            }
+            // Rust 中类型可以在定义之前使用，不需要前置声明
+            Declaration::ForwardDecl(_) => {}
+            // #pragma pack 影响其后结构体的布局，对应 #[repr(C, packed(N))]
+            Declaration::Pragma(text) => {
+                if let Some(pack) = pragma_pack(text) {
+                    self.pack.set(pack);
+                }
+                result.push_str(&format!("// #pragma {}\n", text));
+            }
+            Declaration::Comment(text) => {
+                result.push_str(&rust_comment(text, ""));
+                result.push('\n');
+            }
+        }
+        result
+    }
+}
+
+// 主体只有一个块语句时直接取块内的语句，避免输出双层花括号
+fn unwrap_block(stmts: &[Stmt]) -> &[Stmt] {
+    match stmts {
+        [Stmt::Block(inner)] => inner,
+        stmts => stmts,
+    }
+}
+
+// C 存储类说明符的输出前缀（带尾随空格）
+fn storage_prefix(storage: &Option<StorageClass>) -> &'static str {
+    match storage {
+        Some(StorageClass::Static) => "static ",
+        Some(StorageClass::Extern) => "extern ",
+        Some(StorageClass::Auto) => "auto ",
+        Some(StorageClass::Register) => "register ",
+        None => "",
+    }
+}
+
+// 解析 #pragma pack 指令：pack(N) 与 pack(push, N) 返回 Some(Some(N))，
+// pack()、pack(pop) 恢复默认对齐返回 Some(None)；不改变对齐的指令返回 None
+fn pragma_pack(text: &str) -> Option<Option<u32>> {
+    let args = text
+        .strip_prefix("pack")?
+        .trim()
+        .strip_prefix('(')?
+        .strip_suffix(')')?;
+    match args.rsplit(',').next().unwrap_or("").trim() {
+        "" | "pop" => Some(None),
+        n => n.parse().ok().map(Some),
+    }
+}
+
+// Rust 输出中按原名调用、需要 use libc::*; 的 C 标准库函数
+// （printf/puts 能翻译为 print! 宏时不算在内）
+const LIBC_FUNCTIONS: &[&str] = &[
+    "malloc", "calloc", "realloc", "free", "memcpy", "memmove", "memset", "memcmp", "strlen",
+    "strcmp", "strncmp", "strcpy", "strncpy", "strcat", "strchr", "strrchr", "strstr", "strdup",
+    "strndup", "strtol", "strtoul", "strtod", "atoi", "atof", "abs", "exit", "abort", "getenv",
+    "printf", "fprintf", "sprintf", "snprintf", "puts", "putchar", "fopen", "fclose", "fread",
+    "fwrite", "fgets", "fputs", "fgetc", "fputc", "fflush", "qsort",
+];
+
+// libc crate 中同名定义的 C 类型名
+const LIBC_TYPES: &[&str] = &[
+    "size_t",
+    "ssize_t",
+    "ptrdiff_t",
+    "intptr_t",
+    "uintptr_t",
+    "off_t",
+    "time_t",
+    "FILE",
+    "int8_t",
+    "int16_t",
+    "int32_t",
+    "int64_t",
+    "uint8_t",
+    "uint16_t",
+    "uint32_t",
+    "uint64_t",
+];
+
+// C 的块注释不能嵌套而 Rust 的可以：内部含有 /* 的块注释改写为逐行的 // 注释，
+// 续行前加上 indent
+fn rust_comment(text: &str, indent: &str) -> String {
+    match text.strip_prefix("/*").and_then(|t| t.strip_suffix("*/")) {
+        Some(inner) if inner.contains("/*") => inner
+            .lines()
+            .map(|line| format!("// {}", line.trim()).trim_end().to_string())
+            .collect::<Vec<_>>()
+            .join(&format!("\n{}", indent)),
+        _ => text.to_string(),
+    }
+}
+
+// 用逗号连接参数列表，可变参数函数在末尾加上 rest（C 的 ...）
+fn join_params(mut params: Vec<String>, rest: Option<&str>) -> String {
+    params.extend(rest.map(str::to_string));
+    params.join(", ")
+}
+
+// 把字面量内容转义为 Rust 源码形式，quote 为所在字面量的引号
+fn escape_rust_string(s: &str, quote: char) -> String {
+    let mut result = String::with_capacity(s.len());
+    for ch in s.chars() {
+        // Rust 字符串必须是合法 UTF-8，放不下单个高位字节，按 Latin-1 输出对应字符
+        let ch = char_raw_byte(ch).map_or(ch, char::from);
+        match ch {
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            '\0' => result.push_str("\\0"),
+            c if c == quote => {
+                result.push('\\');
+                result.push(c);
+            }
+            c if c.is_control() => result.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+// C 表达式的优先级，数值越大结合越紧
+const PREC_COMMA: u8 = 1;
+const PREC_ASSIGN: u8 = 2;
+const PREC_TERNARY: u8 = 3;
+const PREC_UNARY: u8 = 14;
+const PREC_POSTFIX: u8 = 15;
+const PREC_PRIMARY: u8 = 16;
+
+fn binary_precedence(op: &BinaryOp) -> u8 {
+    match op {
+        BinaryOp::Or => 4,
+        BinaryOp::And => 5,
+        BinaryOp::BitOr => 6,
+        BinaryOp::BitXor => 7,
+        BinaryOp::BitAnd => 8,
+        BinaryOp::Eq | BinaryOp::Ne => 9,
+        BinaryOp::Lt | BinaryOp::Gt | BinaryOp::Le | BinaryOp::Ge => 10,
+        BinaryOp::LeftShift | BinaryOp::RightShift => 11,
+        BinaryOp::Add | BinaryOp::Sub => 12,
+        BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => 13,
+        // 复合赋值在解析时已降级为普通赋值，这里按赋值处理
+        _ => PREC_ASSIGN,
+    }
+}
+
+fn c_precedence(expr: &Expr) -> u8 {
+    match expr {
+        Expr::Comma(_) => PREC_COMMA,
+        Expr::Assignment { .. } => PREC_ASSIGN,
+        Expr::Ternary { .. } => PREC_TERNARY,
+        Expr::Binary { op, .. } => binary_precedence(op),
+        Expr::Unary {
+            op: UnaryOp::PostIncrement | UnaryOp::PostDecrement,
+            ..
+        } => PREC_POSTFIX,
+        Expr::Unary { .. } | Expr::Cast { .. } | Expr::SizeOf(_) | Expr::SizeOfExpr(_) => {
+            PREC_UNARY
+        }
+        // 负数字面量输出时带负号，相当于一元表达式
+        Expr::IntLiteral(n) | Expr::IntLiteralSuffixed { value: n, .. } if *n < 0 => PREC_UNARY,
+        Expr::FloatLiteral(f) | Expr::FloatLiteralSuffixed { value: f, .. }
+            if f.is_sign_negative() =>
+        {
+            PREC_UNARY
+        }
+        Expr::Call { .. }
+        | Expr::CallExpr { .. }
+        | Expr::ArrayAccess { .. }
+        | Expr::MemberAccess { .. }
+        | Expr::PointerMemberAccess { .. }
+        | Expr::CompoundLiteral { .. } => PREC_POSTFIX,
+        _ => PREC_PRIMARY,
+    }
+}
+
+// 去掉类型外层的 const/volatile/restrict，返回其中是否有 const 以及内部类型
+fn strip_qualifiers(typ: &CType) -> (bool, &CType) {
+    match typ {
+        CType::Const(inner) => (true, strip_qualifiers(inner).1),
+        CType::Volatile(inner) | CType::Restrict(inner) => strip_qualifiers(inner),
+        _ => (false, typ),
+    }
+}
+
+// 类型中是否有指向数组或函数的指针，这样的返回类型需要把函数名写进声明符内部
+fn returns_nested_declarator(typ: &CType) -> bool {
+    match typ {
+        CType::Pointer(inner) => {
+            matches!(**inner, CType::Array { .. } | CType::Function { .. })
+                || returns_nested_declarator(inner)
+        }
+        CType::Const(inner) | CType::Volatile(inner) | CType::Restrict(inner) => {
+            returns_nested_declarator(inner)
+        }
+        _ => false,
+    }
+}
+
+// 翻译为 Rust 后结果已经是 bool 的表达式：比较、逻辑与或以及对它们的逻辑非
+fn is_rust_bool(expr: &Expr) -> bool {
+    match expr {
+        Expr::Binary { op, .. } => matches!(
+            op,
+            BinaryOp::Lt
+                | BinaryOp::Gt
+                | BinaryOp::Le
+                | BinaryOp::Ge
+                | BinaryOp::Eq
+                | BinaryOp::Ne
+                | BinaryOp::And
+                | BinaryOp::Or
+        ),
+        Expr::Unary {
+            op: UnaryOp::Not,
+            operand,
+        } => is_rust_bool(operand),
+        _ => false,
+    }
+}
+
+// 循环头前的标签，如 'outer: while ...
+fn label_prefix(label: Option<&str>) -> String {
+    label.map(|l| format!("{}: ", l)).unwrap_or_default()
+}
+
+// break/continue 后面的标签
+fn label_suffix(label: Option<&str>) -> String {
+    label.map(|l| format!(" {}", l)).unwrap_or_default()
+}
+
+// 去掉 case 末尾多余的 break，与 match 分支的生成保持一致
+fn case_body(stmts: &[Stmt]) -> &[Stmt] {
+    match stmts.last() {
+        Some(Stmt::Break(_)) => &stmts[..stmts.len() - 1],
+        _ => stmts,
+    }
+}
+
+// switch 的某个分支在末尾之外还有 break，翻译为 match 时需要带标签的块
+fn switch_needs_label(cases: &[SwitchCase]) -> bool {
+    cases.iter().any(|case| {
+        let mut finder = SwitchBreakFinder { found: false };
+        walk_stmts(&mut finder, case_body(&case.stmts));
+        finder.found
+    })
+}
+
+// 查找属于当前 switch 的 break，不进入嵌套的循环和 switch
+struct SwitchBreakFinder {
+    found: bool,
+}
+
+impl Visitor for SwitchBreakFinder {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::While { .. } | Stmt::DoWhile { .. } | Stmt::For { .. } | Stmt::Switch { .. } => {}
+            Stmt::Break(_) => self.found = true,
+            _ => walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_type(&mut self, _typ: &CType) {}
+}
+
+// 循环体中是否有 continue 位于带标签的 switch 块内，此时 continue 必须写明循环标签
+fn loop_needs_label(body: &[Stmt]) -> bool {
+    let mut finder = LabeledContinueFinder {
+        labeled_switches: 0,
+        found: false,
+    };
+    walk_stmts(&mut finder, body);
+    finder.found
+}
+
+struct LabeledContinueFinder {
+    labeled_switches: usize,
+    found: bool,
+}
+
+impl Visitor for LabeledContinueFinder {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::While { .. } | Stmt::DoWhile { .. } | Stmt::For { .. } => {}
+            Stmt::Switch { cases, .. } => {
+                let labeled = usize::from(switch_needs_label(cases));
+                self.labeled_switches += labeled;
+                walk_stmt(self, stmt);
+                self.labeled_switches -= labeled;
+            }
+            Stmt::Continue(_) => self.found |= self.labeled_switches > 0,
+            _ => walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_type(&mut self, _typ: &CType) {}
+}
+
+// 指针类型，忽略 const 等限定符
+fn is_pointer_type(typ: &CType) -> bool {
+    match typ {
+        CType::Pointer(_) => true,
+        CType::Const(inner) | CType::Volatile(inner) | CType::Restrict(inner) => {
+            is_pointer_type(inner)
+        }
+        _ => false,
+    }
+}
+
+// 不需要符号表就能确定的表达式类型
+fn obvious_type(expr: &Expr) -> Option<CType> {
+    match expr {
+        // 字符常量在 C 中是 int
+        Expr::IntLiteral(_) | Expr::CharLiteral(_) => Some(CType::Int),
+        Expr::IntLiteralSuffixed {
+            unsigned,
+            long_count,
+            ..
+        } => Some(match (unsigned, long_count) {
+            (false, 0) => CType::Int,
+            (true, 0) => CType::UnsignedInt,
+            (false, 1) => CType::Long,
+            (true, 1) => CType::UnsignedLong,
+            (false, _) => CType::LongLong,
+            (true, _) => CType::UnsignedLongLong,
+        }),
+        Expr::FloatLiteral(_) => Some(CType::Double),
+        Expr::FloatLiteralSuffixed { suffix, .. } => Some(match suffix {
+            'f' | 'F' => CType::Float,
+            _ => CType::LongDouble,
+        }),
+        Expr::StringLiteral(_) => Some(CType::Pointer(Box::new(CType::Char))),
+        Expr::Cast { typ, .. } | Expr::CompoundLiteral { typ, .. } => Some(typ.clone()),
+        Expr::SizeOf(_) | Expr::SizeOfExpr(_) => Some(CType::UnsignedLong),
+        _ => None,
+    }
+}
+
+// 拼接前缀运算符与操作数；两者相邻字符会合成另一个 token 时（如 - 与 -x 合成 --x）插入空格
+fn join_prefix_op(op: &str, operand: &str) -> String {
+    match (op.chars().last(), operand.chars().next()) {
+        (Some(a), Some(b)) if a == b && matches!(a, '-' | '+' | '&') => {
+            format!("{} {}", op, operand)
+        }
+        _ => format!("{}{}", op, operand),
+    }
+}
+
+// 查找满足 pred 的表达式；pred 返回 Some 时即为该表达式的结果，不再深入其子表达式。
+// nested 为 false 时只检查语句自身的表达式，不进入子语句
+struct ExprFinder<'a> {
+    pred: &'a dyn Fn(&Expr) -> Option<bool>,
+    nested: bool,
+    found: bool,
+}
+
+impl Visitor for ExprFinder<'_> {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        if self.nested && !self.found {
+            walk_stmt(self, stmt);
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        if self.found {
+            return;
+        }
+        match (self.pred)(expr) {
+            Some(result) => self.found = result,
+            None => walk_expr(self, expr),
+        }
+    }
+
+    // 类型中的表达式（数组大小、typeof）不会被求值，不参与检查
+    fn visit_type(&mut self, _typ: &CType) {}
+}
+
+impl<'a> ExprFinder<'a> {
+    fn new(pred: &'a dyn Fn(&Expr) -> Option<bool>) -> Self {
+        ExprFinder {
+            pred,
+            nested: true,
+            found: false,
+        }
+    }
+}
+
+// 语句自身的表达式中是否有作为值使用的自增自减。语句位置的 i++（包括逗号表达式的各项）
+// 会直接写成 i += 1，不算在内；else if 链的条件也属于这条语句
+fn has_value_increment(stmt: &Stmt) -> bool {
+    let is_step = |e: &Expr| {
+        matches!(
+            e,
+            Expr::Unary {
+                op: UnaryOp::PreIncrement
+                    | UnaryOp::PreDecrement
+                    | UnaryOp::PostIncrement
+                    | UnaryOp::PostDecrement,
+                ..
+            }
+        )
+    };
+    let contains_step = |e: &Expr| expr_any(e, &|e| is_step(e).then_some(true));
+    let statement_level = |e: &Expr| match e {
+        Expr::Unary { operand, .. } if is_step(e) => contains_step(operand),
+        _ => contains_step(e),
+    };
+    let own = match stmt {
+        Stmt::Expr(Expr::Comma(items)) => items.iter().any(statement_level),
+        Stmt::Expr(e) => statement_level(e),
+        Stmt::For { cond, update, .. } => {
+            cond.iter().any(contains_step) || update.iter().any(statement_level)
+        }
+        _ => {
+            let pred = |e: &Expr| Some(contains_step(e));
+            let mut finder = ExprFinder {
+                nested: false,
+                ..ExprFinder::new(&pred)
+            };
+            walk_stmt(&mut finder, stmt);
+            finder.found
+        }
+    };
+    own || match stmt {
+        Stmt::If {
+            else_block: Some(else_block),
+            ..
+        } => {
+            matches!(else_block.as_slice(), [else_if @ Stmt::If { .. }] if has_value_increment(else_if))
+        }
+        _ => false,
+    }
+}
+
+// 语句中是否有表达式满足 pred；pred 返回 Some 时不再深入该表达式
+fn stmts_any(stmts: &[Stmt], pred: &dyn Fn(&Expr) -> Option<bool>) -> bool {
+    let mut finder = ExprFinder::new(pred);
+    walk_stmts(&mut finder, stmts);
+    finder.found
+}
+
+fn expr_any(expr: &Expr, pred: &dyn Fn(&Expr) -> Option<bool>) -> bool {
+    let mut finder = ExprFinder::new(pred);
+    finder.visit_expr(expr);
+    finder.found
+}
+
+// 语句中是否解引用了原始指针（*p 或 p->x），safe_refs 中的名字是已翻译为 Rust 引用的变量
+fn derefs_raw_pointer(stmts: &[Stmt], safe_refs: &[&str]) -> bool {
+    let is_ref = |e: &Expr| matches!(e, Expr::Identifier(n) if safe_refs.contains(&n.as_str()));
+    stmts_any(stmts, &|expr| match expr {
+        Expr::Unary {
+            op: UnaryOp::Deref,
+            operand,
+        }
+        | Expr::PointerMemberAccess {
+            object: operand, ..
+        } if !is_ref(operand) => Some(true),
+        _ => None,
+    })
+}
+
+// 语句中是否会修改变量 name（赋值、自增自减或取地址）
+fn stmts_modify(stmts: &[Stmt], name: &str) -> bool {
+    stmts_any(stmts, &|expr| modifies_directly(expr, name).then_some(true))
+}
+
+fn modifies_directly(expr: &Expr, name: &str) -> bool {
+    let is_var = |e: &Expr| matches!(e, Expr::Identifier(n) if n == name);
+    match expr {
+        Expr::Assignment { target, .. } => is_var(target),
+        Expr::Unary { op, operand } => {
+            matches!(
+                op,
+                UnaryOp::PreIncrement
+                    | UnaryOp::PreDecrement
+                    | UnaryOp::PostIncrement
+                    | UnaryOp::PostDecrement
+                    | UnaryOp::AddressOf
+            ) && is_var(operand)
+        }
+        _ => false,
+    }
+}
+
+// 指针变量 name 是否只通过 *name 或 name->field 使用，
+// 这样的指针参数可以安全地换成 Rust 引用
+fn only_dereferenced(stmts: &[Stmt], name: &str) -> bool {
+    let is_var = |e: &Expr| matches!(e, Expr::Identifier(n) if n == name);
+    !stmts_any(stmts, &|expr| match expr {
+        Expr::Unary {
+            op: UnaryOp::Deref,
+            operand,
+        } if is_var(operand) => Some(false),
+        Expr::PointerMemberAccess { object, .. } if is_var(object) => Some(false),
+        Expr::Identifier(n) if n == name => Some(true),
+        _ => None,
+    })
+}
+
+// 依次计算枚举各项的值：显式值按常量表达式求值，省略时为前一项加一。
+// 任一项无法在编译期求值时返回 None
+fn enum_values(enum_def: &EnumDef) -> Option<Vec<i128>> {
+    let mut known: Vec<(&str, i128)> = Vec::new();
+    let mut next = 0i128;
+    for variant in &enum_def.variants {
+        let value = match &variant.value {
+            Some(expr) => const_int_value(expr, &|name| {
+                known.iter().find(|(n, _)| *n == name).map(|(_, v)| *v)
+            })?,
+            None => next,
+        };
+        known.push((&variant.name, value));
+        next = value.checked_add(1)?;
+    }
+    Some(known.into_iter().map(|(_, value)| value).collect())
 }
@@ -1,3 +1,6 @@
+use crate::ast::{escape_c_string, raw_byte_char};
+use std::fmt;
+
 /// 简单的词法分析器
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
@@ -7,6 +10,7 @@ pub enum Token {
     Float,
     Double,
     Void,
+    Bool,
     Long,
     Short,
     Unsigned,
@@ -17,10 +21,16 @@ pub enum Token {
     Typedef,
     Const,
     Volatile,
+    Restrict,
+    Atomic,
+    Alignas,
+    Typeof,
+    Inline,
     Static,
     Extern,
     Auto,
     Register,
+    ThreadLocal,
 
     // 关键字 - 控制流
     If,
@@ -38,18 +48,32 @@ pub enum Token {
 
     // 关键字 - 其他
     Sizeof,
+    Asm,
 
     // 预处理器
     Include(String),
     Define(String, String),
+    // #pragma 之后的整行内容，如 once、pack(1)
+    Pragma(String),
     Ifdef,
     Ifndef,
     Endif,
 
     // 标识符和字面量
     Identifier(String),
-    IntLiteral(i32),
+    IntLiteral(i128),
+    // 带 U/L/LL 后缀的整数字面量，记录符号与长度以便后续选择目标类型
+    IntLiteralSuffixed {
+        value: i128,
+        unsigned: bool,
+        long_count: u8,
+    },
     FloatLiteral(f64),
+    // 带 f/l 后缀的浮点字面量，suffix 统一为小写 'f' 或 'l'
+    FloatLiteralSuffixed {
+        value: f64,
+        suffix: char,
+    },
     CharLiteral(char),
     StringLiteral(String),
 
@@ -116,12 +140,144 @@ pub enum Token {
     Comma,
 
     // 特殊
+    // 无法识别的字符，保留下来交给语法分析器报错
+    Unknown(char),
+    // 词法错误（如不完整的数字字面量），携带错误信息
+    Error(String),
     Eof,
 }
 
+impl fmt::Display for Token {
+    // 以 C 源码中的写法显示 token，用于错误信息
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Token::Int => "int",
+            Token::Char => "char",
+            Token::Float => "float",
+            Token::Double => "double",
+            Token::Void => "void",
+            Token::Bool => "_Bool",
+            Token::Long => "long",
+            Token::Short => "short",
+            Token::Unsigned => "unsigned",
+            Token::Signed => "signed",
+            Token::Struct => "struct",
+            Token::Union => "union",
+            Token::Enum => "enum",
+            Token::Typedef => "typedef",
+            Token::Const => "const",
+            Token::Volatile => "volatile",
+            Token::Restrict => "restrict",
+            Token::Atomic => "_Atomic",
+            Token::Alignas => "_Alignas",
+            Token::Typeof => "typeof",
+            Token::Inline => "inline",
+            Token::Static => "static",
+            Token::Extern => "extern",
+            Token::Auto => "auto",
+            Token::Register => "register",
+            Token::ThreadLocal => "_Thread_local",
+            Token::If => "if",
+            Token::Else => "else",
+            Token::While => "while",
+            Token::Do => "do",
+            Token::For => "for",
+            Token::Switch => "switch",
+            Token::Case => "case",
+            Token::Default => "default",
+            Token::Break => "break",
+            Token::Continue => "continue",
+            Token::Return => "return",
+            Token::Goto => "goto",
+            Token::Sizeof => "sizeof",
+            Token::Asm => "asm",
+            Token::Include(path) => return write!(f, "#include {}", path),
+            Token::Define(name, value) => return write!(f, "#define {} {}", name, value),
+            Token::Pragma(text) => return write!(f, "#pragma {}", text),
+            Token::Ifdef => "#ifdef",
+            Token::Ifndef => "#ifndef",
+            Token::Endif => "#endif",
+            Token::Identifier(name) => name,
+            Token::IntLiteral(n) => return write!(f, "{}", n),
+            Token::IntLiteralSuffixed { value, .. } => return write!(f, "{}", value),
+            Token::FloatLiteral(v) => return write!(f, "{:?}", v),
+            Token::FloatLiteralSuffixed { value, suffix } => {
+                return write!(f, "{:?}{}", value, suffix)
+            }
+            Token::CharLiteral(c) => {
+                return write!(f, "'{}'", escape_c_string(&c.to_string(), '\''))
+            }
+            Token::StringLiteral(s) => return write!(f, "\"{}\"", escape_c_string(s, '"')),
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Star => "*",
+            Token::Slash => "/",
+            Token::Percent => "%",
+            Token::BitAnd | Token::Ampersand => "&",
+            Token::BitOr => "|",
+            Token::BitXor => "^",
+            Token::BitNot => "~",
+            Token::LeftShift => "<<",
+            Token::RightShift => ">>",
+            Token::Assign => "=",
+            Token::PlusAssign => "+=",
+            Token::MinusAssign => "-=",
+            Token::StarAssign => "*=",
+            Token::SlashAssign => "/=",
+            Token::PercentAssign => "%=",
+            Token::AndAssign => "&=",
+            Token::OrAssign => "|=",
+            Token::XorAssign => "^=",
+            Token::LeftShiftAssign => "<<=",
+            Token::RightShiftAssign => ">>=",
+            Token::Eq => "==",
+            Token::Ne => "!=",
+            Token::Lt => "<",
+            Token::Gt => ">",
+            Token::Le => "<=",
+            Token::Ge => ">=",
+            Token::And => "&&",
+            Token::Or => "||",
+            Token::Not => "!",
+            Token::Increment => "++",
+            Token::Decrement => "--",
+            Token::Arrow => "->",
+            Token::Dot => ".",
+            Token::Question => "?",
+            Token::Colon => ":",
+            Token::Ellipsis => "...",
+            Token::LParen => "(",
+            Token::RParen => ")",
+            Token::LBrace => "{",
+            Token::RBrace => "}",
+            Token::LBracket => "[",
+            Token::RBracket => "]",
+            Token::Semicolon => ";",
+            Token::Comma => ",",
+            Token::Unknown(c) => return write!(f, "{}", c),
+            Token::Error(msg) => msg,
+            Token::Eof => "end of input",
+        };
+        f.write_str(text)
+    }
+}
+
+/// 带源码位置（1 起始的行号与列号）的 token
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub line: usize,
+    pub col: usize,
+}
+
 pub struct Lexer {
     input: Vec<char>,
     pos: usize,
+    line: usize,
+    col: usize,
+    // 是否保留注释文本；保留的注释由 take_comments 取出
+    keep_comments: bool,
+    comments: Vec<String>,
 }
 
 impl Lexer {
@@ -129,9 +285,24 @@ impl Lexer {
         Lexer {
             input: input.chars().collect(),
             pos: 0,
+            line: 1,
+            col: 1,
+            keep_comments: false,
+            comments: Vec::new(),
         }
     }
 
+    // 开启后跳过的注释会按原文（含 // 或 /* */）记录下来
+    pub fn with_comments(mut self, keep: bool) -> Self {
+        self.keep_comments = keep;
+        self
+    }
+
+    // 取出自上次调用以来跳过的注释
+    pub fn take_comments(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.comments)
+    }
+
     fn current_char(&self) -> Option<char> {
         if self.pos < self.input.len() {
             Some(self.input[self.pos])
@@ -141,21 +312,24 @@ impl Lexer {
     }
 
     fn advance(&mut self) {
+        if self.current_char() == Some('\n') {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
         self.pos += 1;
     }
 
     fn skip_whitespace(&mut self) {
-        loop {
-            let ch = match self.current_char() {
-                Some(c) => c,
-                None => break,
-            };
+        while let Some(ch) = self.current_char() {
             if ch.is_whitespace() {
                 self.advance();
                 continue;
             }
             // line comment: // ...\n
             if ch == '/' {
+                let start = self.pos;
                 match self.peek_char(1) {
                     Some('/') => {
                         // skip till end of line
@@ -167,6 +341,7 @@ impl Lexer {
                             }
                             self.advance();
                         }
+                        self.record_comment(start);
                         continue;
                     }
                     Some('*') => {
@@ -181,6 +356,7 @@ impl Lexer {
                             }
                             self.advance();
                         }
+                        self.record_comment(start);
                         continue;
                     }
                     _ => {}
@@ -190,11 +366,18 @@ impl Lexer {
         }
     }
 
+    fn record_comment(&mut self, start: usize) {
+        if self.keep_comments {
+            let text: String = self.input[start..self.pos].iter().collect();
+            self.comments.push(text.trim_end().to_string());
+        }
+    }
+
     fn read_number(&mut self) -> Token {
         let mut s = String::new();
         let mut is_float = false;
 
-        // 处理进制前缀 0x / 0X (十六进制)；0 开头的八进制（简化为十进制解析）
+        // 处理进制前缀 0x / 0X (十六进制)；0 开头的整数按八进制解析
         if self.current_char() == Some('0') {
             s.push('0');
             self.advance();
@@ -210,9 +393,15 @@ impl Lexer {
                             break;
                         }
                     }
+                    // 十六进制浮点数，如 0x1.8p3
+                    if matches!(self.current_char(), Some('.' | 'p' | 'P')) {
+                        return self.read_hex_float(s);
+                    }
+                    if s.len() == 2 {
+                        return Token::Error(format!("Invalid hex literal '{}': no digits", s));
+                    }
                     // 将十六进制字符串转换为整数
-                    let val = i64::from_str_radix(&s[2..], 16).unwrap_or(0) as i32;
-                    return Token::IntLiteral(val);
+                    return self.finish_int(&s[2..], 16);
                 } else if ch1 == 'b' || ch1 == 'B' {
                     // 二进制字面量 0b...
                     self.advance();
@@ -225,10 +414,15 @@ impl Lexer {
                             break;
                         }
                     }
-                    let val = i64::from_str_radix(&bits, 2).unwrap_or(0) as i32;
-                    return Token::IntLiteral(val);
+                    if bits.is_empty() {
+                        return Token::Error(format!(
+                            "Invalid binary literal '0{}': no digits",
+                            ch1
+                        ));
+                    }
+                    return self.finish_int(&bits, 2);
                 } else {
-                    // 0 开头的数字，继续按十进制/浮点解析（简单处理）
+                    // 0 开头的数字：整数按八进制，含小数点的仍按十进制浮点
                 }
             }
         }
@@ -246,10 +440,157 @@ impl Lexer {
             }
         }
 
+        // 科学计数法指数部分：e/E 后接可选符号与数字，如 1.5e10、2.0E-3、1e9
+        if matches!(self.current_char(), Some('e') | Some('E')) {
+            is_float = true;
+            s.push('e');
+            self.advance();
+            if let Some(sign @ ('+' | '-')) = self.current_char() {
+                s.push(sign);
+                self.advance();
+            }
+            let mut has_digits = false;
+            while let Some(ch) = self.current_char() {
+                if ch.is_ascii_digit() {
+                    s.push(ch);
+                    self.advance();
+                    has_digits = true;
+                } else {
+                    break;
+                }
+            }
+            if !has_digits {
+                return Token::Error(format!(
+                    "Invalid float literal '{}': missing exponent digits",
+                    s
+                ));
+            }
+        }
+
         if is_float {
-            Token::FloatLiteral(s.parse().unwrap_or(0.0))
+            self.finish_float(s.parse().unwrap_or(0.0))
+        } else if s.len() > 1 && s.starts_with('0') {
+            // 八进制字面量，如 0777
+            self.finish_int(&s[1..], 8)
         } else {
-            Token::IntLiteral(s.parse().unwrap_or(0))
+            self.finish_int(&s, 10)
+        }
+    }
+
+    // 读取十六进制浮点数 0xH.Hp±D 的小数部分与二进制指数，s 为已读取的 0x 与整数部分。
+    // 值为尾数乘以 2 的指数次幂，指数部分不可省略
+    fn read_hex_float(&mut self, mut s: String) -> Token {
+        let mut value = 0.0f64;
+        for d in s[2..].chars().filter_map(|c| c.to_digit(16)) {
+            value = value * 16.0 + d as f64;
+        }
+        let mut has_digits = s.len() > 2;
+        if self.current_char() == Some('.') {
+            s.push('.');
+            self.advance();
+            let mut scale = 1.0 / 16.0;
+            while let Some(ch) = self.current_char().filter(|c| c.is_ascii_hexdigit()) {
+                s.push(ch);
+                self.advance();
+                value += ch.to_digit(16).unwrap_or(0) as f64 * scale;
+                scale /= 16.0;
+                has_digits = true;
+            }
+        }
+        if !matches!(self.current_char(), Some('p' | 'P')) {
+            if !has_digits {
+                return Token::Error(format!("Invalid hex float literal '{}': no digits", s));
+            }
+            return Token::Error(format!(
+                "Invalid hex float literal '{}': missing binary exponent",
+                s
+            ));
+        }
+        s.push('p');
+        self.advance();
+        let mut exp = String::new();
+        if let Some(sign @ ('+' | '-')) = self.current_char() {
+            exp.push(sign);
+            self.advance();
+        }
+        while let Some(ch) = self.current_char().filter(|c| c.is_ascii_digit()) {
+            exp.push(ch);
+            self.advance();
+        }
+        if !has_digits {
+            return Token::Error(format!(
+                "Invalid hex float literal '{}{}': no digits",
+                s, exp
+            ));
+        }
+        let Ok(exp) = exp.parse::<i32>() else {
+            return Token::Error(format!(
+                "Invalid hex float literal '{}{}': missing exponent digits",
+                s, exp
+            ));
+        };
+        self.finish_float(value * 2f64.powi(exp))
+    }
+
+    // 读取浮点后缀：f/F 表示 float，l/L 表示 long double
+    fn finish_float(&mut self, value: f64) -> Token {
+        match self.current_char() {
+            Some(suffix @ ('f' | 'F' | 'l' | 'L')) => {
+                self.advance();
+                Token::FloatLiteralSuffixed {
+                    value,
+                    suffix: suffix.to_ascii_lowercase(),
+                }
+            }
+            _ => Token::FloatLiteral(value),
+        }
+    }
+
+    // 按进制转换整数字面量并读取后缀。
+    // 使用 i128 存储，足以容纳 unsigned long long 的全部取值（如 0xcbf29ce484222325）
+    fn finish_int(&mut self, digits: &str, radix: u32) -> Token {
+        match u64::from_str_radix(digits, radix) {
+            Ok(val) => self.read_int_suffix(val as i128),
+            Err(_) => {
+                // 先吃掉可能存在的后缀，避免残留字符变成标识符
+                self.read_int_suffix(0);
+                Token::Error(format!(
+                    "Invalid or out-of-range integer literal '{}'",
+                    digits
+                ))
+            }
+        }
+    }
+
+    // 读取整数后缀 u/U 与 l/L/ll/LL 的任意合法组合（如 10U、0L、100ULL、7LLU）
+    fn read_int_suffix(&mut self, value: i128) -> Token {
+        let mut suffix = String::new();
+        while let Some(ch) = self.current_char() {
+            if matches!(ch, 'u' | 'U' | 'l' | 'L') {
+                suffix.push(ch);
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        if suffix.is_empty() {
+            return Token::IntLiteral(value);
+        }
+
+        let unsigned_count = suffix.chars().filter(|c| matches!(c, 'u' | 'U')).count();
+        let longs: String = suffix.chars().filter(|c| matches!(c, 'l' | 'L')).collect();
+        // l 与 L 不能混用（lL 非法），U 只能出现在长度后缀的一端
+        let valid_longs = matches!(longs.as_str(), "" | "l" | "L" | "ll" | "LL");
+        let valid_order =
+            !suffix.starts_with(['l', 'L']) || !suffix.ends_with(['l', 'L']) || unsigned_count == 0;
+        if unsigned_count > 1 || !valid_longs || !valid_order {
+            return Token::Error(format!("Invalid integer suffix '{}'", suffix));
+        }
+
+        Token::IntLiteralSuffixed {
+            value,
+            unsigned: unsigned_count == 1,
+            long_count: longs.len() as u8,
         }
     }
 
@@ -272,6 +613,8 @@ impl Lexer {
             "float" => Token::Float,
             "double" => Token::Double,
             "void" => Token::Void,
+            // C99 的 _Bool 与 C23 的 bool 关键字
+            "_Bool" | "bool" => Token::Bool,
             "long" => Token::Long,
             "short" => Token::Short,
             "unsigned" => Token::Unsigned,
@@ -281,11 +624,18 @@ impl Lexer {
             "enum" => Token::Enum,
             "typedef" => Token::Typedef,
             "const" => Token::Const,
-            "volatile" => Token::Volatile,
+            "volatile" | "__volatile__" | "__volatile" => Token::Volatile,
+            // GCC 的 __restrict / __inline 等拼写与标准关键字等价
+            "restrict" | "__restrict" | "__restrict__" => Token::Restrict,
+            "_Atomic" => Token::Atomic,
+            "_Alignas" | "alignas" => Token::Alignas,
+            "typeof" | "__typeof__" | "__typeof" => Token::Typeof,
+            "inline" | "__inline" | "__inline__" => Token::Inline,
             "static" => Token::Static,
             "extern" => Token::Extern,
             "auto" => Token::Auto,
             "register" => Token::Register,
+            "_Thread_local" | "thread_local" | "__thread" => Token::ThreadLocal,
 
             // 控制流关键字
             "if" => Token::If,
@@ -303,30 +653,77 @@ impl Lexer {
 
             // 其他关键字
             "sizeof" => Token::Sizeof,
+            "asm" | "__asm__" | "__asm" => Token::Asm,
 
             _ => Token::Identifier(ident),
         }
     }
 
+    // 解析反斜杠之后的转义序列，返回对应的实际字符
+    fn read_escape(&mut self) -> Option<char> {
+        let escaped = self.current_char()?;
+        self.advance();
+        let ch = match escaped {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'v' => '\x0b',
+            'f' => '\x0c',
+            'a' => '\x07',
+            'b' => '\x08',
+            '\\' => '\\',
+            '\'' => '\'',
+            '"' => '"',
+            // 十六进制转义 \xHH...：读取所有后续十六进制数字
+            'x' => {
+                let mut value: u32 = 0;
+                while let Some(d) = self.current_char().and_then(|c| c.to_digit(16)) {
+                    value = value.saturating_mul(16).saturating_add(d);
+                    self.advance();
+                }
+                char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER)
+            }
+            // 八进制转义 \OOO：最多三位（\0 也走这里）
+            '0'..='7' => {
+                let mut value = escaped.to_digit(8).unwrap_or(0);
+                for _ in 0..2 {
+                    match self.current_char().and_then(|c| c.to_digit(8)) {
+                        Some(d) => {
+                            value = value * 8 + d;
+                            self.advance();
+                        }
+                        None => break,
+                    }
+                }
+                char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER)
+            }
+            _ => escaped,
+        };
+        Some(ch)
+    }
+
     fn read_string(&mut self) -> Token {
         self.advance(); // skip opening "
         let mut string = String::new();
 
-        while let Some(ch) = self.current_char() {
+        // 到行尾或文件末尾仍没有闭合引号时报错，不吞掉后面的代码
+        loop {
+            let Some(ch) = self.current_char().filter(|&c| c != '\n') else {
+                return Token::Error("Unterminated string literal".to_string());
+            };
             if ch == '"' {
                 self.advance();
                 break;
             } else if ch == '\\' {
                 self.advance();
-                if let Some(escaped) = self.current_char() {
-                    match escaped {
-                        'n' => string.push('\n'),
-                        't' => string.push('\t'),
-                        '\\' => string.push('\\'),
-                        '"' => string.push('"'),
-                        _ => string.push(escaped),
+                let numeric = matches!(self.current_char(), Some('x' | '0'..='7'));
+                match self.read_escape() {
+                    // 数值转义写的是字节，不是 Unicode 码点
+                    Some(escaped) if numeric && (0x80..=0xff).contains(&(escaped as u32)) => {
+                        string.push(raw_byte_char(escaped as u8))
                     }
-                    self.advance();
+                    Some(escaped) => string.push(escaped),
+                    None => {}
                 }
             } else {
                 string.push(ch);
@@ -339,12 +736,175 @@ impl Lexer {
 
     fn read_char(&mut self) -> Token {
         self.advance(); // skip opening '
-        let ch = self.current_char().unwrap_or('\0');
+        let ch = match self.current_char() {
+            Some('\'') => {
+                self.advance();
+                return Token::Error("Empty character literal".to_string());
+            }
+            Some('\\') => {
+                self.advance();
+                self.read_escape()
+            }
+            Some(c) => {
+                self.advance();
+                Some(c)
+            }
+            None => None,
+        };
+
+        match (ch, self.current_char()) {
+            (Some(c), Some('\'')) => {
+                self.advance();
+                Token::CharLiteral(c)
+            }
+            (Some(_), Some(c2)) if c2 != '\n' => {
+                // 多字符常量（如 'ab'）：跳到闭合引号后报错
+                while let Some(c3) = self.current_char() {
+                    if c3 == '\'' || c3 == '\n' {
+                        break;
+                    }
+                    self.advance();
+                }
+                if self.current_char() == Some('\'') {
+                    self.advance();
+                }
+                Token::Error("Multi-character character literal".to_string())
+            }
+            _ => Token::Error("Unterminated character literal".to_string()),
+        }
+    }
+
+    // 跳过同一行内的空格和制表符
+    fn skip_inline_spaces(&mut self) {
+        while matches!(self.current_char(), Some(' ' | '\t')) {
+            self.advance();
+        }
+    }
+
+    // 跳到当前行末尾（不消耗换行符）
+    fn skip_to_line_end(&mut self) {
+        while let Some(ch) = self.current_char() {
+            if ch == '\n' {
+                break;
+            }
+            self.advance();
+        }
+    }
+
+    // 读取一个由字母、数字和下划线组成的单词（可能为空）
+    fn read_word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(ch) = self
+            .current_char()
+            .filter(|c| c.is_alphanumeric() || *c == '_')
+        {
+            word.push(ch);
+            self.advance();
+        }
+        word
+    }
+
+    // 读取到逻辑行末尾（不消耗换行符），反斜杠续行替换为一个空格；
+    // 字符串和字符字面量之外的 // 注释被丢弃
+    fn read_logical_line(&mut self) -> String {
+        let mut line = String::new();
+        let mut quote: Option<char> = None;
+        while let Some(ch) = self.current_char() {
+            match ch {
+                '\n' => break,
+                '\\' if self.peek_char(1) == Some('\n') => {
+                    self.advance();
+                    self.advance();
+                    line.push(' ');
+                    continue;
+                }
+                '\\' if quote.is_some() => {
+                    // 字面量中的转义字符原样保留
+                    line.push(ch);
+                    self.advance();
+                    if let Some(escaped) = self.current_char().filter(|c| *c != '\n') {
+                        line.push(escaped);
+                        self.advance();
+                    }
+                    continue;
+                }
+                '"' | '\'' => match quote {
+                    None => quote = Some(ch),
+                    Some(q) if q == ch => quote = None,
+                    Some(_) => {}
+                },
+                '/' if quote.is_none() && self.peek_char(1) == Some('/') => {
+                    self.skip_to_line_end();
+                    break;
+                }
+                _ => {}
+            }
+            line.push(ch);
+            self.advance();
+        }
+        line.trim().to_string()
+    }
+
+    // 预处理指令：识别 #include、对象式 #define 与 #pragma；函数式宏整行跳过，
+    // 其余的 '#' 仍作为未知字符交给语法分析报错
+    fn read_directive(&mut self) -> Token {
+        let start = (self.pos, self.line, self.col);
+        self.advance(); // skip #
+        self.skip_inline_spaces();
+        match self.read_word().as_str() {
+            "include" => self.read_include(),
+            "define" => self.read_define(),
+            "pragma" => Token::Pragma(self.read_logical_line()),
+            _ => {
+                (self.pos, self.line, self.col) = start;
+                self.advance();
+                Token::Unknown('#')
+            }
+        }
+    }
+
+    fn read_define(&mut self) -> Token {
+        self.skip_inline_spaces();
+        let name = self.read_word();
+        if name.is_empty() {
+            self.read_logical_line();
+            return Token::Error("Expected macro name after #define".to_string());
+        }
+        // 名字后紧跟 '(' 的是函数式宏，暂不支持，跳过整条指令
+        if self.current_char() == Some('(') {
+            self.read_logical_line();
+            return self.next_token();
+        }
+        let value = self.read_logical_line();
+        Token::Define(name, value)
+    }
+
+    fn read_include(&mut self) -> Token {
+        self.skip_inline_spaces();
+        // 路径保留两侧的 <> 或 ""，便于原样输出
+        let close = match self.current_char() {
+            Some('<') => '>',
+            Some('"') => '"',
+            _ => {
+                self.skip_to_line_end();
+                return Token::Error("Expected <file> or \"file\" after #include".to_string());
+            }
+        };
+        let mut path = String::new();
+        path.push(self.current_char().unwrap_or_default());
         self.advance();
-        if self.current_char() == Some('\'') {
+        while let Some(ch) = self.current_char() {
+            if ch == '\n' {
+                break;
+            }
+            path.push(ch);
             self.advance();
+            if ch == close {
+                self.skip_to_line_end();
+                return Token::Include(path);
+            }
         }
-        Token::CharLiteral(ch)
+        Token::Error("Unterminated #include path".to_string())
     }
 
     fn peek_char(&self, offset: usize) -> Option<char> {
@@ -362,7 +922,10 @@ impl Lexer {
         match self.current_char() {
             None => Token::Eof,
             Some(ch) => {
-                if ch.is_numeric() {
+                if ch.is_ascii_digit()
+                    || (ch == '.' && self.peek_char(1).is_some_and(|c| c.is_ascii_digit()))
+                {
+                    // 数字或以小数点开头的浮点数（如 .5）
                     self.read_number()
                 } else if ch.is_alphabetic() || ch == '_' {
                     self.read_identifier()
@@ -579,9 +1142,10 @@ impl Lexer {
                         }
                         '"' => self.read_string(),
                         '\'' => self.read_char(),
+                        '#' => self.read_directive(),
                         _ => {
                             self.advance();
-                            Token::Eof
+                            Token::Unknown(ch)
                         }
                     }
                 }
@@ -589,16 +1153,42 @@ impl Lexer {
         }
     }
 
-    pub fn tokenize(&mut self) -> Vec<Token> {
+    // 读取下一个 token 并记录其起始位置
+    pub fn next_spanned(&mut self) -> SpannedToken {
+        self.skip_whitespace();
+        let (line, col) = (self.line, self.col);
+        let token = self.next_token();
+        SpannedToken { token, line, col }
+    }
+
+    pub fn tokenize_spanned(&mut self) -> Vec<SpannedToken> {
         let mut tokens = Vec::new();
         loop {
-            let token = self.next_token();
-            if token == Token::Eof {
-                tokens.push(token);
+            let spanned = self.next_spanned();
+            let is_eof = spanned.token == Token::Eof;
+            tokens.push(spanned);
+            if is_eof {
                 break;
             }
-            tokens.push(token);
         }
         tokens
     }
+
+    pub fn tokenize(&mut self) -> Vec<Token> {
+        let mut tokens: Vec<Token> = self.by_ref().collect();
+        tokens.push(Token::Eof);
+        tokens
+    }
+}
+
+// 以迭代器方式逐个产出 token，读到 EOF 时结束（不产出 Eof 本身）
+impl Iterator for Lexer {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        match self.next_token() {
+            Token::Eof => None,
+            token => Some(token),
+        }
+    }
 }
@@ -1,4 +1,127 @@
 pub mod ast;
+pub mod ast_visit;
 pub mod codegen;
 pub mod lexer;
 pub mod parser;
+pub mod preprocess;
+
+use ast::{Declaration, Program};
+use codegen::CodeGenerator;
+use parser::{ParseError, Parser};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// 将一段 C 源码解析为 AST
+pub fn parse_str(src: &str) -> Result<Program, ParseError> {
+    Parser::new(src).parse_program()
+}
+
+/// 解析 C 源码并重新生成规范化的 C 代码
+pub fn translate_to_c(src: &str) -> Result<String, ParseError> {
+    let program = parse_str(src)?;
+    Ok(CodeGenerator::new().generate_program(&program))
+}
+
+/// 目录扫描结果
+#[derive(Debug, Default)]
+pub struct ParseReport {
+    pub total_files: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    // 带错误恢复解析时，所有文件中成功解析的声明数与出错的声明数
+    pub declarations_ok: usize,
+    pub declarations_failed: usize,
+    pub files: Vec<FileReport>,
+}
+
+/// 单个文件的解析结果；errors 为空表示解析成功
+#[derive(Debug)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub declarations: usize,
+    pub errors: Vec<String>,
+}
+
+/// 解析目录下所有 .c 文件并统计结果
+pub fn parse_dir(dir: &Path) -> std::io::Result<ParseReport> {
+    parse_dir_with(dir, "", &|src| src.to_string())
+}
+
+/// 收集程序中定义的 typedef 名
+pub fn typedef_names(program: &Program) -> HashSet<String> {
+    program
+        .declarations
+        .iter()
+        .filter_map(|decl| match decl {
+            Declaration::Typedef(def) => Some(def.name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 同 parse_dir，每个文件先经过 sanitize 预处理，再在前面拼上 prelude（如补充的 typedef）后解析。
+/// 错误信息中的行号相对于拼接后的输入；prelude 为空时不拼接，行号与源文件一致。
+/// 分两遍进行：先解析所有文件收集其中定义的 typedef 名，再让每个文件都带着这些名字重新解析，
+/// 这样一个文件中定义的类型可以在另一个文件中使用。struct/union/enum 标签总是可以不经声明直接引用，
+/// 不需要收集
+pub fn parse_dir_with(
+    dir: &Path,
+    prelude: &str,
+    sanitize: &dyn Fn(&str) -> String,
+) -> std::io::Result<ParseReport> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|p| p.extension().and_then(|s| s.to_str()) == Some("c"))
+        .collect();
+    paths.sort();
+
+    let inputs: Vec<std::io::Result<String>> = paths
+        .iter()
+        .map(|path| {
+            std::fs::read_to_string(path).map(|src| {
+                if prelude.is_empty() {
+                    sanitize(&src)
+                } else {
+                    format!("{}\n{}", prelude, sanitize(&src))
+                }
+            })
+        })
+        .collect();
+    let mut known = HashSet::new();
+    for input in inputs.iter().flatten() {
+        let (program, _) = Parser::new(input).parse_program_recovering();
+        known.extend(typedef_names(&program));
+    }
+
+    let mut report = ParseReport::default();
+    for (path, input) in paths.into_iter().zip(inputs) {
+        report.total_files += 1;
+        let file = match input {
+            Ok(input) => {
+                let (program, errors) = Parser::new(&input)
+                    .with_known_typedefs(known.clone())
+                    .parse_program_recovering();
+                report.declarations_ok += program.declarations.len();
+                report.declarations_failed += errors.len();
+                FileReport {
+                    path,
+                    declarations: program.declarations.len(),
+                    errors: errors.iter().map(|e| e.render(&input)).collect(),
+                }
+            }
+            Err(e) => FileReport {
+                path,
+                declarations: 0,
+                errors: vec![format!("cannot read file: {}", e)],
+            },
+        };
+        if file.errors.is_empty() {
+            report.succeeded += 1;
+        } else {
+            report.failed += 1;
+        }
+        report.files.push(file);
+    }
+    Ok(report)
+}
@@ -1,12 +1,84 @@
-mod ast;
-mod codegen;
-mod lexer;
-mod parser;
+use c_to_rust_tool::codegen::{CodeGenerator, TargetLang};
+use c_to_rust_tool::preprocess::{sanitize, SanitizeOptions};
+use c_to_rust_tool::{parse_dir_with, parse_str};
+use std::path::Path;
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: c_to_rust_tool <input.c> [-o <output.rs|output.c>]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    // 不带参数时运行内置演示
+    if args.is_empty() {
+        run_demo();
+        return ExitCode::SUCCESS;
+    }
+    run_cli(&args)
+}
+
+// 命令行模式：读取输入文件，解析并生成代码；输出路径以 .rs 结尾时生成 Rust，否则生成 C
+fn run_cli(args: &[String]) -> ExitCode {
+    let mut input = None;
+    let mut output = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            "-o" => match iter.next() {
+                Some(path) => output = Some(path.clone()),
+                None => {
+                    eprintln!("error: missing path after -o\n{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            },
+            _ if input.is_none() => input = Some(arg.clone()),
+            _ => {
+                eprintln!("error: unexpected argument '{}'\n{}", arg, USAGE);
+                return ExitCode::from(2);
+            }
+        }
+    }
+    let Some(input) = input else {
+        eprintln!("error: missing input file\n{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let source = match std::fs::read_to_string(&input) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("error: cannot read {}: {}", input, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let program = match parse_str(&source) {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("{}:{}", input, e.render(&source));
+            return ExitCode::FAILURE;
+        }
+    };
 
-use codegen::CodeGenerator;
-use parser::Parser;
+    let target = match &output {
+        Some(path) if path.ends_with(".rs") => TargetLang::Rust,
+        _ => TargetLang::C,
+    };
+    let generated = CodeGenerator::with_target(target).generate_program(&program);
+    match output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(&path, generated) {
+                eprintln!("error: cannot write {}: {}", path, e);
+                return ExitCode::FAILURE;
+            }
+        }
+        None => print!("{}", generated),
+    }
+    ExitCode::SUCCESS
+}
 
-fn main() {
+fn run_demo() {
     println!("=== C表达式解析增强测试 ===\n");
 
     // 测试1: 类型转换和malloc
@@ -166,8 +238,7 @@ int main() {
 }
 
 fn process_code(code: &str) {
-    let mut parser = Parser::new(code);
-    match parser.parse_program() {
+    match parse_str(code) {
         Ok(program) => {
             println!("✓ 解析成功!");
             println!("AST: {:#?}\n", program);
@@ -183,16 +254,8 @@ fn process_code(code: &str) {
     }
 }
 
-fn parse_translate_chibicc_dir(dir: &str) {
-    use std::fs;
-    use std::path::Path;
-
-    let path = Path::new(dir);
-    let mut total = 0usize;
-    let mut ok = 0usize;
-
-    let prelude = r#"
-typedef int bool;
+// 补充 chibicc 源码依赖的标准库与前置声明的类型
+const CHIBICC_PRELUDE: &str = r#"
 typedef long long int64_t;
 typedef unsigned long long uint64_t;
 typedef int int32_t;
@@ -226,160 +289,46 @@ typedef int TokenKind;
 typedef int TypeKind;
 "#;
 
-    let entries = match fs::read_dir(path) {
-        Ok(e) => e,
+fn parse_translate_chibicc_dir(dir: &str) {
+    let report = match parse_dir_with(Path::new(dir), CHIBICC_PRELUDE, &sanitize_source) {
+        Ok(report) => report,
         Err(e) => {
             println!("无法读取目录 {}: {}", dir, e);
             return;
         }
     };
 
-    for entry in entries.flatten() {
-        let p = entry.path();
-        if p.extension().and_then(|s| s.to_str()) != Some("c") {
-            continue;
-        }
-
-        total += 1;
-        let fname = p.display().to_string();
-        match fs::read_to_string(&p) {
-            Ok(src) => {
-                let sanitized = sanitize_source(&src);
-                let input = format!("{}\n{}", prelude, sanitized);
-                let mut parser = Parser::new(&input);
-                match parser.parse_program() {
-                    Ok(_program) => {
-                        ok += 1;
-                        println!("✓ 解析成功: {}", fname);
-                    }
-                    Err(e) => {
-                        println!("✗ 解析失败: {}\n  -> {}", fname, e);
-                    }
-                }
+    for file in &report.files {
+        let fname = file.path.display();
+        if file.errors.is_empty() {
+            println!("✓ 解析成功: {}", fname);
+        } else {
+            println!(
+                "✗ 解析失败: {}（成功 {} 个声明，{} 个声明出错）",
+                fname,
+                file.declarations,
+                file.errors.len()
+            );
+            for e in &file.errors {
+                println!("  {}", e.replace('\n', "\n  "));
             }
-            Err(e) => println!("✗ 读取失败: {} -> {}", fname, e),
         }
     }
 
-    println!("\n统计: 成功 {}/{} 文件", ok, total);
+    println!(
+        "\n统计: 成功 {}/{} 文件",
+        report.succeeded, report.total_files
+    );
+    println!(
+        "声明: 成功 {}，出错 {}",
+        report.declarations_ok, report.declarations_failed
+    );
 }
 
+// chibicc 的 codegen.c 中使用的宏片段无法在没有预处理的情况下展开，直接删除
 fn sanitize_source(src: &str) -> String {
-    // 1) 去掉预处理指令行（以#开头），并处理续行反斜杠，将整个宏定义块移除
-    let mut out_lines: Vec<String> = Vec::new();
-    let mut iter = src.lines();
-    while let Some(line) = iter.next() {
-        let t = line.trim_start();
-        if t.starts_with('#') {
-            // 跳过该行以及后续以反斜杠续行的行
-            let prev_ends_with_bs = t.trim_end().ends_with('\\');
-            if !prev_ends_with_bs {
-                continue;
-            }
-            while let Some(next_line) = iter.next() {
-                let tt = next_line.trim_end();
-                let cont = tt.ends_with('\\');
-                if !cont {
-                    break;
-                }
-            }
-            continue;
-        }
-        out_lines.push(line.to_string());
-    }
-    let mut s = out_lines.join("\n");
-
-    // 2) 移除 __attribute__((...)) / __attribute__ (...) 块（简单括号匹配）
-    s = remove_attribute_blocks(&s, "__attribute__");
-
-    // 3) 移除 GCC 扩展关键字/限定符：inline, _Noreturn, noreturn, restrict
-    for kw in ["inline", "_Noreturn", "noreturn", "restrict"] {
-        s = replace_word(&s, kw, "");
-    }
-
-    // 4) 常见内建宏/关键字占位（如果存在，直接删除，不参与解析）
-    for kw in ["__restrict", "__restrict__", "__inline", "__inline__"] {
-        s = replace_word(&s, kw, "");
-    }
-    // 定向移除 codegen.c 中使用的宏片段（无预处理状态下无法展开）
-    for kw in ["FROM_F80_1", "FROM_F80_2"] {
-        s = replace_word(&s, kw, "");
-    }
-
-    // 5) 去掉常见的系统头文件 include 行（如果 sanitize 第一步遗漏了尾随空格等情况）
-    let mut out2 = Vec::new();
-    for line in s.lines() {
-        let t = line.trim();
-        if t.starts_with("#include <") || t.starts_with("# include <") {
-            continue;
-        }
-        if t.starts_with("#define FROM_F80_1") || t.starts_with("#define FROM_F80_2") {
-            continue;
-        }
-        out2.push(line.to_string());
-    }
-    s = out2.join("\n");
-
-    s
-}
-
-fn remove_attribute_blocks(input: &str, marker: &str) -> String {
-    let mut s = input.to_string();
-    while let Some(pos) = s.find(marker) {
-        // 找到第一个 '('
-        let start_paren = match s[pos..].find('(') {
-            Some(off) => pos + off,
-            None => {
-                s.replace_range(pos..pos + marker.len(), "");
-                continue;
-            }
-        };
-        // 匹配括号直到配平
-        let mut i = start_paren;
-        let mut depth = 0i32;
-        while i < s.len() {
-            let ch = s.as_bytes()[i] as char;
-            if ch == '(' {
-                depth += 1;
-            }
-            if ch == ')' {
-                depth -= 1;
-                if depth == 0 {
-                    i += 1;
-                    break;
-                }
-            }
-            i += 1;
-        }
-        let end = i.min(s.len());
-        s.replace_range(pos..end, "");
-    }
-    s
-}
-
-fn replace_word(input: &str, word: &str, repl: &str) -> String {
-    // 简单基于分隔符的词替换，避免替换到标识符子串
-    let mut out = String::with_capacity(input.len());
-    let mut start = 0usize;
-    while let Some(pos) = input[start..].find(word) {
-        let abs = start + pos;
-        let left_ok = abs == 0 || !is_ident_char(input.as_bytes()[abs - 1] as char);
-        let right_ok = abs + word.len() >= input.len()
-            || !is_ident_char(input.as_bytes()[abs + word.len()] as char);
-        if left_ok && right_ok {
-            out.push_str(&input[start..abs]);
-            out.push_str(repl);
-            start = abs + word.len();
-        } else {
-            // 非独立单词，跳过该位置
-            out.push_str(&input[start..=abs]);
-            start = abs + 1;
-        }
-    }
-    out.push_str(&input[start..]);
-    out
-}
-
-fn is_ident_char(ch: char) -> bool {
-    ch.is_alphanumeric() || ch == '_'
+    let mut opts = SanitizeOptions::default();
+    opts.removed_keywords
+        .extend(["FROM_F80_1", "FROM_F80_2"].map(String::from));
+    sanitize(src, &opts)
 }
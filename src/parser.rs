@@ -1,55 +1,244 @@
 use crate::ast::*;
-use crate::lexer::{Lexer, Token};
-use std::collections::HashSet;
+use crate::lexer::{Lexer, SpannedToken, Token};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+// 解析时需要预读的 token 数（当前 token 之后）
+const LOOKAHEAD: usize = 2;
+
+// 声明说明符：存储类、inline 函数说明符与基础类型
+struct DeclSpecifiers {
+    storage: Option<StorageClass>,
+    is_inline: bool,
+    typ: CType,
+}
+
+// 解析后的声明符；直接作用于名字的函数后缀会保留带参数名的参数列表
+struct Declarator {
+    name: String,
+    typ: CType,
+    params: Option<Vec<Param>>,
+}
+
+/// 带源码位置（1 起始的行号与列号）的解析错误
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl ParseError {
+    /// 渲染出错的源码行，并在出错列下方标出 ^
+    pub fn render(&self, source: &str) -> String {
+        let text = source
+            .lines()
+            .nth(self.line.saturating_sub(1))
+            .unwrap_or("");
+        // 保留行首的制表符，使 ^ 与源码对齐
+        let pad: String = text
+            .chars()
+            .take(self.col.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let gutter = " ".repeat(self.line.to_string().len());
+        format!(
+            "{}\n{} |\n{} | {}\n{} | {}^",
+            self, gutter, self.line, text, gutter, pad
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.col, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
 
 pub struct Parser {
-    tokens: Vec<Token>,
+    lexer: Lexer,
+    // 已从词法分析器拉取的 token，按需增量填充
+    tokens: Vec<SpannedToken>,
     pos: usize,
     typedef_names: HashSet<String>,
+    // 已知的整数常量（枚举项与可求值的 #define），用于计算数组大小
+    constants: HashMap<String, i128>,
+    // 常量表达式中 sizeof(类型) 按这一数据模型求值
+    data_model: DataModel,
+    // 词法分析器保留注释时，各 token 之前的注释，按 token 下标索引
+    comments: HashMap<usize, Vec<String>>,
 }
 
 impl Parser {
     pub fn new(input: &str) -> Self {
-        let mut lexer = Lexer::new(input);
-        let tokens = lexer.tokenize();
-        Parser {
-            tokens,
+        Parser::from_lexer(Lexer::new(input))
+    }
+
+    // 从词法分析器按需拉取 token，而不是一次性生成完整的 token 列表
+    pub fn from_lexer(lexer: Lexer) -> Self {
+        let mut parser = Parser {
+            lexer,
+            tokens: Vec::new(),
             pos: 0,
             typedef_names: HashSet::new(),
+            constants: HashMap::new(),
+            data_model: DataModel::default(),
+            comments: HashMap::new(),
+        };
+        parser.fill_lookahead();
+        parser
+    }
+
+    // 预先登记在其他翻译单元中定义的 typedef 名，使本文件中对它们的使用能按类型名解析
+    pub fn with_known_typedefs(mut self, names: HashSet<String>) -> Self {
+        self.typedef_names.extend(names);
+        self
+    }
+
+    // 指定目标平台的数据模型，默认为 LP64
+    pub fn with_data_model(mut self, model: DataModel) -> Self {
+        self.data_model = model;
+        self
+    }
+
+    // 保证缓冲区中至少有 pos + LOOKAHEAD 个 token（遇到 EOF 即停止）
+    fn fill_lookahead(&mut self) {
+        self.fill_to(self.pos + LOOKAHEAD);
+    }
+
+    // 保证缓冲区中至少有下标 end 处的 token（遇到 EOF 即停止）
+    fn fill_to(&mut self, end: usize) {
+        while self.tokens.len() <= end {
+            if matches!(self.tokens.last(), Some(t) if t.token == Token::Eof) {
+                break;
+            }
+            let spanned = self.lexer.next_spanned();
+            let comments = self.lexer.take_comments();
+            if !comments.is_empty() {
+                self.comments.insert(self.tokens.len(), comments);
+            }
+            self.tokens.push(spanned);
+        }
+    }
+
+    // 取出当前 token 之前的注释；只在语句或声明的开头调用，其余位置的注释被丢弃
+    fn take_comments(&mut self) -> Vec<String> {
+        self.comments.remove(&self.pos).unwrap_or_default()
+    }
+
+    // 解析 { 之后直到 } 之前的语句列表（不消耗 }），注释作为独立的语句保留
+    fn parse_block_items(&mut self) -> Result<Vec<Stmt>, ParseError> {
+        let mut stmts = Vec::new();
+        while self.current_token() != &Token::RBrace && self.current_token() != &Token::Eof {
+            stmts.extend(self.take_comments().into_iter().map(Stmt::Comment));
+            stmts.push(self.parse_statement()?);
         }
+        stmts.extend(self.take_comments().into_iter().map(Stmt::Comment));
+        Ok(stmts)
     }
 
     fn current_token(&self) -> &Token {
-        self.tokens.get(self.pos).unwrap_or(&Token::Eof)
+        self.tokens
+            .get(self.pos)
+            .map(|t| &t.token)
+            .unwrap_or(&Token::Eof)
+    }
+
+    // 向前查看第 n 个 token（n 不超过 LOOKAHEAD）
+    fn peek_token(&self, n: usize) -> &Token {
+        self.tokens
+            .get(self.pos + n)
+            .map(|t| &t.token)
+            .unwrap_or(&Token::Eof)
+    }
+
+    // 记录当前位置，之后可以用 reset 回到这里
+    fn mark(&self) -> usize {
+        self.pos
+    }
+
+    // 回溯到 mark 记录的位置；token 缓冲区保留已读入的 token，不需要重新词法分析
+    fn reset(&mut self, mark: usize) {
+        self.pos = mark;
+        self.fill_lookahead();
+    }
+
+    // 试探性解析：f 失败时回到调用前的位置并返回 None，不消耗任何 token
+    fn try_parse<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, ParseError>) -> Option<T> {
+        let mark = self.mark();
+        match f(self) {
+            Ok(value) => Some(value),
+            Err(_) => {
+                self.reset(mark);
+                None
+            }
+        }
+    }
+
+    // 向前查看第 n 个 token，不受 LOOKAHEAD 限制，需要时继续从词法分析器拉取
+    fn peek(&mut self, n: usize) -> &Token {
+        self.fill_to(self.pos + n);
+        self.peek_token(n)
+    }
+
+    // 当前 token 的位置（行, 列）；越界时取最后一个 token（EOF）的位置
+    fn current_location(&self) -> (usize, usize) {
+        self.tokens
+            .get(self.pos)
+            .or_else(|| self.tokens.last())
+            .map(|t| (t.line, t.col))
+            .unwrap_or((1, 1))
+    }
+
+    fn source_pos(&self) -> SourcePos {
+        let (line, col) = self.current_location();
+        SourcePos { line, col }
+    }
+
+    // 生成指向当前 token 位置的错误
+    fn error(&self, message: &str) -> ParseError {
+        let (line, col) = self.current_location();
+        ParseError {
+            message: message.to_string(),
+            line,
+            col,
+        }
     }
 
     fn advance(&mut self) {
         if self.pos < self.tokens.len() {
             self.pos += 1;
         }
+        self.fill_lookahead();
     }
 
-    fn expect(&mut self, expected: Token) -> Result<(), String> {
+    fn expect(&mut self, expected: Token) -> Result<(), ParseError> {
         if self.current_token() == &expected {
             self.advance();
             Ok(())
         } else {
-            Err(format!(
-                "Expected {:?}, got {:?}",
+            Err(self.error(&format!(
+                "Expected '{}', got '{}'",
                 expected,
                 self.current_token()
-            ))
+            )))
         }
     }
 
-    fn parse_type(&mut self) -> Result<CType, String> {
-        // 存储类说明符（丢弃）
-        while matches!(
-            self.current_token(),
-            Token::Static | Token::Extern | Token::Auto | Token::Register
-        ) {
-            self.advance();
-        }
+    // 解析类型（含指针前缀，如类型转换中的 char *），丢弃其中的存储类说明符
+    fn parse_type(&mut self) -> Result<CType, ParseError> {
+        let specifiers = self.parse_decl_specifiers()?;
+        self.parse_pointers(specifiers.typ)
+    }
+
+    // 解析声明说明符：存储类说明符与类型，如 static const int。
+    // 不包括指针前缀，int *a, b; 中的 '*' 只属于第一个声明符
+    fn parse_decl_specifiers(&mut self) -> Result<DeclSpecifiers, ParseError> {
+        let mut storage = None;
+        let mut is_inline = false;
+        let mut is_restrict = false;
 
         // 类型修饰/说明收集
         let mut is_const = false;
@@ -62,17 +251,69 @@ impl Parser {
         let mut saw_void = false;
         let mut long_count: u8 = 0; // 支持 long long
         let mut saw_short = false;
+        let mut saw_bool = false;
+        let mut saw_int = false;
 
         // 基础类型（可能来自 struct/union/enum/typedef 或组合关键字）
         let mut base_type: Option<CType> = None;
         let mut consumed_any = false;
         loop {
             match self.current_token().clone() {
+                Token::Static | Token::Extern | Token::Auto | Token::Register => {
+                    storage = Some(match self.current_token() {
+                        Token::Static => StorageClass::Static,
+                        Token::Extern => StorageClass::Extern,
+                        Token::Auto => StorageClass::Auto,
+                        _ => StorageClass::Register,
+                    });
+                    self.advance();
+                }
+                Token::Inline => {
+                    is_inline = true;
+                    self.advance();
+                }
                 Token::Const => {
                     is_const = true;
                     self.advance();
                     consumed_any = true;
                 }
+                Token::Restrict => {
+                    is_restrict = true;
+                    self.advance();
+                    consumed_any = true;
+                }
+                // _Atomic(T) 作为类型说明符取其中的类型；单独的 _Atomic 限定符忽略
+                Token::Atomic => {
+                    self.advance();
+                    if self.current_token() == &Token::LParen {
+                        self.advance();
+                        base_type = Some(self.parse_type_name()?);
+                        self.expect(Token::RParen)?;
+                    }
+                    consumed_any = true;
+                }
+                Token::Typeof => {
+                    self.advance();
+                    self.expect(Token::LParen)?;
+                    let typ = if self.is_type_keyword()
+                        || matches!(self.current_token(), Token::Identifier(name) if self.typedef_names.contains(name))
+                    {
+                        self.parse_type_name()?
+                    } else {
+                        CType::TypeOf(Box::new(self.parse_expr()?))
+                    };
+                    self.expect(Token::RParen)?;
+                    base_type = Some(typ);
+                    consumed_any = true;
+                }
+                // 线程局部存储不影响解析结果，与 static/extern 组合时保留后者
+                Token::ThreadLocal => self.advance(),
+                // 对齐说明符不影响翻译结果，跳过 _Alignas(...)
+                Token::Alignas => {
+                    self.advance();
+                    self.expect(Token::LParen)?;
+                    self.skip_paren_group()?;
+                }
                 Token::Volatile => {
                     is_volatile = true;
                     self.advance();
@@ -99,7 +340,7 @@ impl Parser {
                     consumed_any = true;
                 }
                 Token::Int => {
-                    /* mark int */
+                    saw_int = true;
                     self.advance();
                     consumed_any = true;
                 }
@@ -123,57 +364,32 @@ impl Parser {
                     self.advance();
                     consumed_any = true;
                 }
-                Token::Struct => {
-                    self.advance();
-                    match self.current_token().clone() {
-                        Token::Identifier(name) => {
-                            self.advance();
-                            base_type = Some(CType::Struct(name));
-                            consumed_any = true;
-                        }
-                        Token::LBrace => {
-                            // 内联结构体定义，跳过块，作为匿名类型处理
-                            self.skip_brace_block()?;
-                            base_type = Some(CType::Struct(String::new()));
-                            consumed_any = true;
-                        }
-                        _ => return Err("Expected struct name".to_string()),
-                    }
-                }
-                Token::Union => {
+                Token::Bool => {
+                    saw_bool = true;
                     self.advance();
-                    match self.current_token().clone() {
-                        Token::Identifier(name) => {
-                            self.advance();
-                            base_type = Some(CType::Union(name));
-                            consumed_any = true;
-                        }
-                        Token::LBrace => {
-                            self.skip_brace_block()?;
-                            base_type = Some(CType::Union(String::new()));
-                            consumed_any = true;
-                        }
-                        _ => return Err("Expected union name".to_string()),
-                    }
+                    consumed_any = true;
                 }
-                Token::Enum => {
-                    self.advance();
-                    match self.current_token().clone() {
-                        Token::Identifier(name) => {
-                            self.advance();
-                            base_type = Some(CType::Enum(name));
-                            consumed_any = true;
-                        }
-                        Token::LBrace => {
-                            self.skip_brace_block()?;
-                            base_type = Some(CType::Enum(String::new()));
-                            consumed_any = true;
-                        }
-                        _ => return Err("Expected enum name".to_string()),
-                    }
+                Token::Struct | Token::Union | Token::Enum => {
+                    base_type = Some(self.parse_tagged_type()?);
+                    consumed_any = true;
                 }
+                // 已经有基础类型时后面的标识符是声明符，如 typedef struct T T;
+                // 以及重复的 typedef unsigned long size_t;
                 Token::Identifier(name) => {
-                    if self.typedef_names.contains(&name) {
+                    let saw_keyword_type = is_unsigned
+                        || is_signed
+                        || long_count > 0
+                        || saw_short
+                        || saw_int
+                        || saw_char
+                        || saw_float
+                        || saw_double
+                        || saw_void
+                        || saw_bool;
+                    if base_type.is_none()
+                        && !saw_keyword_type
+                        && self.typedef_names.contains(&name)
+                    {
                         self.advance();
                         base_type = Some(CType::Typedef(name));
                         consumed_any = true;
@@ -187,7 +403,7 @@ impl Parser {
         }
 
         if !consumed_any {
-            return Err(format!("Expected type, got {:?}", self.current_token()));
+            return Err(self.error(&format!("Expected type, got '{}'", self.current_token())));
         }
 
         // 归一化推导基本类型（当未通过 struct/union/enum/typedef 指定时）
@@ -202,12 +418,17 @@ impl Parser {
                 CType::Char
             }
         } else if saw_double {
-            // long double 简化为 Double
-            CType::Double
+            if long_count > 0 {
+                CType::LongDouble
+            } else {
+                CType::Double
+            }
         } else if saw_float {
             CType::Float
         } else if saw_void {
             CType::Void
+        } else if saw_bool {
+            CType::Bool
         } else {
             // int 系：考虑 short / long / signed / unsigned
             if saw_short {
@@ -216,7 +437,13 @@ impl Parser {
                 } else {
                     CType::Short
                 }
-            } else if long_count > 0 {
+            } else if long_count >= 2 {
+                if is_unsigned {
+                    CType::UnsignedLongLong
+                } else {
+                    CType::LongLong
+                }
+            } else if long_count == 1 {
                 if is_unsigned {
                     CType::UnsignedLong
                 } else {
@@ -233,116 +460,177 @@ impl Parser {
             }
         };
 
-        // 指针星号
-        while self.current_token() == &Token::Star {
-            self.advance();
-            typ = CType::Pointer(Box::new(typ));
-        }
-
-        // 应用 const/volatile（简单包裹）
+        // 基础类型前后的 const/volatile 修饰基础类型本身
         if is_const {
             typ = CType::Const(Box::new(typ));
         }
         if is_volatile {
             typ = CType::Volatile(Box::new(typ));
         }
+        if is_restrict {
+            typ = CType::Restrict(Box::new(typ));
+        }
+
+        Ok(DeclSpecifiers {
+            storage,
+            is_inline,
+            typ,
+        })
+    }
+
+    // 解析指针前缀，如 * const * volatile；'*' 之后的限定符修饰该层指针
+    fn parse_pointers(&mut self, mut typ: CType) -> Result<CType, ParseError> {
+        while self.current_token() == &Token::Star {
+            self.advance();
+            typ = CType::Pointer(Box::new(typ));
+            let (mut is_const, mut is_volatile, mut is_restrict) = (false, false, false);
+            loop {
+                match self.current_token() {
+                    Token::Const => is_const = true,
+                    Token::Volatile => is_volatile = true,
+                    Token::Restrict => is_restrict = true,
+                    // _Atomic 限定符不影响翻译结果
+                    Token::Atomic => {}
+                    _ => break,
+                }
+                self.advance();
+            }
+            if is_const {
+                typ = CType::Const(Box::new(typ));
+            }
+            if is_volatile {
+                typ = CType::Volatile(Box::new(typ));
+            }
+            if is_restrict {
+                typ = CType::Restrict(Box::new(typ));
+            }
+        }
         Ok(typ)
     }
 
-    // 解析结构体定义
-    fn parse_struct_def(&mut self) -> Result<StructDef, String> {
-        self.expect(Token::Struct)?;
+    // 解析 struct/union/enum 类型说明符：
+    //   struct Tag            引用已有类型
+    //   struct Tag { ... }    带标签的定义
+    //   struct { ... }        匿名定义
+    // 带 { ... } 的形式保留完整定义，以便代码生成时原样输出
+    fn parse_tagged_type(&mut self) -> Result<CType, ParseError> {
+        let kind = self.current_token().clone();
+        self.advance();
 
-        let name = if let Token::Identifier(n) = self.current_token().clone() {
+        let tag = if let Token::Identifier(n) = self.current_token().clone() {
             self.advance();
-            n
+            Some(n)
         } else {
-            return Err("Expected struct name".to_string());
+            None
         };
 
-        self.expect(Token::LBrace)?;
-        let mut fields = Vec::new();
-
-        while self.current_token() != &Token::RBrace && self.current_token() != &Token::Eof {
-            let basety = self.parse_type()?;
-            let (field_name, field_type) = self.parse_declarator(basety)?;
-            self.expect(Token::Semicolon)?;
-            fields.push(StructField {
-                typ: field_type,
-                name: field_name,
+        if self.current_token() != &Token::LBrace {
+            let Some(name) = tag else {
+                return Err(self.error(&format!("Expected {} name", kind)));
+            };
+            return Ok(match kind {
+                Token::Struct => CType::Struct(name),
+                Token::Union => CType::Union(name),
+                _ => CType::Enum(name),
             });
         }
 
-        self.expect(Token::RBrace)?;
-
-        Ok(StructDef { name, fields })
+        self.advance();
+        let name = tag.unwrap_or_default();
+        Ok(match kind {
+            Token::Struct => CType::StructDef(Box::new(StructDef {
+                name,
+                fields: self.parse_struct_body()?,
+            })),
+            Token::Union => CType::UnionDef(Box::new(UnionDef {
+                name,
+                fields: self.parse_struct_body()?,
+            })),
+            _ => CType::EnumDef(Box::new(EnumDef {
+                name,
+                variants: self.parse_enum_body()?,
+            })),
+        })
     }
 
-    // 解析联合体定义
-    fn parse_union_def(&mut self) -> Result<UnionDef, String> {
-        self.expect(Token::Union)?;
-
-        let name = if let Token::Identifier(n) = self.current_token().clone() {
-            self.advance();
-            n
-        } else {
-            return Err("Expected union name".to_string());
-        };
-
-        self.expect(Token::LBrace)?;
+    // 解析结构体/联合体的成员列表，调用前已消费 '{'，会消费结尾的 '}'
+    fn parse_struct_body(&mut self) -> Result<Vec<StructField>, ParseError> {
         let mut fields = Vec::new();
-
         while self.current_token() != &Token::RBrace && self.current_token() != &Token::Eof {
-            let basety = self.parse_type()?;
-            let (field_name, field_type) = self.parse_declarator(basety)?;
-            self.expect(Token::Semicolon)?;
-            fields.push(StructField {
-                typ: field_type,
-                name: field_name,
-            });
+            fields.push(self.parse_struct_field()?);
         }
-
         self.expect(Token::RBrace)?;
-
-        Ok(UnionDef { name, fields })
+        Ok(fields)
     }
 
-    // 解析枚举定义
-    fn parse_enum_def(&mut self) -> Result<EnumDef, String> {
-        self.expect(Token::Enum)?;
+    // 解析结构体/联合体的一个成员，支持位域 `type name : width;`、
+    // 用于填充的无名位域 `type : width;`，以及匿名结构体/联合体成员
+    // `union { ... };`（成员名为空，其字段直接属于外层类型）
+    fn parse_struct_field(&mut self) -> Result<StructField, ParseError> {
+        let basety = self.parse_type()?;
+        let (name, typ) = match self.current_token() {
+            Token::Colon => (String::new(), basety),
+            Token::Semicolon if matches!(basety, CType::StructDef(_) | CType::UnionDef(_)) => {
+                (String::new(), basety)
+            }
+            _ => self.parse_declarator(basety)?,
+        };
 
-        // 允许匿名枚举：enum { ... }
-        let name = if let Token::Identifier(n) = self.current_token().clone() {
+        let bit_width = if self.current_token() == &Token::Colon {
             self.advance();
-            n
+            // 宽度是整数常量表达式，可以引用枚举项或宏常量
+            let width = self.parse_ternary()?;
+            match self.const_value(&width) {
+                Some(n) => {
+                    let width = u32::try_from(n)
+                        .map_err(|_| self.error(&format!("Invalid bit-field width {}", n)))?;
+                    Some(width)
+                }
+                None => return Err(self.error("Expected integer constant for bit-field width")),
+            }
         } else {
-            String::new()
+            None
         };
 
-        self.expect(Token::LBrace)?;
+        self.expect(Token::Semicolon)?;
+        Ok(StructField {
+            typ,
+            name,
+            bit_width,
+        })
+    }
+
+    // 解析枚举项列表，调用前已消费 '{'，会消费结尾的 '}'
+    fn parse_enum_body(&mut self) -> Result<Vec<EnumVariant>, ParseError> {
         let mut variants = Vec::new();
+        let mut next_value = Some(0i128);
 
         while self.current_token() != &Token::RBrace && self.current_token() != &Token::Eof {
             let variant_name = if let Token::Identifier(n) = self.current_token().clone() {
                 self.advance();
                 n
             } else {
-                return Err("Expected enum variant name".to_string());
+                return Err(self.error("Expected enum variant name"));
             };
 
+            // 枚举值是常量表达式，可以引用之前的枚举项，如 B = A + 1
             let value = if self.current_token() == &Token::Assign {
                 self.advance();
-                if let Token::IntLiteral(n) = self.current_token() {
-                    let v = *n;
-                    self.advance();
-                    Some(v)
-                } else {
-                    return Err("Expected integer literal for enum value".to_string());
-                }
+                Some(self.parse_ternary()?)
             } else {
                 None
             };
 
+            // 记录枚举项的值，供之后的数组大小等常量表达式使用
+            let known = match &value {
+                Some(expr) => self.const_value(expr),
+                None => next_value,
+            };
+            if let Some(v) = known {
+                self.constants.insert(variant_name.clone(), v);
+            }
+            next_value = known.and_then(|v| v.checked_add(1));
+
             variants.push(EnumVariant {
                 name: variant_name,
                 value,
@@ -356,205 +644,324 @@ impl Parser {
         }
 
         self.expect(Token::RBrace)?;
-
-        Ok(EnumDef { name, variants })
+        Ok(variants)
     }
 
     // 解析typedef定义
-    fn parse_typedef(&mut self) -> Result<TypedefDef, String> {
-        self.expect(Token::Typedef)?;
-        // 专门处理 typedef 与 struct/union/enum 组合的几种形式：
-        //   typedef struct { ... } Name;
-        //   typedef struct Tag { ... } Name;
-        //   typedef struct Tag Name;
-        //   typedef enum { ... } Name;  等
-        match self.current_token().clone() {
-            Token::Struct | Token::Union | Token::Enum => {
-                // 记录哪一种
-                let kind = self.current_token().clone();
-                self.advance();
-
-                // 可选的标签名
-                let mut tag_name: Option<String> = None;
-                if let Token::Identifier(n) = self.current_token().clone() {
-                    // 下一个如果是标识符且后续不是 "(" 之类，则视为标签名
-                    tag_name = Some(n.clone());
-                    self.advance();
-                }
-
-                // 如遇到内联定义，跳过 { ... }
-                if self.current_token() == &Token::LBrace {
-                    self.skip_brace_block()?;
-                }
-
-                // 基础类型（匿名时可临时以别名名作为类型名占位，稍后由 declarator 返回 name）
-                let base = match kind {
-                    Token::Struct => CType::Struct(tag_name.unwrap_or_else(|| "".to_string())),
-                    Token::Union => CType::Union(tag_name.unwrap_or_else(|| "".to_string())),
-                    Token::Enum => CType::Enum(tag_name.unwrap_or_else(|| "".to_string())),
-                    _ => unreachable!(),
-                };
-
-                // 读取 declarator，拿到名字与可能的数组/函数等后缀
-                let (name, target_type) = self.parse_declarator(base)?;
+    // 带名字的 struct/union/enum 定义只随第一个声明符输出，其余声明符引用该名字
+    fn type_ref(typ: CType) -> CType {
+        match typ {
+            CType::StructDef(def) if !def.name.is_empty() => CType::Struct(def.name),
+            CType::UnionDef(def) if !def.name.is_empty() => CType::Union(def.name),
+            CType::EnumDef(def) if !def.name.is_empty() => CType::Enum(def.name),
+            typ => typ,
+        }
+    }
 
-                self.expect(Token::Semicolon)?;
-                // 记录 typedef 名称
-                self.typedef_names.insert(name.clone());
-                Ok(TypedefDef { name, target_type })
-            }
-            _ => {
-                // 常规形式：typedef <type> declarator (, declarator)* ;
-                let base_type = self.parse_type()?;
-                let base_clone = base_type.clone();
-                let (name, target_type) = self.parse_declarator(base_type)?;
-                self.typedef_names.insert(name.clone());
-                // 额外 typedef 名称仅加入表中
-                while self.current_token() == &Token::Comma {
-                    self.advance();
-                    let (n2, _t2) = self.parse_declarator(base_clone.clone())?;
-                    self.typedef_names.insert(n2);
-                }
-                self.expect(Token::Semicolon)?;
-                Ok(TypedefDef { name, target_type })
+    fn parse_typedef(&mut self) -> Result<Vec<TypedefDef>, ParseError> {
+        self.expect(Token::Typedef)?;
+        // typedef <type> declarator (, declarator)* ;
+        // <type> 可以是带定义体的 struct/union/enum，如 typedef struct { ... } Name;
+        let mut base_type = self.parse_decl_specifiers()?.typ;
+        let mut typedefs = Vec::new();
+        loop {
+            let (name, target_type) = self.parse_declarator(base_type.clone())?;
+            self.typedef_names.insert(name.clone());
+            typedefs.push(TypedefDef { name, target_type });
+            if self.current_token() != &Token::Comma {
+                break;
             }
+            self.advance();
+            base_type = Self::type_ref(base_type);
         }
+        self.expect(Token::Semicolon)?;
+        Ok(typedefs)
     }
 
     // 解析 declarator 的后缀部分：
     // - 数组声明： [N]
     // - 函数类型： (param_types)
-    fn parse_declarator_suffix(&mut self, mut base: CType) -> Result<CType, String> {
-        loop {
-            match self.current_token() {
-                Token::LBracket => {
-                    self.advance();
-                    let size = if let Token::IntLiteral(n) = self.current_token() {
-                        let s = *n as usize;
-                        self.advance();
-                        Some(s)
-                    } else {
-                        // 允许不写大小，如 typedef int T[]; 简化为 None
-                        None
-                    };
-                    self.expect(Token::RBracket)?;
-                    base = CType::Array {
-                        element_type: Box::new(base),
-                        size,
-                    };
-                }
-                Token::LParen => {
-                    // 函数类型声明：返回类型为当前 base
-                    self.advance();
-                    let mut params: Vec<CType> = Vec::new();
-                    if self.current_token() != &Token::RParen {
-                        loop {
-                            // 处理可变参数 ...
-                            if self.current_token() == &Token::Ellipsis {
-                                // 记录为一个特殊的占位类型：用 "..." 的 typedef 名占位以保留信息
-                                self.advance();
-                                // 我们用 void 类型作为占位，不影响后续流程
-                                //（当前实现不真正使用参数类型信息进行代码生成）
-                                // 不再接受更多参数
-                                break;
-                            }
-
-                            let pty = self.parse_type()?;
-                            // 可选的参数名（忽略）
-                            if let Token::Identifier(_) = self.current_token() {
-                                self.advance();
-                            }
-                            params.push(pty);
-                            if self.current_token() == &Token::Comma {
-                                self.advance();
-                                continue;
-                            }
-                            break;
+    // 后缀从左到右对应类型从外到内，因此先递归解析剩余后缀得到内层类型，
+    // 例如 int a[3][4] 是“3 个 int[4] 组成的数组”
+    fn parse_declarator_suffix(&mut self, base: CType) -> Result<CType, ParseError> {
+        match self.current_token() {
+            Token::LBracket => {
+                self.advance();
+                // 允许不写大小，如 typedef int T[]; 简化为 None
+                let (size, size_expr) = if self.current_token() == &Token::RBracket {
+                    (None, None)
+                } else {
+                    let expr = self.parse_ternary()?;
+                    let size = self
+                        .const_value(&expr)
+                        .and_then(|n| usize::try_from(n).ok());
+                    // 整数字面量直接由 size 表示，其余表达式保留以便原样输出
+                    match expr {
+                        Expr::IntLiteral(_) | Expr::IntLiteralSuffixed { .. } if size.is_some() => {
+                            (size, None)
                         }
+                        expr => (size, Some(Box::new(expr))),
                     }
-                    self.expect(Token::RParen)?;
-                    base = CType::Function {
-                        return_type: Box::new(base),
-                        params,
-                    };
-                }
-                _ => break,
-            }
-        }
-        Ok(base)
-    }
-
-    // 解析 C declarator，返回 (名称, 完整类型)
-    // 支持形式： ident 后接 []/() 后缀；以及括号包裹的 declarator（如 (*fn)(T)）
-    fn parse_declarator(&mut self, base: CType) -> Result<(String, CType), String> {
-        // 先解析可选的指针前缀（例如 `*`、`**`）
-        let mut ty = base;
-        while self.current_token() == &Token::Star {
-            self.advance();
-            ty = CType::Pointer(Box::new(ty));
-        }
-
-        // 解析直接声明子句：标识符 或 (declarator)
-        let (name, mut ty) = match self.current_token().clone() {
-            Token::Identifier(n) => {
-                self.advance();
-                (n, ty)
+                };
+                self.expect(Token::RBracket)?;
+                let element_type = self.parse_declarator_suffix(base)?;
+                Ok(CType::Array {
+                    element_type: Box::new(element_type),
+                    size,
+                    size_expr,
+                })
             }
             Token::LParen => {
-                // 括号中的 declarator 可以携带自己的指针前缀
+                // 函数类型声明：返回类型为其余后缀作用于 base 的结果
                 self.advance();
-                let (n, inner_ty) = self.parse_declarator(ty)?;
-                self.expect(Token::RParen)?;
-                (n, inner_ty)
-            }
-            _ => {
-                return Err(format!(
-                    "Expected typedef name, got {:?}",
-                    self.current_token()
-                ))
+                let (params, variadic) = self.parse_param_list()?;
+                let return_type = self.parse_declarator_suffix(base)?;
+                Ok(Self::function_type(return_type, &params, variadic))
             }
-        };
+            _ => Ok(base),
+        }
+    }
 
-        // 解析后缀：数组或函数参数列表
-        ty = self.parse_declarator_suffix(ty)?;
+    // 用已知的枚举项和宏常量对整数常量表达式求值，sizeof 按 data_model 计算
+    fn const_value(&self, expr: &Expr) -> Option<i128> {
+        const_int_value_in(expr, self.data_model, &|name| {
+            self.constants.get(name).copied()
+        })
+    }
 
-        Ok((name, ty))
+    fn function_type(return_type: CType, params: &[Param], variadic: bool) -> CType {
+        CType::Function {
+            return_type: Box::new(return_type),
+            params: params.iter().map(|p| p.typ.clone()).collect(),
+            variadic,
+        }
     }
 
-    // 跳过一个用大括号包裹的块（支持嵌套）
-    fn skip_brace_block(&mut self) -> Result<(), String> {
-        self.expect(Token::LBrace)?;
-        let mut depth: i32 = 1;
-        while depth > 0 {
-            match self.current_token() {
-                Token::LBrace => {
-                    depth += 1;
+    // 解析参数列表，调用前已消费 '('，会消费结尾的 ')'。
+    // 返回参数与是否以 ... 结尾
+    fn parse_param_list(&mut self) -> Result<(Vec<Param>, bool), ParseError> {
+        let mut params = Vec::new();
+        let mut variadic = false;
+        let kr_list = self.starts_kr_identifier_list();
+        if self.current_token() != &Token::RParen {
+            loop {
+                // 可变参数 ... 只能出现在最后
+                if self.current_token() == &Token::Ellipsis {
                     self.advance();
+                    variadic = true;
+                    break;
                 }
-                Token::RBrace => {
-                    depth -= 1;
-                    self.advance();
+                if let Token::Identifier(name) = self.current_token().clone() {
+                    // K&R 风格的标识符列表 f(a, b)：参数类型在 ')' 之后声明，默认为 int
+                    if kr_list {
+                        self.advance();
+                        params.push(Param {
+                            typ: CType::Int,
+                            name,
+                        });
+                        if self.current_token() == &Token::Comma {
+                            self.advance();
+                            continue;
+                        }
+                        break;
+                    }
+                    // 原型中参数开头的标识符只能是类型名，多半来自未展开的头文件，之后同样按类型名处理
+                    self.typedef_names.insert(name);
+                }
+                params.push(self.parse_param()?);
+                if self.current_token() == &Token::Comma {
+                    self.advance();
+                    continue;
                 }
-                Token::Eof => {
-                    // 清洗过的源码可能丢失配对的 '}'，此处容错退出
+                break;
+            }
+        }
+        self.expect(Token::RParen)?;
+        Ok((params, variadic))
+    }
+
+    // 解析 K&R 函数定义中 ')' 与 '{' 之间的参数声明，如 int f(a, b) int a; char *b; { ... }
+    fn parse_kr_param_decls(&mut self, params: &mut [Param]) -> Result<(), ParseError> {
+        while self.current_token() != &Token::LBrace && self.current_token() != &Token::Eof {
+            let base_type = self.parse_decl_specifiers()?.typ;
+            loop {
+                let (line, col) = self.current_location();
+                let (name, typ) = self.parse_declarator(base_type.clone())?;
+                let Some(param) = params.iter_mut().find(|p| p.name == name) else {
+                    return Err(ParseError {
+                        message: format!(
+                            "Declaration for parameter '{}' but no such parameter",
+                            name
+                        ),
+                        line,
+                        col,
+                    });
+                };
+                param.typ = typ;
+                if self.current_token() != &Token::Comma {
                     break;
                 }
-                _ => self.advance(),
+                self.advance();
+            }
+            self.expect(Token::Semicolon)?;
+        }
+        Ok(())
+    }
+
+    // K&R 风格的标识符列表只出现在函数定义中：参数列表全是未知的标识符，
+    // 且 ')' 之后紧跟函数体或参数声明，如 int f(a, b) int a; { ... }。
+    // 调用时已消费 '('
+    fn starts_kr_identifier_list(&mut self) -> bool {
+        let mut n = 0;
+        loop {
+            match self.peek(n).clone() {
+                Token::Identifier(name) if !self.typedef_names.contains(&name) => {}
+                _ => return false,
+            }
+            match self.peek(n + 1) {
+                Token::Comma => n += 2,
+                Token::RParen => break,
+                _ => return false,
+            }
+        }
+        match self.peek(n + 2).clone() {
+            Token::LBrace | Token::Register => true,
+            token if Self::is_type_keyword_token(&token) => true,
+            Token::Identifier(name) => {
+                self.typedef_names.contains(&name)
+                    || matches!(self.peek(n + 3), Token::Identifier(_) | Token::Star)
+            }
+            _ => false,
+        }
+    }
+
+    // 解析单个参数；原型中的参数可以省略名字，此时名字为空
+    fn parse_param(&mut self) -> Result<Param, ParseError> {
+        let typ = self.parse_type()?;
+        if matches!(self.current_token(), Token::Identifier(_) | Token::LParen) {
+            // int (*)(int) 这样括号中没有名字的参数按抽象声明符重新解析
+            if let Some((name, typ)) = self.try_parse(|p| p.parse_declarator(typ.clone())) {
+                return Ok(Param { typ, name });
+            }
+            if matches!(self.current_token(), Token::Identifier(_)) {
+                let (name, typ) = self.parse_declarator(typ)?;
+                return Ok(Param { typ, name });
+            }
+        }
+        let typ = self.parse_abstract_declarator(typ)?;
+        Ok(Param {
+            typ,
+            name: String::new(),
+        })
+    }
+
+    // 解析 C declarator，返回 (名称, 完整类型)
+    // 支持形式： ident 后接 []/() 后缀；以及括号包裹的 declarator（如 (*fn)(T)）
+    fn parse_declarator(&mut self, base: CType) -> Result<(String, CType), ParseError> {
+        let decl = self.parse_full_declarator(base)?;
+        Ok((decl.name, decl.typ))
+    }
+
+    // 与 parse_declarator 相同，但保留函数声明符的参数名
+    fn parse_full_declarator(&mut self, base: CType) -> Result<Declarator, ParseError> {
+        // 先解析可选的指针前缀（例如 `*`、`**`、`* const`）
+        let ty = self.parse_pointers(base)?;
+
+        // 解析直接声明子句：标识符 或 (declarator)
+        let (name, ty) = match self.current_token().clone() {
+            Token::Identifier(n) => {
+                self.advance();
+                // 紧跟名字的参数列表即函数本身的参数，需要保留参数名
+                if self.current_token() == &Token::LParen {
+                    self.advance();
+                    let (params, variadic) = self.parse_param_list()?;
+                    let return_type = self.parse_declarator_suffix(ty)?;
+                    return Ok(Declarator {
+                        name: n,
+                        typ: Self::function_type(return_type, &params, variadic),
+                        params: Some(params),
+                    });
+                }
+                (n, ty)
+            }
+            Token::LParen => {
+                // 括号中的 declarator 可以携带自己的指针前缀。
+                // 括号外的后缀比括号内的声明符更靠近基类型，例如 int (*fp)(int)
+                // 中 fp 是指向 int(int) 的指针，因此先跳过括号解析外层后缀，
+                // 再回到括号内以外层类型为基类型解析内层声明符
+                self.advance();
+                let inner_start = self.pos;
+                self.skip_paren_group()?;
+                let outer_ty = self.parse_declarator_suffix(ty)?;
+                let outer_end = self.pos;
+
+                // 内层声明符带参数列表时（如 int (*pick(int n))(int)）保留其参数名
+                self.pos = inner_start;
+                let inner = self.parse_full_declarator(outer_ty)?;
+                self.expect(Token::RParen)?;
+                self.pos = outer_end;
+                self.fill_lookahead();
+                return Ok(inner);
             }
+            _ => {
+                return Err(self.error(&format!(
+                    "Expected typedef name, got '{}'",
+                    self.current_token()
+                )))
+            }
+        };
+
+        // 解析后缀：数组或函数参数列表
+        let typ = self.parse_declarator_suffix(ty)?;
+
+        Ok(Declarator {
+            name,
+            typ,
+            params: None,
+        })
+    }
+
+    // 跳过到与已消费的 '(' 匹配的 ')'（包含），支持嵌套
+    fn skip_paren_group(&mut self) -> Result<(), ParseError> {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.current_token() {
+                Token::LParen => depth += 1,
+                Token::RParen => depth -= 1,
+                Token::Eof => return Err(self.error("Unterminated '(' in declarator")),
+                _ => {}
+            }
+            self.advance();
         }
         Ok(())
     }
 
-    fn parse_primary(&mut self) -> Result<Expr, String> {
+    fn parse_primary(&mut self) -> Result<Expr, ParseError> {
         match self.current_token().clone() {
             Token::IntLiteral(n) => {
                 self.advance();
                 Ok(Expr::IntLiteral(n))
             }
+            Token::IntLiteralSuffixed {
+                value,
+                unsigned,
+                long_count,
+            } => {
+                self.advance();
+                Ok(Expr::IntLiteralSuffixed {
+                    value,
+                    unsigned,
+                    long_count,
+                })
+            }
             Token::FloatLiteral(f) => {
                 self.advance();
                 Ok(Expr::FloatLiteral(f))
             }
+            Token::FloatLiteralSuffixed { value, suffix } => {
+                self.advance();
+                Ok(Expr::FloatLiteralSuffixed { value, suffix })
+            }
             Token::CharLiteral(c) => {
                 self.advance();
                 Ok(Expr::CharLiteral(c))
@@ -573,19 +980,11 @@ impl Parser {
                 self.advance();
                 // 检查是否是函数调用
                 if self.current_token() == &Token::LParen {
-                    self.advance();
-                    let mut args = Vec::new();
-
-                    if self.current_token() != &Token::RParen {
-                        args.push(self.parse_expr()?);
-                        while self.current_token() == &Token::Comma {
-                            self.advance();
-                            args.push(self.parse_expr()?);
-                        }
-                    }
-
-                    self.expect(Token::RParen)?;
+                    let args = self.parse_call_args()?;
                     Ok(Expr::Call { func: name, args })
+                } else if name == "NULL" {
+                    // NULL 宏单独表示，便于代码生成时按目标语言改写
+                    Ok(Expr::Null)
                 } else {
                     Ok(Expr::Identifier(name))
                 }
@@ -595,22 +994,26 @@ impl Parser {
                 self.advance();
                 // GNU 扩展：语句表达式 ({ ... })
                 if self.current_token() == &Token::LBrace {
-                    // 消耗一个块，直到 '}'，然后期望 ')'
-                    self.skip_brace_block()?;
+                    let Stmt::Block(stmts) = self.parse_statement()? else {
+                        unreachable!("'{{' always starts a block statement");
+                    };
                     self.expect(Token::RParen)?;
-                    return Ok(Expr::Null);
+                    return Ok(Expr::StmtExpr(stmts));
                 }
 
-                // 仅当后续是明确的类型关键字或已知 typedef 名称时，按类型转换/复合字面量处理
-                if self.is_type_keyword()
-                    || matches!(self.current_token(), Token::Identifier(name) if self.typedef_names.contains(name))
-                {
-                    let typ = self.parse_type()?;
-                    self.expect(Token::RParen)?;
+                // 先试着把括号内容解析为类型名，成功时是类型转换或复合字面量，
+                // 失败时回到 '(' 之后按普通括号表达式解析
+                let typ = match self.try_parse(Self::parse_paren_type_name) {
+                    Some(typ) => Some(typ),
+                    // 以类型关键字开头却不是合法类型名时，报告类型本身的错误
+                    None if self.is_type_keyword() => Some(self.parse_paren_type_name()?),
+                    None => None,
+                };
+                if let Some(typ) = typ {
                     // 复合字面量 (Type){ ... }
                     if self.current_token() == &Token::LBrace {
-                        self.skip_brace_block()?;
-                        return Ok(Expr::Null);
+                        let init = self.parse_init_items()?;
+                        return Ok(Expr::CompoundLiteral { typ, init });
                     }
                     let expr = self.parse_unary()?;
                     Ok(Expr::Cast {
@@ -628,53 +1031,100 @@ impl Parser {
                 self.advance();
                 if self.current_token() == &Token::LParen {
                     self.advance();
-                    if self.is_type_keyword()
-                        || matches!(self.current_token(), Token::Identifier(name) if self.typedef_names.contains(name))
-                    {
-                        let typ = self.parse_type()?;
-                        self.expect(Token::RParen)?;
+                    // 与类型转换相同，先试着解析类型名，如 sizeof(int[4])、sizeof(int (*)[3])
+                    if let Some(typ) = self.try_parse(Self::parse_paren_type_name) {
                         Ok(Expr::SizeOf(typ))
+                    } else if self.is_type_keyword() {
+                        Ok(Expr::SizeOf(self.parse_paren_type_name()?))
                     } else {
                         // sizeof(表达式)
-                        let _ = self.parse_expr()?;
+                        let expr = self.parse_expr()?;
                         self.expect(Token::RParen)?;
-                        Ok(Expr::Null)
+                        Ok(Expr::SizeOfExpr(Box::new(expr)))
                     }
                 } else {
                     // sizeof 后直接接一元表达式（如 sizeof *p）
-                    let _ = self.parse_unary()?;
-                    Ok(Expr::Null)
+                    let expr = self.parse_unary()?;
+                    Ok(Expr::SizeOfExpr(Box::new(expr)))
                 }
             }
-            _ => Err(format!(
-                "Unexpected token in expression: {:?}",
+            Token::Unknown(ch) => Err(self.error(&format!("Unexpected character '{}'", ch))),
+            Token::Error(msg) => Err(self.error(&msg)),
+            _ => Err(self.error(&format!(
+                "Unexpected token in expression: '{}'",
                 self.current_token()
-            )),
+            ))),
         }
     }
 
+    // 解析括号中的类型名直到 ')'（'(' 已消耗），如 int*、struct Point、int[4]
+    fn parse_paren_type_name(&mut self) -> Result<CType, ParseError> {
+        let typ = self.parse_type_name()?;
+        self.expect(Token::RParen)?;
+        Ok(typ)
+    }
+
+    // 解析类型名：类型说明符加上可选的抽象声明符，如 int (*)[3]、char *(*)(int)
+    fn parse_type_name(&mut self) -> Result<CType, ParseError> {
+        let typ = self.parse_type()?;
+        self.parse_abstract_declarator(typ)
+    }
+
+    // 解析不带名字的声明符，如 *、[4]、(*)[3]、(*)(int)；没有声明符时返回 base。
+    // '(' 后面是 *、( 或 [ 时是分组括号，否则是函数类型的参数列表
+    fn parse_abstract_declarator(&mut self, base: CType) -> Result<CType, ParseError> {
+        let ty = self.parse_pointers(base)?;
+        if self.current_token() == &Token::LParen
+            && matches!(
+                self.peek_token(1),
+                Token::Star | Token::LParen | Token::LBracket
+            )
+        {
+            // 与 parse_full_declarator 相同，先解析括号外的后缀作为括号内声明符的基类型
+            self.advance();
+            let inner_start = self.mark();
+            self.skip_paren_group()?;
+            let outer_ty = self.parse_declarator_suffix(ty)?;
+            let outer_end = self.mark();
+            self.reset(inner_start);
+            let typ = self.parse_abstract_declarator(outer_ty)?;
+            self.expect(Token::RParen)?;
+            self.reset(outer_end);
+            return Ok(typ);
+        }
+        self.parse_declarator_suffix(ty)
+    }
+
     // 辅助函数：检查当前token是否是类型关键字
     fn is_type_keyword(&self) -> bool {
+        Self::is_type_keyword_token(self.current_token())
+    }
+
+    fn is_type_keyword_token(token: &Token) -> bool {
         matches!(
-            self.current_token(),
+            token,
             Token::Int
                 | Token::Char
                 | Token::Float
                 | Token::Double
                 | Token::Void
+                | Token::Bool
                 | Token::Long
                 | Token::Short
                 | Token::Unsigned
                 | Token::Signed
                 | Token::Const
                 | Token::Volatile
+                | Token::Restrict
+                | Token::Atomic
+                | Token::Typeof
                 | Token::Struct
                 | Token::Union
                 | Token::Enum
         )
     }
 
-    fn parse_unary(&mut self) -> Result<Expr, String> {
+    fn parse_unary(&mut self) -> Result<Expr, ParseError> {
         match self.current_token() {
             Token::Minus => {
                 self.advance();
@@ -684,6 +1134,14 @@ impl Parser {
                     operand: Box::new(operand),
                 })
             }
+            Token::Plus => {
+                self.advance();
+                let operand = self.parse_unary()?;
+                Ok(Expr::Unary {
+                    op: UnaryOp::Plus,
+                    operand: Box::new(operand),
+                })
+            }
             Token::Not => {
                 self.advance();
                 let operand = self.parse_unary()?;
@@ -737,11 +1195,19 @@ impl Parser {
     }
 
     // 新增：处理后缀表达式（数组访问、成员访问、后缀++/--）
-    fn parse_postfix(&mut self) -> Result<Expr, String> {
+    fn parse_postfix(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.parse_primary()?;
 
         loop {
             match self.current_token() {
+                Token::LParen => {
+                    // 通过表达式调用，如函数指针 (*fp)(a, b)、函数表 tbl[i](x)
+                    let args = self.parse_call_args()?;
+                    expr = Expr::CallExpr {
+                        callee: Box::new(expr),
+                        args,
+                    };
+                }
                 Token::LBracket => {
                     // 数组访问 arr[index]
                     self.advance();
@@ -762,10 +1228,10 @@ impl Parser {
                             member,
                         };
                     } else {
-                        return Err(format!(
-                            "Expected identifier after '.', got {:?}",
+                        return Err(self.error(&format!(
+                            "Expected identifier after '.', got '{}'",
                             self.current_token()
-                        ));
+                        )));
                     }
                 }
                 Token::Arrow => {
@@ -778,10 +1244,10 @@ impl Parser {
                             member,
                         };
                     } else {
-                        return Err(format!(
-                            "Expected identifier after '->', got {:?}",
+                        return Err(self.error(&format!(
+                            "Expected identifier after '->', got '{}'",
                             self.current_token()
-                        ));
+                        )));
                     }
                 }
                 Token::Increment => {
@@ -807,7 +1273,22 @@ impl Parser {
         Ok(expr)
     }
 
-    fn parse_multiplicative(&mut self) -> Result<Expr, String> {
+    // 解析调用的实参列表 (a, b, ...)，当前 token 为 '('
+    fn parse_call_args(&mut self) -> Result<Vec<Expr>, ParseError> {
+        self.expect(Token::LParen)?;
+        let mut args = Vec::new();
+        if self.current_token() != &Token::RParen {
+            args.push(self.parse_assignment()?);
+            while self.current_token() == &Token::Comma {
+                self.advance();
+                args.push(self.parse_assignment()?);
+            }
+        }
+        self.expect(Token::RParen)?;
+        Ok(args)
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, ParseError> {
         let mut left = self.parse_unary()?;
 
         loop {
@@ -829,7 +1310,7 @@ impl Parser {
         Ok(left)
     }
 
-    fn parse_additive(&mut self) -> Result<Expr, String> {
+    fn parse_additive(&mut self) -> Result<Expr, ParseError> {
         let mut left = self.parse_multiplicative()?;
 
         loop {
@@ -850,7 +1331,30 @@ impl Parser {
         Ok(left)
     }
 
-    fn parse_comparison(&mut self) -> Result<Expr, String> {
+    // 相等比较 == !=，优先级低于关系比较
+    fn parse_comparison(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_relational()?;
+
+        loop {
+            let op = match self.current_token() {
+                Token::Eq => BinaryOp::Eq,
+                Token::Ne => BinaryOp::Ne,
+                _ => break,
+            };
+            self.advance();
+            let right = self.parse_relational()?;
+            left = Expr::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+
+        Ok(left)
+    }
+
+    // 关系比较 < > <= >=
+    fn parse_relational(&mut self) -> Result<Expr, ParseError> {
         let mut left = self.parse_shift()?;
 
         loop {
@@ -859,8 +1363,6 @@ impl Parser {
                 Token::Gt => BinaryOp::Gt,
                 Token::Le => BinaryOp::Le,
                 Token::Ge => BinaryOp::Ge,
-                Token::Eq => BinaryOp::Eq,
-                Token::Ne => BinaryOp::Ne,
                 _ => break,
             };
             self.advance();
@@ -876,7 +1378,7 @@ impl Parser {
     }
 
     // 新增：位移运算符 << >>
-    fn parse_shift(&mut self) -> Result<Expr, String> {
+    fn parse_shift(&mut self) -> Result<Expr, ParseError> {
         let mut left = self.parse_additive()?;
 
         loop {
@@ -897,19 +1399,32 @@ impl Parser {
         Ok(left)
     }
 
-    fn parse_logical(&mut self) -> Result<Expr, String> {
-        let mut left = self.parse_bitwise_or()?;
+    // 逻辑或 ||，优先级低于逻辑与
+    fn parse_logical(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_logical_and()?;
 
-        loop {
-            let op = match self.current_token() {
-                Token::And => BinaryOp::And,
-                Token::Or => BinaryOp::Or,
-                _ => break,
+        while self.current_token() == &Token::Or {
+            self.advance();
+            let right = self.parse_logical_and()?;
+            left = Expr::Binary {
+                op: BinaryOp::Or,
+                left: Box::new(left),
+                right: Box::new(right),
             };
+        }
+
+        Ok(left)
+    }
+
+    // 逻辑与 &&
+    fn parse_logical_and(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_bitwise_or()?;
+
+        while self.current_token() == &Token::And {
             self.advance();
             let right = self.parse_bitwise_or()?;
             left = Expr::Binary {
-                op,
+                op: BinaryOp::And,
                 left: Box::new(left),
                 right: Box::new(right),
             };
@@ -919,7 +1434,7 @@ impl Parser {
     }
 
     // 新增：位或运算 |
-    fn parse_bitwise_or(&mut self) -> Result<Expr, String> {
+    fn parse_bitwise_or(&mut self) -> Result<Expr, ParseError> {
         let mut left = self.parse_bitwise_xor()?;
 
         while self.current_token() == &Token::BitOr {
@@ -936,7 +1451,7 @@ impl Parser {
     }
 
     // 新增：位异或运算 ^
-    fn parse_bitwise_xor(&mut self) -> Result<Expr, String> {
+    fn parse_bitwise_xor(&mut self) -> Result<Expr, ParseError> {
         let mut left = self.parse_bitwise_and()?;
 
         while self.current_token() == &Token::BitXor {
@@ -953,7 +1468,7 @@ impl Parser {
     }
 
     // 新增：位与运算 &
-    fn parse_bitwise_and(&mut self) -> Result<Expr, String> {
+    fn parse_bitwise_and(&mut self) -> Result<Expr, ParseError> {
         let mut left = self.parse_comparison()?;
 
         while self.current_token() == &Token::Ampersand {
@@ -969,7 +1484,7 @@ impl Parser {
         Ok(left)
     }
 
-    fn parse_assignment(&mut self) -> Result<Expr, String> {
+    fn parse_assignment(&mut self) -> Result<Expr, ParseError> {
         let left = self.parse_ternary()?;
 
         // 处理赋值与复合赋值
@@ -1025,14 +1540,17 @@ impl Parser {
     }
 
     // 新增：三元运算符 ? :
-    fn parse_ternary(&mut self) -> Result<Expr, String> {
+    fn parse_ternary(&mut self) -> Result<Expr, ParseError> {
         let cond = self.parse_logical()?;
 
         if self.current_token() == &Token::Question {
             self.advance();
+            // 中间分支可以是带逗号的完整表达式；else 分支按需求采用 C++ 的文法，
+            // 解析为赋值表达式，a ? b : c = d 得到 a ? b : (c = d)。
+            // C 标准文法中 else 分支只是条件表达式，会得到 (a ? b : c) = d 并被编译器拒绝
             let then_expr = self.parse_expr()?;
             self.expect(Token::Colon)?;
-            let else_expr = self.parse_ternary()?;
+            let else_expr = self.parse_assignment()?;
             Ok(Expr::Ternary {
                 cond: Box::new(cond),
                 then_expr: Box::new(then_expr),
@@ -1043,103 +1561,271 @@ impl Parser {
         }
     }
 
-    fn parse_expr(&mut self) -> Result<Expr, String> {
-        self.parse_assignment()
+    // 解析声明中可选的 `= 初始化器`
+    fn parse_optional_init(&mut self) -> Result<Option<Expr>, ParseError> {
+        if self.current_token() != &Token::Assign {
+            return Ok(None);
+        }
+        self.advance();
+        Ok(Some(self.parse_initializer()?))
+    }
+
+    // asm [volatile|inline|goto] ( ... )：按括号配平整体读入，操作数列表不做解析。
+    // 文本由 token 重新拼接而成，关键字统一写作 asm，空白按固定规则生成
+    fn parse_asm(&mut self) -> Result<String, ParseError> {
+        self.advance();
+        let mut text = "asm".to_string();
+        while matches!(
+            self.current_token(),
+            Token::Volatile | Token::Inline | Token::Goto
+        ) {
+            text.push(' ');
+            text.push_str(&self.current_token().to_string());
+            self.advance();
+        }
+        if self.current_token() != &Token::LParen {
+            return Err(self.error("Expected '(' after asm"));
+        }
+        let mut depth = 0usize;
+        let mut prev: Option<Token> = None;
+        loop {
+            let token = self.current_token().clone();
+            match &token {
+                Token::LParen => depth += 1,
+                Token::RParen => depth -= 1,
+                Token::Error(msg) => return Err(self.error(msg)),
+                Token::Eof => return Err(self.error("Unterminated asm statement")),
+                _ => {}
+            }
+            let tight_after = matches!(prev, None | Some(Token::LParen | Token::LBracket));
+            let tight_before = matches!(
+                token,
+                Token::LParen | Token::RParen | Token::RBracket | Token::Comma
+            );
+            if !tight_after && !tight_before {
+                text.push(' ');
+            }
+            text.push_str(&token.to_string());
+            self.advance();
+            if depth == 0 {
+                return Ok(text);
+            }
+            prev = Some(token);
+        }
+    }
+
+    // 初始化器：赋值表达式，或用大括号包裹的初始化列表
+    fn parse_initializer(&mut self) -> Result<Expr, ParseError> {
+        if self.current_token() == &Token::LBrace {
+            Ok(Expr::InitList(self.parse_init_items()?))
+        } else {
+            self.parse_assignment()
+        }
+    }
+
+    // 解析 { 1, 2, [3] = x, [4 ... 7] = y, .field = { ... } }，允许末尾多一个逗号
+    fn parse_init_items(&mut self) -> Result<Vec<InitItem>, ParseError> {
+        self.expect(Token::LBrace)?;
+        let mut items = Vec::new();
+        while self.current_token() != &Token::RBrace {
+            let mut designators = Vec::new();
+            loop {
+                match self.current_token() {
+                    Token::Dot => {
+                        self.advance();
+                        let Token::Identifier(field) = self.current_token().clone() else {
+                            return Err(self.error("Expected field name after '.'"));
+                        };
+                        self.advance();
+                        designators.push(Designator::Field(field));
+                    }
+                    Token::LBracket => {
+                        self.advance();
+                        let index = self.parse_ternary()?;
+                        if self.current_token() == &Token::Ellipsis {
+                            self.advance();
+                            if self.current_token() == &Token::RBracket {
+                                return Err(self.error(
+                                    "Expected upper bound after '...' in range designator",
+                                ));
+                            }
+                            let end = self.parse_ternary()?;
+                            self.expect(Token::RBracket)?;
+                            designators.push(Designator::Range(index, end));
+                        } else {
+                            self.expect(Token::RBracket)?;
+                            designators.push(Designator::Index(index));
+                        }
+                    }
+                    _ => break,
+                }
+            }
+            if !designators.is_empty() {
+                self.expect(Token::Assign)?;
+            }
+            let value = self.parse_initializer()?;
+            items.push(InitItem { designators, value });
+
+            if self.current_token() == &Token::Comma {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        self.expect(Token::RBrace)?;
+        Ok(items)
+    }
+
+    // 逗号运算符，优先级最低：a = 1, b = 2
+    // 函数实参和初始化器中的逗号是分隔符，那些位置直接调用 parse_assignment
+    fn parse_comma(&mut self) -> Result<Expr, ParseError> {
+        let first = self.parse_assignment()?;
+        if self.current_token() != &Token::Comma {
+            return Ok(first);
+        }
+        let mut exprs = vec![first];
+        while self.current_token() == &Token::Comma {
+            self.advance();
+            exprs.push(self.parse_assignment()?);
+        }
+        Ok(Expr::Comma(exprs))
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, ParseError> {
+        self.parse_comma()
+    }
+
+    // 解析 if/while/do/for 的语句体：花括号块或单条语句。
+    // 单独的 ; 视为空语句体；标签语句连同其后标注的语句一起归入语句体
+    fn parse_body(&mut self) -> Result<Vec<Stmt>, ParseError> {
+        if self.current_token() == &Token::LBrace {
+            self.advance();
+            let stmts = self.parse_block_items()?;
+            self.expect(Token::RBrace)?;
+            return Ok(stmts);
+        }
+        let mut stmts = vec![self.parse_statement()?];
+        while matches!(stmts.last(), Some(Stmt::Label(_))) {
+            stmts.push(self.parse_statement()?);
+        }
+        stmts.retain(|stmt| stmt != &Stmt::Empty);
+        Ok(stmts)
+    }
+
+    // 局部变量声明，支持逗号分隔的多个声明符
+    fn parse_local_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let DeclSpecifiers {
+            storage,
+            typ: basety,
+            ..
+        } = self.parse_decl_specifiers()?;
+        let base_clone = Self::type_ref(basety.clone());
+        let mut decls: Vec<Stmt> = Vec::new();
+        // 第一个声明符
+        {
+            let (name, final_type) = self.parse_declarator(basety)?;
+            let init = self.parse_optional_init()?;
+            decls.push(Stmt::VarDecl {
+                storage: storage.clone(),
+                typ: final_type,
+                name,
+                init,
+            });
+        }
+        // 额外的逗号后续声明符，与第一个一起组成 DeclList
+        while self.current_token() == &Token::Comma {
+            self.advance();
+            let (name, final_type) = self.parse_declarator(base_clone.clone())?;
+            let init = self.parse_optional_init()?;
+            decls.push(Stmt::VarDecl {
+                storage: storage.clone(),
+                typ: final_type,
+                name,
+                init,
+            });
+        }
+        self.expect(Token::Semicolon)?;
+        if decls.len() == 1 {
+            Ok(decls.remove(0))
+        } else {
+            Ok(Stmt::DeclList(decls))
+        }
     }
 
-    fn parse_statement(&mut self) -> Result<Stmt, String> {
+    // 以不认识的标识符开头、但只能解释为声明的语句：T x ...，或 T *x = ...
+    // （作为表达式时 T * x 不能被赋值）。T *x; 这样有歧义的写法仍按表达式解析
+    fn starts_unknown_type_declaration(&mut self) -> bool {
+        match self.current_token() {
+            Token::Identifier(name) if !self.typedef_names.contains(name) => {}
+            _ => return false,
+        }
+        let mut n = 1;
+        while self.peek(n) == &Token::Star {
+            n += 1;
+        }
+        matches!(self.peek(n), Token::Identifier(_))
+            && (n == 1 || self.peek(n + 1) == &Token::Assign)
+    }
+
+    // 试探性地把语句按声明解析，首个标识符当作来自头文件等处的类型名；
+    // 失败时回到语句开头按表达式解析，两者都失败时报告声明的错误
+    fn parse_tentative_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let start = self.mark();
+        let Token::Identifier(name) = self.current_token().clone() else {
+            return Err(self.error("Expected type name"));
+        };
+        self.typedef_names.insert(name.clone());
+        let err = match self.parse_local_declaration() {
+            // 之后同样按类型名处理
+            Ok(stmt) => return Ok(stmt),
+            Err(err) => err,
+        };
+        self.typedef_names.remove(&name);
+        self.reset(start);
+        let expr = self.parse_expr().map_err(|_| err.clone())?;
+        self.expect(Token::Semicolon).map_err(|_| err)?;
+        Ok(Stmt::Expr(expr))
+    }
+
+    fn parse_statement(&mut self) -> Result<Stmt, ParseError> {
+        if self.starts_unknown_type_declaration() {
+            return self.parse_tentative_declaration();
+        }
         match self.current_token() {
             // 基础类型关键字开头的声明
             Token::Int
             | Token::Char
             | Token::Float
             | Token::Double
+            | Token::Bool
             | Token::Long
             | Token::Short
             | Token::Unsigned
             | Token::Signed
             | Token::Const
             | Token::Volatile
+            | Token::Restrict
+            | Token::Atomic
+            | Token::Typeof
+            | Token::Alignas
             | Token::Static
             | Token::Extern
+            | Token::Auto
+            | Token::Register
+            | Token::ThreadLocal
             | Token::Struct
             | Token::Union
-            | Token::Enum => {
-                // 局部变量声明，支持逗号分隔的多个声明符
-                let basety = self.parse_type()?;
-                let base_clone = basety.clone();
-                let mut decls: Vec<Stmt> = Vec::new();
-                // 第一个声明符
-                {
-                    let (name, final_type) = self.parse_declarator(basety)?;
-                    let init = if self.current_token() == &Token::Assign {
-                        self.advance();
-                        if self.current_token() == &Token::LBrace {
-                            // 跳过聚合初始化器 { ... }
-                            self.skip_brace_block()?;
-                            None
-                        } else {
-                            Some(self.parse_expr()?)
-                        }
-                    } else {
-                        None
-                    };
-                    decls.push(Stmt::VarDecl {
-                        typ: final_type,
-                        name,
-                        init,
-                    });
-                }
-                // 额外的逗号后续声明符（丢入同一块中）
-                while self.current_token() == &Token::Comma {
-                    self.advance();
-                    let (name, final_type) = self.parse_declarator(base_clone.clone())?;
-                    let init = if self.current_token() == &Token::Assign {
-                        self.advance();
-                        if self.current_token() == &Token::LBrace {
-                            self.skip_brace_block()?;
-                            None
-                        } else {
-                            Some(self.parse_expr()?)
-                        }
-                    } else {
-                        None
-                    };
-                    decls.push(Stmt::VarDecl {
-                        typ: final_type,
-                        name,
-                        init,
-                    });
-                }
-                self.expect(Token::Semicolon)?;
-                if decls.len() == 1 {
-                    Ok(decls.remove(0))
-                } else {
-                    Ok(Stmt::Block(decls))
-                }
+            | Token::Enum => self.parse_local_declaration(),
+            // 标签语句 `name:`，需要向前多看一个 token 与表达式语句区分
+            Token::Identifier(label) if self.peek_token(1) == &Token::Colon => {
+                let label = label.clone();
+                self.advance();
+                self.advance();
+                Ok(Stmt::Label(label))
             }
             // 以 typedef 名称开头的声明
-            Token::Identifier(_) if matches!(self.current_token(), Token::Identifier(name) if self.typedef_names.contains(name)) =>
-            {
-                let basety = self.parse_type()?;
-                let (name, final_type) = self.parse_declarator(basety)?;
-                let init = if self.current_token() == &Token::Assign {
-                    self.advance();
-                    if self.current_token() == &Token::LBrace {
-                        self.skip_brace_block()?;
-                        None
-                    } else {
-                        Some(self.parse_expr()?)
-                    }
-                } else {
-                    None
-                };
-                self.expect(Token::Semicolon)?;
-                Ok(Stmt::VarDecl {
-                    typ: final_type,
-                    name,
-                    init,
-                })
+            Token::Identifier(_) if matches!(self.current_token(), Token::Identifier(name) if self.typedef_names.contains(name)) => {
+                self.parse_local_declaration()
             }
             Token::Return => {
                 self.advance();
@@ -1151,41 +1837,21 @@ impl Parser {
                 self.expect(Token::Semicolon)?;
                 Ok(Stmt::Return(expr))
             }
+            Token::Semicolon => {
+                self.advance();
+                Ok(Stmt::Empty)
+            }
             Token::If => {
                 self.advance();
                 self.expect(Token::LParen)?;
                 let cond = self.parse_expr()?;
                 self.expect(Token::RParen)?;
 
-                let then_block = if self.current_token() == &Token::LBrace {
-                    self.advance();
-                    let mut stmts = Vec::new();
-                    while self.current_token() != &Token::RBrace
-                        && self.current_token() != &Token::Eof
-                    {
-                        stmts.push(self.parse_statement()?);
-                    }
-                    self.expect(Token::RBrace)?;
-                    stmts
-                } else {
-                    vec![self.parse_statement()?]
-                };
+                let then_block = self.parse_body()?;
 
                 let else_block = if self.current_token() == &Token::Else {
                     self.advance();
-                    if self.current_token() == &Token::LBrace {
-                        self.advance();
-                        let mut stmts = Vec::new();
-                        while self.current_token() != &Token::RBrace
-                            && self.current_token() != &Token::Eof
-                        {
-                            stmts.push(self.parse_statement()?);
-                        }
-                        self.expect(Token::RBrace)?;
-                        Some(stmts)
-                    } else {
-                        Some(vec![self.parse_statement()?])
-                    }
+                    Some(self.parse_body()?)
                 } else {
                     None
                 };
@@ -1202,53 +1868,78 @@ impl Parser {
                 let cond = self.parse_expr()?;
                 self.expect(Token::RParen)?;
 
-                let body = if self.current_token() == &Token::LBrace {
-                    self.advance();
-                    let mut stmts = Vec::new();
-                    while self.current_token() != &Token::RBrace
-                        && self.current_token() != &Token::Eof
-                    {
-                        stmts.push(self.parse_statement()?);
-                    }
-                    self.expect(Token::RBrace)?;
-                    stmts
-                } else {
-                    vec![self.parse_statement()?]
-                };
+                let body = self.parse_body()?;
 
                 Ok(Stmt::While { cond, body })
             }
             Token::Switch => {
-                // 简化支持：消费 switch (<expr>) { ... }，将其作为一个空语句占位
                 self.advance();
                 self.expect(Token::LParen)?;
-                // 条件表达式
-                let _ = self.parse_expr()?;
+                let expr = self.parse_expr()?;
                 self.expect(Token::RParen)?;
-                if self.current_token() == &Token::LBrace {
-                    // 跳过整个 switch 块
-                    self.skip_brace_block()?;
-                } else {
-                    // 如果不是块，尽量消费一个语句（容错）
-                    let _ = self.parse_statement()?;
+                self.expect(Token::LBrace)?;
+
+                // 每个 case/default 标签开启一个新的分支，其后的语句归入该分支；
+                // 连续的标签（fall-through）表现为语句为空的分支
+                let mut cases: Vec<SwitchCase> = Vec::new();
+                while self.current_token() != &Token::RBrace && self.current_token() != &Token::Eof
+                {
+                    match self.current_token() {
+                        Token::Case => {
+                            self.advance();
+                            let value = self.parse_ternary()?;
+                            // GNU 扩展：case 1 ... 5:
+                            let range_end = if self.current_token() == &Token::Ellipsis {
+                                self.advance();
+                                if self.current_token() == &Token::Colon {
+                                    return Err(self
+                                        .error("Expected upper bound after '...' in case range"));
+                                }
+                                Some(self.parse_ternary()?)
+                            } else {
+                                None
+                            };
+                            self.expect(Token::Colon)?;
+                            cases.push(SwitchCase {
+                                value: Some(value),
+                                range_end,
+                                stmts: Vec::new(),
+                            });
+                        }
+                        Token::Default => {
+                            self.advance();
+                            self.expect(Token::Colon)?;
+                            cases.push(SwitchCase {
+                                value: None,
+                                range_end: None,
+                                stmts: Vec::new(),
+                            });
+                        }
+                        _ => {
+                            // 只保留语句前的注释，case 标签前的注释被丢弃，
+                            // 以免只有标签的 fall-through 分支变为非空
+                            let comments = self.take_comments();
+                            let stmt = self.parse_statement()?;
+                            match cases.last_mut() {
+                                Some(case) => {
+                                    case.stmts.extend(comments.into_iter().map(Stmt::Comment));
+                                    case.stmts.push(stmt);
+                                }
+                                None => {
+                                    return Err(
+                                        self.error("Expected 'case' or 'default' in switch body")
+                                    )
+                                }
+                            }
+                        }
+                    }
                 }
-                Ok(Stmt::Empty)
+                self.expect(Token::RBrace)?;
+                Ok(Stmt::Switch { expr, cases })
             }
             Token::Do => {
                 self.advance();
-                let body = if self.current_token() == &Token::LBrace {
-                    self.advance();
-                    let mut stmts = Vec::new();
-                    while self.current_token() != &Token::RBrace
-                        && self.current_token() != &Token::Eof
-                    {
-                        stmts.push(self.parse_statement()?);
-                    }
-                    self.expect(Token::RBrace)?;
-                    stmts
-                } else {
-                    vec![self.parse_statement()?]
-                };
+                let body = self.parse_body()?;
 
                 self.expect(Token::While)?;
                 self.expect(Token::LParen)?;
@@ -1291,19 +1982,7 @@ impl Parser {
                 self.expect(Token::RParen)?;
 
                 // 解析循环体
-                let body = if self.current_token() == &Token::LBrace {
-                    self.advance();
-                    let mut stmts = Vec::new();
-                    while self.current_token() != &Token::RBrace
-                        && self.current_token() != &Token::Eof
-                    {
-                        stmts.push(self.parse_statement()?);
-                    }
-                    self.expect(Token::RBrace)?;
-                    stmts
-                } else {
-                    vec![self.parse_statement()?]
-                };
+                let body = self.parse_body()?;
 
                 Ok(Stmt::For {
                     init,
@@ -1313,14 +1992,16 @@ impl Parser {
                 })
             }
             Token::Break => {
+                let pos = self.source_pos();
                 self.advance();
                 self.expect(Token::Semicolon)?;
-                Ok(Stmt::Break)
+                Ok(Stmt::Break(pos))
             }
             Token::Continue => {
+                let pos = self.source_pos();
                 self.advance();
                 self.expect(Token::Semicolon)?;
-                Ok(Stmt::Continue)
+                Ok(Stmt::Continue(pos))
             }
             Token::Goto => {
                 self.advance();
@@ -1329,16 +2010,17 @@ impl Parser {
                     self.expect(Token::Semicolon)?;
                     Ok(Stmt::Goto(label))
                 } else {
-                    Err("Expected label after goto".to_string())
+                    Err(self.error("Expected label after goto"))
                 }
             }
+            Token::Asm => {
+                let text = self.parse_asm()?;
+                self.expect(Token::Semicolon)?;
+                Ok(Stmt::InlineAsm(text))
+            }
             Token::LBrace => {
                 self.advance();
-                let mut stmts = Vec::new();
-                while self.current_token() != &Token::RBrace && self.current_token() != &Token::Eof
-                {
-                    stmts.push(self.parse_statement()?);
-                }
+                let stmts = self.parse_block_items()?;
                 self.expect(Token::RBrace)?;
                 Ok(Stmt::Block(stmts))
             }
@@ -1350,183 +2032,239 @@ impl Parser {
         }
     }
 
-    fn parse_function(&mut self) -> Result<Function, String> {
-        let return_type = self.parse_type()?;
-
-        let name = if let Token::Identifier(n) = self.current_token().clone() {
+    // 解析一条顶层声明；一个 typedef 可以同时定义多个名字，多余的分号不产生声明
+    fn parse_declarations(&mut self) -> Result<Vec<Declaration>, ParseError> {
+        let mut decls: Vec<Declaration> = self
+            .take_comments()
+            .into_iter()
+            .map(Declaration::Comment)
+            .collect();
+        // 文件作用域多余的分号，如 int x;; 或函数定义后的 ;
+        if self.current_token() == &Token::Semicolon {
             self.advance();
-            n
+        } else if self.current_token() == &Token::Typedef {
+            let typedefs = self.parse_typedef()?;
+            decls.extend(typedefs.into_iter().map(Declaration::Typedef));
         } else {
-            return Err("Expected function name".to_string());
-        };
-
-        self.expect(Token::LParen)?;
-
-        let mut params = Vec::new();
-        if self.current_token() != &Token::RParen {
-            loop {
-                let typ = self.parse_type()?;
-                let param_name = if let Token::Identifier(n) = self.current_token().clone() {
-                    self.advance();
-                    n
-                } else {
-                    return Err("Expected parameter name".to_string());
-                };
-                params.push(Param {
-                    typ,
-                    name: param_name,
-                });
-
-                if self.current_token() == &Token::Comma {
-                    self.advance();
-                } else {
-                    break;
-                }
-            }
+            decls.extend(self.parse_declaration()?);
         }
-
-        self.expect(Token::RParen)?;
-        self.expect(Token::LBrace)?;
-
-        let mut body = Vec::new();
-        while self.current_token() != &Token::RBrace && self.current_token() != &Token::Eof {
-            body.push(self.parse_statement()?);
-        }
-
-        self.expect(Token::RBrace)?;
-
-        Ok(Function {
-            return_type,
-            name,
-            params,
-            body,
-        })
+        Ok(decls)
     }
 
-    // 解析顶层声明（函数、结构体、枚举等）
-    fn parse_declaration(&mut self) -> Result<Declaration, String> {
+    // 解析顶层声明（函数、结构体、枚举等）；int a = 1, b = 2; 这样的全局变量声明
+    // 每个声明符各返回一个 GlobalVar
+    fn parse_declaration(&mut self) -> Result<Vec<Declaration>, ParseError> {
         match self.current_token() {
-            Token::Struct => {
-                let struct_def = self.parse_struct_def()?;
-                // 可能有分号
-                if self.current_token() == &Token::Semicolon {
-                    self.advance();
-                }
-                Ok(Declaration::Struct(struct_def))
+            Token::Include(path) => {
+                let path = path.clone();
+                self.advance();
+                Ok(vec![Declaration::Include(path)])
             }
-            Token::Union => {
-                let union_def = self.parse_union_def()?;
-                if self.current_token() == &Token::Semicolon {
-                    self.advance();
-                }
-                Ok(Declaration::Union(union_def))
+            Token::Pragma(text) => {
+                let text = text.clone();
+                self.advance();
+                Ok(vec![Declaration::Pragma(text)])
             }
-            Token::Enum => {
-                let enum_def = self.parse_enum_def()?;
-                if self.current_token() == &Token::Semicolon {
-                    self.advance();
+            Token::Define(name, value) => {
+                let (name, value) = (name.clone(), value.clone());
+                self.advance();
+                // 宏值能解析为整数常量表达式时记录下来，如 #define SIZE (4 * 8)
+                let mut sub = Parser::new(&value);
+                if let Ok(expr) = sub.parse_expr() {
+                    if sub.current_token() == &Token::Eof {
+                        if let Some(v) = self.const_value(&expr) {
+                            self.constants.insert(name.clone(), v);
+                        }
+                    }
                 }
-                Ok(Declaration::Enum(enum_def))
-            }
-            Token::Typedef => {
-                let typedef_def = self.parse_typedef()?;
-                Ok(Declaration::Typedef(typedef_def))
+                Ok(vec![Declaration::Define { name, value }])
             }
             _ => {
                 // 尝试解析函数或全局变量：使用 declarator 支持指针/数组/函数声明
-                let base_type = self.parse_type()?;
+                let DeclSpecifiers {
+                    storage,
+                    is_inline,
+                    typ: base_type,
+                } = self.parse_decl_specifiers()?;
+
+                // 没有声明符的 struct/union/enum 定义，如 struct Point { ... };
+                if self.current_token() == &Token::Semicolon {
+                    let decl = match base_type {
+                        CType::StructDef(def) => Declaration::Struct(*def),
+                        CType::UnionDef(def) => Declaration::Union(*def),
+                        CType::EnumDef(def) => Declaration::Enum(*def),
+                        // 不完整类型的前置声明 struct Node;
+                        typ @ (CType::Struct(_) | CType::Union(_)) => Declaration::ForwardDecl(typ),
+                        _ => return Err(self.error("Declaration does not declare anything")),
+                    };
+                    self.advance();
+                    return Ok(vec![decl]);
+                }
+
                 let base_clone = base_type.clone();
-                let (name, full_type) = self.parse_declarator(base_type)?;
+                let Declarator {
+                    name,
+                    typ: full_type,
+                    params,
+                } = self.parse_full_declarator(base_type)?;
 
                 // 函数声明或定义
                 if let CType::Function {
                     return_type,
                     params: param_types,
+                    variadic,
                 } = full_type.clone()
                 {
-                    // 参数名在当前实现中忽略，使用空名
-                    let params: Vec<Param> = param_types
-                        .into_iter()
-                        .map(|t| Param {
-                            typ: t,
-                            name: String::new(),
-                        })
-                        .collect();
+                    // 通过括号声明符等形式得到的函数类型没有参数名，使用空名
+                    let mut params = params.unwrap_or_else(|| {
+                        param_types
+                            .into_iter()
+                            .map(|typ| Param {
+                                typ,
+                                name: String::new(),
+                            })
+                            .collect()
+                    });
+                    // (void) 表示没有参数，不作为参数保留
+                    let explicit_void = matches!(
+                        params.as_slice(),
+                        [Param { typ: CType::Void, name }] if name.is_empty()
+                    );
+                    if explicit_void {
+                        params.clear();
+                    }
 
                     if self.current_token() == &Token::Semicolon {
                         self.advance();
-                        return Ok(Declaration::Function(Function {
+                        return Ok(vec![Declaration::Function(Function {
+                            storage,
+                            is_inline,
                             return_type: *return_type,
                             name,
                             params,
+                            explicit_void,
+                            variadic,
                             body: Vec::new(),
-                        }));
+                        })]);
                     }
 
-                    // 函数定义
-                    self.expect(Token::LBrace)?;
-                    let mut body = Vec::new();
-                    while self.current_token() != &Token::RBrace
-                        && self.current_token() != &Token::Eof
-                    {
-                        body.push(self.parse_statement()?);
+                    // 函数定义；K&R 风格在函数体之前声明参数类型
+                    if self.current_token() != &Token::LBrace && !params.is_empty() {
+                        self.parse_kr_param_decls(&mut params)?;
                     }
+                    self.expect(Token::LBrace)?;
+                    let body = self.parse_block_items()?;
                     self.expect(Token::RBrace)?;
-                    return Ok(Declaration::Function(Function {
+                    return Ok(vec![Declaration::Function(Function {
+                        storage,
+                        is_inline,
                         return_type: *return_type,
                         name,
                         params,
+                        explicit_void,
+                        variadic,
                         body,
-                    }));
+                    })]);
                 }
 
-                // 全局变量：支持逗号分隔的多个声明符。我们仅返回第一个，其余的消费但丢弃。
-                let init = if self.current_token() == &Token::Assign {
-                    self.advance();
-                    if self.current_token() == &Token::LBrace {
-                        // 跳过全局变量的聚合初始化器 { ... }
-                        self.skip_brace_block()?;
-                        None
-                    } else {
-                        Some(self.parse_expr()?)
-                    }
-                } else {
-                    None
-                };
+                // 全局变量：逗号分隔的每个声明符各生成一个 GlobalVar
+                let init = self.parse_optional_init()?;
+                let mut decls = vec![Declaration::GlobalVar {
+                    storage: storage.clone(),
+                    typ: full_type,
+                    name,
+                    init,
+                }];
 
-                // 吃掉逗号分隔的其他声明（丢弃）
+                let base_ref = Self::type_ref(base_clone);
                 while self.current_token() == &Token::Comma {
                     self.advance();
-                    let (_name2, _type2) = self.parse_declarator(base_clone.clone())?;
-                    if self.current_token() == &Token::Assign {
-                        self.advance();
-                        if self.current_token() == &Token::LBrace {
-                            self.skip_brace_block()?;
-                        } else {
-                            // 丢弃一个表达式初始化器
-                            let _ = self.parse_expr()?;
-                        }
-                    }
+                    let (name, typ) = self.parse_declarator(base_ref.clone())?;
+                    let init = self.parse_optional_init()?;
+                    decls.push(Declaration::GlobalVar {
+                        storage: storage.clone(),
+                        typ,
+                        name,
+                        init,
+                    });
                 }
 
                 self.expect(Token::Semicolon)?;
-
-                Ok(Declaration::GlobalVar {
-                    typ: full_type,
-                    name,
-                    init,
-                })
+                Ok(decls)
             }
         }
     }
 
-    pub fn parse_program(&mut self) -> Result<Program, String> {
+    pub fn parse_program(&mut self) -> Result<Program, ParseError> {
         let mut declarations = Vec::new();
 
         while self.current_token() != &Token::Eof {
-            declarations.push(self.parse_declaration()?);
+            declarations.extend(self.parse_declarations()?);
         }
+        // 文件末尾的注释
+        declarations.extend(self.take_comments().into_iter().map(Declaration::Comment));
 
         Ok(Program { declarations })
     }
+
+    // 解析整个翻译单元（parse_program 的简写）
+    pub fn parse(&mut self) -> Result<Program, ParseError> {
+        self.parse_program()
+    }
+
+    /// 带错误恢复的解析：某个顶层声明出错时记录错误，跳过该声明后继续，
+    /// 返回成功解析的声明以及全部错误
+    pub fn parse_program_recovering(&mut self) -> (Program, Vec<ParseError>) {
+        let mut declarations = Vec::new();
+        let mut errors = Vec::new();
+
+        while self.current_token() != &Token::Eof {
+            let start = self.pos;
+            match self.parse_declarations() {
+                Ok(decls) => declarations.extend(decls),
+                Err(err) => {
+                    errors.push(err);
+                    self.pos = start;
+                    self.skip_declaration();
+                }
+            }
+        }
+        declarations.extend(self.take_comments().into_iter().map(Declaration::Comment));
+
+        (Program { declarations }, errors)
+    }
+
+    // 从声明开头跳过整个顶层声明：到最外层的 ';' 为止，
+    // 或者到函数体（紧跟在 ')' 之后的 '{'）配对的 '}' 为止
+    fn skip_declaration(&mut self) {
+        let mut depth = 0usize;
+        let mut in_function_body = false;
+        let mut prev = Token::Eof;
+        loop {
+            let token = self.current_token().clone();
+            match token {
+                Token::Eof => return,
+                Token::Semicolon if depth == 0 => {
+                    self.advance();
+                    return;
+                }
+                Token::LBrace => {
+                    if depth == 0 {
+                        in_function_body = prev == Token::RParen;
+                    }
+                    depth += 1;
+                }
+                Token::RBrace if depth > 0 => {
+                    depth -= 1;
+                    if depth == 0 && in_function_body {
+                        self.advance();
+                        return;
+                    }
+                }
+                _ => {}
+            }
+            self.advance();
+            prev = token;
+        }
+    }
 }
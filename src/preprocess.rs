@@ -0,0 +1,162 @@
+// 解析前的源码清理：本工具不做完整的预处理，先去掉解析器无法处理的预处理指令、
+// GNU __attribute__ 块和部分关键字
+
+#[derive(Debug, Clone)]
+pub struct SanitizeOptions {
+    // 去掉预处理指令行（含反斜杠续行）；#include 与 #pragma 是否保留分别由
+    // strip_includes、strip_pragmas 决定
+    pub strip_directives: bool,
+    // 同时去掉 #include 行；默认保留，由词法分析器处理
+    pub strip_includes: bool,
+    // 同时去掉 #pragma 行；默认保留，解析为 Declaration::Pragma
+    pub strip_pragmas: bool,
+    // 移除 __attribute__((...)) 块
+    pub remove_attributes: bool,
+    // 作为独立单词出现时直接删除的关键字或宏名
+    pub removed_keywords: Vec<String>,
+}
+
+impl Default for SanitizeOptions {
+    fn default() -> Self {
+        SanitizeOptions {
+            strip_directives: true,
+            strip_includes: false,
+            strip_pragmas: false,
+            remove_attributes: true,
+            // inline 与 restrict 由词法分析器识别
+            removed_keywords: vec!["_Noreturn".to_string(), "noreturn".to_string()],
+        }
+    }
+}
+
+pub fn sanitize(src: &str, opts: &SanitizeOptions) -> String {
+    let mut s = if opts.strip_directives || opts.strip_includes || opts.strip_pragmas {
+        strip_directive_lines(src, opts)
+    } else {
+        src.to_string()
+    };
+    if opts.remove_attributes {
+        s = remove_attribute_blocks(&s, "__attribute__");
+    }
+    for kw in &opts.removed_keywords {
+        s = replace_word(&s, kw, "");
+    }
+    s
+}
+
+// 去掉预处理指令行，并跳过以反斜杠续行的后续行（整个多行宏定义一并移除）
+fn strip_directive_lines(src: &str, opts: &SanitizeOptions) -> String {
+    let mut out_lines: Vec<&str> = Vec::new();
+    let mut iter = src.lines();
+    while let Some(line) = iter.next() {
+        let t = line.trim_start();
+        let strip = if t.starts_with("#include") {
+            opts.strip_includes
+        } else if t.starts_with("#pragma") {
+            opts.strip_pragmas
+        } else {
+            t.starts_with('#') && opts.strip_directives
+        };
+        if !strip {
+            out_lines.push(line);
+            continue;
+        }
+        if !t.trim_end().ends_with('\\') {
+            continue;
+        }
+        for next_line in iter.by_ref() {
+            if !next_line.trim_end().ends_with('\\') {
+                break;
+            }
+        }
+    }
+    out_lines.join("\n")
+}
+
+// 移除 marker((...)) 块，按括号配平匹配，支持嵌套括号。
+// marker 后面不是 '(' 时只删除 marker 本身；括号不配平时也只删除 marker，
+// 保留其后的文本交给解析器报错，避免吞掉文件剩余部分
+pub fn remove_attribute_blocks(input: &str, marker: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(pos) = find_word(rest, marker) {
+        out.push_str(&rest[..pos]);
+        let after = &rest[pos + marker.len()..];
+        let trimmed = after.trim_start();
+        rest = match trimmed.strip_prefix('(') {
+            Some(_) => match matching_paren(trimmed) {
+                Some(end) => &trimmed[end..],
+                None => after,
+            },
+            None => after,
+        };
+    }
+    out.push_str(rest);
+    out
+}
+
+// s 以 '(' 开头，返回与之配平的 ')' 之后的字节位置；字符串与字符字面量中的括号不计入
+fn matching_paren(s: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut chars = s.char_indices();
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '"' | '\'' => {
+                // 跳到配对的引号，反斜杠转义的字符一并跳过
+                loop {
+                    match chars.next()?.1 {
+                        '\\' => {
+                            chars.next()?;
+                        }
+                        c if c == ch => break,
+                        _ => {}
+                    }
+                }
+            }
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// 查找作为独立单词出现的 word，避免匹配到标识符子串
+fn find_word(input: &str, word: &str) -> Option<usize> {
+    let mut start = 0usize;
+    while let Some(pos) = input[start..].find(word) {
+        let abs = start + pos;
+        let left_ok = !input[..abs].chars().next_back().is_some_and(is_ident_char);
+        let right_ok = !input[abs + word.len()..]
+            .chars()
+            .next()
+            .is_some_and(is_ident_char);
+        if left_ok && right_ok {
+            return Some(abs);
+        }
+        start = abs + word.len();
+    }
+    None
+}
+
+// 简单基于分隔符的词替换，避免替换到标识符子串
+pub fn replace_word(input: &str, word: &str, repl: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(pos) = find_word(rest, word) {
+        out.push_str(&rest[..pos]);
+        out.push_str(repl);
+        rest = &rest[pos + word.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn is_ident_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
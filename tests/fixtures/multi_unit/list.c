@@ -0,0 +1,8 @@
+usize length(Node *head) {
+    usize n = 0;
+    while (head) {
+        n++;
+        head = head->next;
+    }
+    return n;
+}
@@ -0,0 +1,7 @@
+typedef struct Node Node;
+typedef unsigned long usize;
+
+struct Node {
+    int value;
+    Node *next;
+};
@@ -0,0 +1,7 @@
+int ok(void) {
+    return 0;
+}
+
+int broken( {
+    return 1;
+}
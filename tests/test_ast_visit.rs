@@ -0,0 +1,157 @@
+/// 测试 AST 遍历接口
+use c_to_rust_tool::ast::*;
+use c_to_rust_tool::ast_visit::{
+    called_functions, forward_declared_structs, undefined_typedefs, validate_labels,
+    validate_loop_control, walk_program, walk_type, Visitor,
+};
+use c_to_rust_tool::parse_str;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+struct Node {
+    int value;
+    struct Node* next;
+};
+
+int main() {
+    struct Node* head = (struct Node*)malloc(sizeof(struct Node));
+    head->value = 42;
+    if (head->value > 0) {
+        printf("%d\n", head->value);
+    }
+    for (int i = 0; i < 3; i++) {
+        printf("%d\n", ({ int t = abs(i); t; }));
+    }
+    free(head);
+    return 0;
+}
+"#;
+
+    #[test]
+    fn test_called_functions() {
+        let program = parse_str(SAMPLE).unwrap();
+        assert_eq!(
+            called_functions(&program),
+            vec!["malloc", "printf", "abs", "free"]
+        );
+    }
+
+    // 统计出现的 struct 类型引用，验证 visit_type 会深入指针、强制转换与 sizeof
+    #[derive(Default)]
+    struct StructRefs(usize);
+
+    impl Visitor for StructRefs {
+        fn visit_type(&mut self, typ: &CType) {
+            if matches!(typ, CType::Struct(_)) {
+                self.0 += 1;
+            }
+            walk_type(self, typ);
+        }
+    }
+
+    #[test]
+    fn test_visit_types() {
+        let program = parse_str(SAMPLE).unwrap();
+        let mut refs = StructRefs::default();
+        walk_program(&mut refs, &program);
+        // 字段 next、变量 head、强制转换与 sizeof 各一处
+        assert_eq!(refs.0, 4);
+    }
+
+    fn first_function(src: &str) -> Function {
+        match parse_str(src).unwrap().declarations.into_iter().next() {
+            Some(Declaration::Function(func)) => func,
+            other => panic!("Expected function, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_forward_goto() {
+        let func = first_function(
+            "int f(int x) { if (x) goto done; switch (x) { case 1: retry: x--; goto retry; } done: return x; }",
+        );
+        assert!(validate_labels(&func).is_empty());
+    }
+
+    #[test]
+    fn test_missing_goto_label() {
+        let func = first_function(
+            "void f(int x) { if (x) goto fail; while (x) { goto out; goto fail; } out: return; }",
+        );
+        assert_eq!(validate_labels(&func), vec!["fail"]);
+    }
+
+    #[test]
+    fn test_nested_loop_control_is_valid() {
+        let func = first_function(
+            "int f(int x) { while (x) { for (;;) { if (x) break; continue; } switch (x) { case 1: break; default: continue; } do { break; } while (x); } return x; }",
+        );
+        assert!(validate_loop_control(&func).is_empty());
+    }
+
+    #[test]
+    fn test_loop_control_outside_loop() {
+        let func = first_function(
+            "void f(int x) {\n    if (x) break;\n    switch (x) { case 1: continue; }\n    while (x) break;\n}",
+        );
+        let messages: Vec<String> = validate_loop_control(&func)
+            .iter()
+            .map(|err| err.to_string())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "2:12: 'break' statement not in loop or switch statement",
+                "3:26: 'continue' statement not in loop statement",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_forward_declared_structs() {
+        let program = parse_str(
+            "typedef struct Node Node;\n\
+             struct List { Node *head; struct Tail *tail; struct List *self; };\n\
+             struct Node { int v; struct Node *next; };\n\
+             int f(struct Node *n) { struct Other *o; union Val *v; return 0; }",
+        )
+        .unwrap();
+        assert_eq!(
+            forward_declared_structs(&program),
+            vec![
+                CType::Struct("Node".to_string()),
+                CType::Struct("Tail".to_string()),
+                CType::Struct("Other".to_string()),
+                CType::Union("Val".to_string()),
+            ]
+        );
+        let program = parse_str("struct P { int x; }; struct P origin;").unwrap();
+        assert!(forward_declared_structs(&program).is_empty());
+        // 源码中已有前置声明的不再重复收集
+        let program = parse_str("union U; struct S; union U *u; struct S *s;").unwrap();
+        assert!(forward_declared_structs(&program).is_empty());
+    }
+
+    #[test]
+    fn test_undefined_typedefs() {
+        let program = parse_str("typedef int myint; myint f(myint x) { return x; }").unwrap();
+        assert!(undefined_typedefs(&program).is_empty());
+        let mut program = parse_str("typedef int myint; int g(myint x) { return x; }").unwrap();
+        program.declarations.push(Declaration::GlobalVar {
+            storage: None,
+            typ: CType::Pointer(Box::new(CType::Typedef("FILE".to_string()))),
+            name: "out".to_string(),
+            init: None,
+        });
+        program.declarations.push(Declaration::GlobalVar {
+            storage: None,
+            typ: CType::Typedef("size_t".to_string()),
+            name: "len".to_string(),
+            init: None,
+        });
+        assert_eq!(undefined_typedefs(&program), vec!["FILE", "size_t"]);
+    }
+}
@@ -0,0 +1,71 @@
+/// 测试命令行入口：读取输入文件并写出生成结果
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("c_to_rust_tool_{}_{}", std::process::id(), name))
+    }
+
+    fn run(args: &[&str]) -> std::process::Output {
+        Command::new(env!("CARGO_BIN_EXE_c-to-rust-tool"))
+            .args(args)
+            .output()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_cli_writes_rust_output() {
+        let input = temp_path("add.c");
+        let output = temp_path("add.rs");
+        fs::write(&input, "int add(int a, int b) { return a + b; }\n").unwrap();
+
+        let result = run(&[input.to_str().unwrap(), "-o", output.to_str().unwrap()]);
+        assert!(result.status.success());
+        let generated = fs::read_to_string(&output).unwrap();
+        assert!(generated.contains("fn add(a: i32, b: i32) -> i32"));
+
+        fs::remove_file(input).unwrap();
+        fs::remove_file(output).unwrap();
+    }
+
+    #[test]
+    fn test_cli_prints_c_to_stdout() {
+        let input = temp_path("main.c");
+        fs::write(&input, "int main(){return 0;}").unwrap();
+
+        let result = run(&[input.to_str().unwrap()]);
+        assert!(result.status.success());
+        let stdout = String::from_utf8(result.stdout).unwrap();
+        assert!(stdout.contains("int main() {\n    return 0;\n}"));
+
+        fs::remove_file(input).unwrap();
+    }
+
+    #[test]
+    fn test_cli_fails_on_parse_error() {
+        let input = temp_path("broken.c");
+        fs::write(&input, "int f( {\n").unwrap();
+
+        let result = run(&[input.to_str().unwrap()]);
+        assert_eq!(result.status.code(), Some(1));
+        let stderr = String::from_utf8(result.stderr).unwrap();
+        assert!(
+            stderr.contains(":1:8: Expected type, got '{'"),
+            "{}",
+            stderr
+        );
+
+        fs::remove_file(input).unwrap();
+    }
+
+    #[test]
+    fn test_cli_usage_errors() {
+        assert_eq!(run(&["a.c", "-o"]).status.code(), Some(2));
+        assert_eq!(run(&["a.c", "b.c"]).status.code(), Some(2));
+    }
+}
@@ -0,0 +1,1508 @@
+/// 测试代码生成功能
+use c_to_rust_tool::ast::*;
+use c_to_rust_tool::codegen::{BraceStyle, CodeGenerator, Emit, IndentStyle, TargetLang};
+use c_to_rust_tool::lexer::Lexer;
+use c_to_rust_tool::parser::Parser;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rust_type(typ: CType) -> String {
+        CodeGenerator::new().generate_rust_type(&typ)
+    }
+
+    fn to_rust(source: &str) -> String {
+        let program = Parser::new(source).parse().unwrap();
+        CodeGenerator::with_target(TargetLang::Rust).generate_program(&program)
+    }
+
+    #[test]
+    fn test_rust_primitive_types() {
+        assert_eq!(rust_type(CType::Int), "i32");
+        assert_eq!(rust_type(CType::Char), "i8");
+        assert_eq!(rust_type(CType::UnsignedInt), "u32");
+        assert_eq!(rust_type(CType::Long), "i64");
+        assert_eq!(rust_type(CType::Float), "f32");
+        assert_eq!(rust_type(CType::Double), "f64");
+        assert_eq!(rust_type(CType::Void), "()");
+        assert_eq!(rust_type(CType::UnsignedChar), "u8");
+        assert_eq!(rust_type(CType::Short), "i16");
+        assert_eq!(rust_type(CType::UnsignedShort), "u16");
+        assert_eq!(rust_type(CType::UnsignedLong), "u64");
+    }
+
+    #[test]
+    fn test_rust_pointer_and_array_types() {
+        assert_eq!(rust_type(CType::Pointer(Box::new(CType::Int))), "*mut i32");
+        assert_eq!(
+            rust_type(CType::Pointer(Box::new(CType::Pointer(Box::new(
+                CType::Char
+            ))))),
+            "*mut *mut i8"
+        );
+        assert_eq!(
+            rust_type(CType::Pointer(Box::new(CType::Void))),
+            "*mut std::ffi::c_void"
+        );
+        assert_eq!(
+            rust_type(CType::Array {
+                element_type: Box::new(CType::Int),
+                size: Some(10),
+                size_expr: None,
+            }),
+            "[i32; 10]"
+        );
+    }
+
+    #[test]
+    fn test_rust_const_pointers() {
+        let ptr = |inner: CType| CType::Pointer(Box::new(inner));
+        let konst = |inner: CType| CType::Const(Box::new(inner));
+        assert_eq!(rust_type(ptr(konst(CType::Int))), "*const i32");
+        assert_eq!(rust_type(ptr(CType::Int)), "*mut i32");
+        // 指针自身的 const 不影响映射
+        assert_eq!(rust_type(konst(ptr(CType::Int))), "*mut i32");
+        assert_eq!(rust_type(konst(ptr(konst(CType::Char)))), "*const i8");
+        assert_eq!(
+            rust_type(ptr(konst(ptr(konst(CType::Char))))),
+            "*const *const i8"
+        );
+        assert_eq!(
+            rust_type(ptr(CType::Volatile(Box::new(konst(CType::Void))))),
+            "*const std::ffi::c_void"
+        );
+
+        let output = to_rust(
+            "const char *name(void) { return 0; }\nint *slot(int *restrict p) { return p + 1; }",
+        );
+        assert!(output.contains("fn name() -> *const i8"), "{}", output);
+        assert!(
+            output.contains("fn slot(p: *mut i32) -> *mut i32"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_rust_named_types() {
+        assert_eq!(rust_type(CType::Struct("Point".to_string())), "Point");
+        assert_eq!(rust_type(CType::Typedef("size_t".to_string())), "size_t");
+        assert_eq!(
+            rust_type(CType::Pointer(Box::new(CType::Struct("Node".to_string())))),
+            "*mut Node"
+        );
+    }
+
+    #[test]
+    fn test_default_target_is_c() {
+        let program = Parser::new("int add(int a, int b) { return a + b; }")
+            .parse()
+            .unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert!(output.starts_with("int add("));
+        assert!(output.contains("return a + b;"));
+    }
+
+    #[test]
+    fn test_rust_function() {
+        let output = to_rust("int square(int x) { int y = x * x; return y; }");
+        assert!(output.starts_with("fn square(") && output.contains(") -> i32 {\n"));
+        assert!(output.contains("    let mut y: i32 = (x * x);\n"));
+        assert!(output.contains("    return y;\n"));
+    }
+
+    #[test]
+    fn test_rust_void_function_has_no_return_type() {
+        let output = to_rust("void tick(void) { count++; }");
+        assert!(output.starts_with("fn tick() {\n"));
+        assert!(output.contains("    count += 1;\n"));
+    }
+
+    #[test]
+    fn test_rust_increment_statements() {
+        let output = to_rust("int f(int i) { i++; ++i; i--; i++, --i; return i; }");
+        assert!(
+            output.contains("    i += 1;\n    i += 1;\n    i -= 1;\n    i += 1;\n    i -= 1;\n")
+        );
+        assert!(!output.contains("TODO"), "{}", output);
+    }
+
+    #[test]
+    fn test_rust_increment_as_value() {
+        let output = to_rust("int f(int i) { int x; x = ++i; x = i++; return x; }");
+        assert!(
+            output
+                .contains("    // TODO: side-effecting ++ in expression\n    x = { i += 1; i };\n"),
+            "{}",
+            output
+        );
+        assert!(output.contains(
+            "    // TODO: side-effecting ++ in expression\n    x = { let tmp = i; i += 1; tmp };\n"
+        ));
+        // 循环的更新部分与条件中的自增分开处理
+        let output = to_rust("int g(int n) { int i; for (i = 0; i < n; i++) { n--; } while (n-- > 0) { } return i; }");
+        assert_eq!(output.matches("TODO").count(), 1, "{}", output);
+    }
+
+    #[test]
+    fn test_rust_logical_not() {
+        let output =
+            to_rust("int f(int x, int m) { int y = !x; int z = ~m; int w = !(x > 0); return y; }");
+        assert!(
+            output.contains("let mut y: i32 = ((x == 0) as i32);"),
+            "{}",
+            output
+        );
+        assert!(output.contains("let mut z: i32 = (!m);"), "{}", output);
+        // 操作数已经是 bool 时直接使用 !
+        assert!(output.contains("(!(x > 0))"), "{}", output);
+    }
+
+    #[test]
+    fn test_rust_conditions_are_bool() {
+        let output = to_rust(
+            "int g; int *gp; int f(int x, int *p) { int *q = p; if (!x) return 1; if (!p) return 2; while (q && !g) q = 0; do { x--; } while (x); if (gp != 0 || x > 1) x = 3; return x ? !q : !x; }",
+        );
+        for expected in [
+            "    if (x == 0) {\n",
+            "    if p.is_null() {\n",
+            "    while (!q.is_null() && (g == 0)) {\n",
+            "        if !((x != 0)) {\n",
+            "    if (!gp.is_null() || (x > 1)) {\n",
+            // 作为值使用时才转换为 i32
+            "    return if (x != 0) { (q.is_null() as i32) } else { ((x == 0) as i32) };\n",
+        ] {
+            assert!(output.contains(expected), "{}\n{}", expected, output);
+        }
+        // 参数与局部变量的指针信息不带到下一个函数
+        let output = to_rust("void f(int *p) { } int g(int p) { return !p; }");
+        assert!(output.contains("return ((p == 0) as i32);"), "{}", output);
+    }
+
+    #[test]
+    fn test_rust_continue_runs_for_update() {
+        let output = to_rust(
+            "int f(int n) { int s = 0; for (int i = 1; i < n; i *= 2) { if (i == 4) continue; while (s > 100) { s--; continue; } s += i; } return s; }",
+        );
+        assert!(
+            output.contains("            if (i == 4) {\n                i = (i * 2);\n                continue;\n            }\n"),
+            "{}",
+            output
+        );
+        // 内层 while 的 continue 不执行外层的 update
+        assert!(
+            output.contains("                s -= 1;\n                continue;\n"),
+            "{}",
+            output
+        );
+        assert!(output.ends_with("            s = (s + i);\n            i = (i * 2);\n        }\n    }\n    return s;\n}\n\n"));
+    }
+
+    #[test]
+    fn test_rust_do_while_continue_checks_condition() {
+        let output = to_rust(
+            "int f(int i) { do { i++; if (i < 3) continue; while (i > 100) { i--; continue; } } while (i < 5); return i; }",
+        );
+        // continue 之前先检查 do-while 的条件，不满足时退出 loop
+        assert!(
+            output.contains("        if (i < 3) {\n            if !((i < 5)) {\n                break;\n            }\n            continue;\n        }\n"),
+            "{}",
+            output
+        );
+        // 内层 while 的 continue 不受影响
+        assert!(
+            output.contains("            i -= 1;\n            continue;\n"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("        if !((i < 5)) {\n            break;\n        }\n    }\n"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_rust_struct_point() {
+        let output = to_rust("struct Point {\n    int x;\n    int y;\n};");
+        assert_eq!(
+            output,
+            "#[repr(C)]\npub struct Point {\n    pub x: i32,\n    pub y: i32,\n}\n\n"
+        );
+    }
+
+    #[test]
+    fn test_rust_struct_node() {
+        let output = to_rust("struct Node {\n    int value;\n    struct Node* next;\n};");
+        assert_eq!(
+            output,
+            "#[repr(C)]\npub struct Node {\n    pub value: i32,\n    pub next: *mut Node,\n}\n\n"
+        );
+    }
+
+    #[test]
+    fn test_rust_struct_array_field() {
+        let output = to_rust("struct Buf { char data[16]; int len; };");
+        assert!(output.contains("    pub data: [i8; 16],\n"));
+    }
+
+    #[test]
+    fn test_rust_anonymous_struct_name() {
+        let anon = |fields: Vec<StructField>| StructDef {
+            name: String::new(),
+            fields,
+        };
+        let field = StructField {
+            typ: CType::Int,
+            name: "a".to_string(),
+            bit_width: None,
+        };
+        let generator = CodeGenerator::with_target(TargetLang::Rust);
+        let first = generator.generate_struct(&anon(vec![field.clone()]));
+        let second = generator.generate_struct(&anon(vec![field]));
+        assert!(first.starts_with("#[repr(C)]\npub struct AnonStruct0 {\n"));
+        assert!(second.starts_with("#[repr(C)]\npub struct AnonStruct1 {\n"));
+    }
+
+    #[test]
+    fn test_rust_control_flow() {
+        let output = to_rust(
+            "int f(int n) { int i; i = 0; while (i < n) { if (i > 5) { break; } i++; } return i; }",
+        );
+        assert!(output.contains("    while (i < n) {\n"));
+        assert!(output.contains("        if (i > 5) {\n"));
+        assert!(output.contains("        i += 1;\n"));
+    }
+
+    #[test]
+    fn test_rust_malloc_struct_becomes_box() {
+        let output = to_rust(
+            "int main() { struct Node* head = (struct Node*)malloc(sizeof(struct Node)); head->value = 42; return 0; }",
+        );
+        assert!(output.contains(
+            "let mut head: *mut Node = Box::into_raw(Box::new(unsafe { std::mem::zeroed::<Node>() }));"
+        ));
+    }
+
+    #[test]
+    fn test_rust_malloc_fallback() {
+        // 分配大小与指针目标类型不符时不能改写成 Box
+        let output = to_rust("int main() { int* buf = (int*)malloc(sizeof(int) * 10); return 0; }");
+        assert!(output.contains(
+            "let mut buf: *mut i32 = (libc::malloc((std::mem::size_of::<i32>() * 10)) as *mut i32);"
+        ));
+    }
+
+    #[test]
+    fn test_rust_printf_with_newline() {
+        let output = to_rust(r#"int main() { int x = 1; printf("x=%d\n", x); return 0; }"#);
+        assert!(output.contains("    println!(\"x={}\", x);\n"));
+    }
+
+    #[test]
+    fn test_rust_printf_without_newline() {
+        let output =
+            to_rust(r#"int main() { printf("%s: %5d|%-3c|%.2f%%", name, n, c, f); return 0; }"#);
+        assert!(output.contains(r#"print!("{}: {:5}|{:<3}|{:.2}%", name, n, c, f);"#));
+    }
+
+    #[test]
+    fn test_rust_puts() {
+        let output = to_rust(r#"int main() { puts("a {b}"); return 0; }"#);
+        assert!(output.contains(r#"println!("a {{b}}");"#));
+    }
+
+    #[test]
+    fn test_rust_printf_fallback() {
+        // 格式串不是字面量时保持普通函数调用
+        let output = to_rust("int main() { printf(fmt, x); foo(x); return 0; }");
+        assert!(output.contains("    printf(fmt, x);\n"));
+        assert!(output.contains("    foo(x);\n"));
+    }
+
+    #[test]
+    fn test_rust_null_comparison() {
+        let output = to_rust(
+            "int main() { if (head->next == NULL) { return 1; } if (NULL != p) { p = NULL; } return 0; }",
+        );
+        assert!(output.contains("    if (*head).next.is_null() {\n"));
+        assert!(output.contains("    if !p.is_null() {\n"));
+        assert!(output.contains("        p = std::ptr::null_mut();\n"));
+    }
+
+    #[test]
+    fn test_c_null_unchanged() {
+        let program = Parser::new("int main() { if (head->next == NULL) { return 1; } return 0; }")
+            .parse()
+            .unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert!(output.contains("if (head->next == NULL) {"));
+    }
+
+    #[test]
+    fn test_c_switch_roundtrip() {
+        let program = Parser::new(
+            "int f(int x) { switch (x) { case 1: case 2: return 2; default: break; } return 0; }",
+        )
+        .parse()
+        .unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert!(output.contains(
+            "    switch (x) {\n        case 1:\n        case 2:\n            return 2;\n        default:\n            break;\n    }\n"
+        ));
+    }
+
+    #[test]
+    fn test_rust_switch_to_match() {
+        let output = to_rust(
+            "int f(int x) { switch (x) { default: x = 0; break; case 1: case 2: x = 2; break; } return x; }",
+        );
+        assert!(output.contains(
+            "    match x {\n        1 | 2 => {\n            x = 2;\n        }\n        _ => {\n            x = 0;\n        }\n    }\n"
+        ));
+    }
+
+    #[test]
+    fn test_rust_switch_nested_break_uses_label() {
+        let output = to_rust(
+            "int f(int i) { while (i < 10) { switch (i) { case 1: if (i) break; i = 2; break; case 3: continue; default: switch (i) { case 4: i = 5; break; } } i++; } return i; }",
+        );
+        // 分支中间的 break 跳出带标签的块，而不是外层循环
+        assert!(
+            output.contains("                1 => {\n                    if (i != 0) {\n                        break 'switch2;\n                    }\n                    i = 2;\n                }\n"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("        'switch2: {\n            match i {\n"),
+            "{}",
+            output
+        );
+        // 带标签的块中 continue 必须写明循环标签
+        assert!(
+            output.contains("    'outer: while (i < 10) {\n"),
+            "{}",
+            output
+        );
+        assert!(output.contains("continue 'outer;\n"), "{}", output);
+        // 只有末尾 break 的 switch 不加标签
+        assert!(
+            output.contains("                _ => {\n                    match i {\n"),
+            "{}",
+            output
+        );
+        assert_eq!(output.matches("'switch").count(), 2, "{}", output);
+    }
+
+    #[test]
+    fn test_c_label_roundtrip() {
+        let program = Parser::new("int f() { int i = 0; loop: i++; goto loop; }")
+            .parse()
+            .unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert!(output.contains("    loop:\n    i++;\n    goto loop;\n"));
+    }
+
+    #[test]
+    fn test_c_multi_dimensional_arrays() {
+        let program = Parser::new(
+            "int grid[2][5]; int f() { int a[3][4]; char b[2][3][4]; int *p[8]; return 0; }",
+        )
+        .parse()
+        .unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert!(output.contains("int grid[2][5];\n"));
+        assert!(output.contains("    int a[3][4];\n"));
+        assert!(output.contains("    char b[2][3][4];\n"));
+        assert!(output.contains("    int *p[8];\n"));
+    }
+
+    #[test]
+    fn test_rust_multi_dimensional_array() {
+        let output = to_rust("int f() { int a[3][4]; return 0; }");
+        assert!(output.contains("    let mut a: [[i32; 4]; 3];\n"));
+    }
+
+    #[test]
+    fn test_c_function_param_names() {
+        let program = Parser::new("int f(int a, int b) { return a; }")
+            .parse()
+            .unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert!(output.starts_with("int f(int a, int b) {\n"));
+    }
+
+    #[test]
+    fn test_rust_function_param_names() {
+        let output = to_rust("int f(int a, char *s) { return a; }");
+        assert!(output.starts_with("fn f(a: i32, s: *mut i8) -> i32 {\n"));
+    }
+
+    #[test]
+    fn test_c_function_pointer_typedef_roundtrip() {
+        let source = "typedef int (*cmp)(const void*, const void*);";
+        let program = Parser::new(source).parse().unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert_eq!(output.trim_end(), source);
+        // 再次解析生成的代码应得到相同的 AST
+        assert_eq!(Parser::new(&output).parse().unwrap(), program);
+    }
+
+    #[test]
+    fn test_c_function_pointer_declarations() {
+        let program = Parser::new(
+            "int (*handler)(int, int); int apply(int (*op)(int, int), int x) { return op(x, x); }",
+        )
+        .parse()
+        .unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert!(output.contains("int (*handler)(int, int);\n"));
+        assert!(output.contains("int apply(int (*op)(int, int), int x) {\n"));
+    }
+
+    #[test]
+    fn test_variadic_functions() {
+        let source =
+            "typedef int (*logger)(const char*, ...);\n\nint sum(int n, ...) {\n    return n;\n}";
+        c_roundtrip(source);
+        let program = Parser::new(source).parse().unwrap();
+        let Declaration::Function(sum) = &program.declarations[1] else {
+            panic!("Expected function, got {:?}", program.declarations[1]);
+        };
+        assert!(sum.variadic);
+        assert_eq!(sum.params.len(), 1);
+        let output = to_rust(source);
+        assert!(
+            output.contains("type logger = unsafe extern \"C\" fn(*const i8, ...) -> i32;\n"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("unsafe extern \"C\" fn sum(n: i32, mut args: ...) -> i32 {\n"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_rust_function_pointer_typedef() {
+        let output = to_rust("typedef int (*binop)(int, int);");
+        assert_eq!(output, "type binop = fn(i32, i32) -> i32;\n\n");
+    }
+
+    #[test]
+    fn test_c_enum_value_expressions() {
+        let program = Parser::new("enum Flags { A = 1 << 3, B = A + 1, C };")
+            .parse()
+            .unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert_eq!(
+            output,
+            "enum Flags {\n    A = 1 << 3,\n    B = A + 1,\n    C\n};\n\n"
+        );
+    }
+
+    #[test]
+    fn test_c_struct_bitfields() {
+        let source = "struct Flags {\n    unsigned int ready : 1;\n    unsigned int : 3;\n    int count;\n};";
+        let program = Parser::new(source).parse().unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert_eq!(output.trim_end(), source);
+    }
+
+    #[test]
+    fn test_rust_struct_bitfields() {
+        let output =
+            to_rust("struct Flags { unsigned int ready : 1; unsigned int : 3; int count; };");
+        assert!(output.contains("    pub ready: u32, // bit-field width 1\n    pub count: i32,\n"));
+    }
+
+    fn c_roundtrip(source: &str) {
+        let program = Parser::new(source).parse().unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert_eq!(output.trim_end(), source);
+        assert_eq!(Parser::new(&output).parse().unwrap(), program);
+    }
+
+    fn parse_with_comments(source: &str) -> Program {
+        Parser::from_lexer(Lexer::new(source).with_comments(true))
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_comments_preserved() {
+        let source = "// header\nint f(int n) {\n    /* count down */\n    while (n) {\n        n--;\n    }\n    return n;\n}\n\n// end\n";
+        let program = parse_with_comments(source);
+        assert_eq!(
+            program.declarations[0],
+            Declaration::Comment("// header".to_string())
+        );
+        let output = CodeGenerator::new().generate_program(&program);
+        assert_eq!(output, source);
+        assert_eq!(parse_with_comments(&output), program);
+
+        // 默认不保留注释
+        let program = Parser::new(source).parse().unwrap();
+        assert!(!CodeGenerator::new()
+            .generate_program(&program)
+            .contains("header"));
+    }
+
+    #[test]
+    fn test_rust_comments() {
+        let program = parse_with_comments(
+            "// header\nint f(int n) {\n    /* a /* b */\n    return n; // done\n}",
+        );
+        let output = CodeGenerator::with_target(TargetLang::Rust).generate_program(&program);
+        assert!(output.starts_with(
+            "// header\nfn f(n: i32) -> i32 {\n    // a /* b\n    return n;\n    // done\n}"
+        ));
+    }
+
+    const STYLE_SAMPLE: &str =
+        "int f(int n) { if (n > 0) { n--; } else { n = 1; } while (n) n--; return n; }";
+
+    #[test]
+    fn test_c_tab_indentation() {
+        let program = Parser::new(STYLE_SAMPLE).parse().unwrap();
+        let output = CodeGenerator::new()
+            .with_indent_style(IndentStyle::Tabs)
+            .generate_program(&program);
+        assert_eq!(
+            output,
+            "int f(int n) {\n\tif (n > 0) {\n\t\tn--;\n\t} else {\n\t\tn = 1;\n\t}\n\twhile (n) {\n\t\tn--;\n\t}\n\treturn n;\n}\n\n"
+        );
+    }
+
+    #[test]
+    fn test_c_allman_braces() {
+        let program = Parser::new(STYLE_SAMPLE).parse().unwrap();
+        let output = CodeGenerator::new()
+            .with_indent_style(IndentStyle::Spaces(2))
+            .with_brace_style(BraceStyle::NextLine)
+            .generate_program(&program);
+        assert_eq!(
+            output,
+            "int f(int n)\n{\n  if (n > 0)\n  {\n    n--;\n  }\n  else\n  {\n    n = 1;\n  }\n  while (n)\n  {\n    n--;\n  }\n  return n;\n}\n\n"
+        );
+        assert_eq!(Parser::new(&output).parse().unwrap(), program);
+
+        let program = Parser::new("struct P { int x; };").parse().unwrap();
+        let output = CodeGenerator::new()
+            .with_brace_style(BraceStyle::NextLine)
+            .generate_program(&program);
+        assert_eq!(output, "struct P\n{\n    int x;\n};\n\n");
+    }
+
+    #[test]
+    fn test_rust_indent_style() {
+        let program =
+            Parser::new("struct P { int x; }; int f(int n) { if (n > 0) { return 1; } return 0; }")
+                .parse()
+                .unwrap();
+        let output = CodeGenerator::with_target(TargetLang::Rust)
+            .with_indent_style(IndentStyle::Tabs)
+            .with_brace_style(BraceStyle::NextLine)
+            .generate_program(&program);
+        assert!(output.contains("pub struct P {\n\tpub x: i32,\n}"));
+        assert!(output.contains("\tif (n > 0) {\n\t\treturn 1;\n\t}\n"));
+    }
+
+    #[test]
+    fn test_c_forward_declarations() {
+        let source = "#include <stdlib.h>\ntypedef struct Node Node;\nstruct Node { int value; Node *next; };";
+        let program = Parser::new(source).parse().unwrap();
+        let output = CodeGenerator::new()
+            .with_forward_decls(true)
+            .generate_program(&program);
+        assert_eq!(
+            output,
+            "#include <stdlib.h>\nstruct Node;\n\ntypedef struct Node Node;\n\nstruct Node {\n    int value;\n    Node* next;\n};\n\n"
+        );
+        // 重新解析后前置声明保留在 AST 中，再次生成不会重复
+        let reparsed = Parser::new(&output).parse().unwrap();
+        assert_eq!(
+            CodeGenerator::new()
+                .with_forward_decls(true)
+                .generate_program(&reparsed),
+            output
+        );
+        // 默认不输出
+        assert!(!CodeGenerator::new()
+            .generate_program(&program)
+            .contains("struct Node;"));
+        // 源码中的前置声明原样保留，联合体同样补全前置声明
+        let program = Parser::new("struct Node;\nunion Value;\nunion Data *d;")
+            .parse()
+            .unwrap();
+        assert_eq!(
+            CodeGenerator::new().generate_program(&program),
+            "struct Node;\n\nunion Value;\n\nunion Data *d;\n\n"
+        );
+        assert_eq!(
+            CodeGenerator::new()
+                .with_forward_decls(true)
+                .generate_program(&program),
+            "union Data;\n\nstruct Node;\n\nunion Value;\n\nunion Data *d;\n\n"
+        );
+        // Rust 不需要前置声明
+        assert!(!to_rust("struct Node;").contains("Node"));
+    }
+
+    #[test]
+    fn test_rust_libc_prelude() {
+        let rust_with_prelude = |source: &str| {
+            let program = Parser::new(source).parse().unwrap();
+            CodeGenerator::with_target(TargetLang::Rust)
+                .with_rust_prelude(true)
+                .generate_program(&program)
+        };
+        let output = rust_with_prelude(
+            "int main() { int* buf = (int*)malloc(sizeof(int) * 10); buf[0] = 1; return buf[0]; }",
+        );
+        assert!(output.starts_with("use libc::*;\n\n"), "{}", output);
+        assert_eq!(output.matches("use libc::*;").count(), 1);
+        // 没有用到 libc 时不输出
+        let output = rust_with_prelude("int add(int a, int b) { return a + b; }");
+        assert!(!output.contains("use libc"), "{}", output);
+        assert!(output.starts_with("fn add("), "{}", output);
+        // 默认关闭
+        let output = to_rust("int main() { int* buf = (int*)malloc(sizeof(int) * 10); return 0; }");
+        assert!(!output.contains("use libc"), "{}", output);
+    }
+
+    #[test]
+    fn test_rust_prelude_unresolved_typedefs() {
+        let program = Program {
+            declarations: vec![Declaration::Function(Function {
+                storage: None,
+                is_inline: false,
+                return_type: CType::Typedef("size_t".to_string()),
+                name: "close".to_string(),
+                params: vec![Param {
+                    typ: CType::Pointer(Box::new(CType::Typedef("my_handle_t".to_string()))),
+                    name: "h".to_string(),
+                }],
+                explicit_void: false,
+                variadic: false,
+                body: vec![Stmt::Return(Some(Expr::IntLiteral(0)))],
+            })],
+        };
+        let output = CodeGenerator::with_target(TargetLang::Rust)
+            .with_rust_prelude(true)
+            .generate_program(&program);
+        assert!(
+            output.starts_with("use libc::*;\npub type my_handle_t = std::ffi::c_void;\n\n"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_c_inline_asm() {
+        c_roundtrip("void f() {\n    asm volatile(\"nop\");\n}");
+        c_roundtrip("int g(int y) {\n    int x;\n    asm(\"mov %1, %0\" : \"=r\"(x) : \"r\"(y));\n    return x;\n}");
+        let output = to_rust("void f() { asm volatile(\"nop\"); }");
+        assert!(output.contains("    // asm volatile(\"nop\");\n"));
+        // 字面量中的转义保持 C 写法
+        c_roundtrip("void h() {\n    asm(\"\\033[0m\\n\\t\" : : \"r\"('\\033'));\n}");
+        let program = Parser::new("void h() { asm(\"\\x1b\\\"\\\\\"); }")
+            .parse()
+            .unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert!(
+            output.contains("    asm(\"\\033\\\"\\\\\");\n"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_c_array_fields() {
+        c_roundtrip("struct Grid {\n    char name[32];\n    int matrix[4][3];\n};");
+        c_roundtrip("union Buf {\n    char bytes[8][2];\n    long v;\n};");
+        c_roundtrip(
+            "struct Table {\n    int *ptrs[2];\n    int (*rows)[3];\n    int (*cb)(int);\n    char label[LEN + 1];\n};",
+        );
+        c_roundtrip("struct Point { int x[2]; int y; } origin;");
+    }
+
+    #[test]
+    fn test_c_inline_definitions_roundtrip() {
+        c_roundtrip("struct Point { int x; int y; } origin;");
+        c_roundtrip("typedef struct Node { int value; struct Node* next; } Node;");
+        c_roundtrip("typedef enum { RED, GREEN = 2 } Color;");
+        c_roundtrip("struct Value {\n    union { int a; float b; };\n    int tag;\n};");
+    }
+
+    #[test]
+    fn test_typedef_aliases_define_struct_once() {
+        let src = "typedef struct Node { int value; struct Node *next; } Node, *NodePtr;";
+        let program = Parser::new(src).parse().unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert_eq!(output.matches("int value;").count(), 1, "{}", output);
+        assert!(
+            output.contains("typedef struct Node *NodePtr;"),
+            "{}",
+            output
+        );
+        assert_eq!(Parser::new(&output).parse().unwrap(), program);
+        let output = to_rust(src);
+        assert_eq!(output.matches("pub struct Node").count(), 1, "{}", output);
+        assert!(output.contains("type NodePtr = *mut Node;"), "{}", output);
+        // 局部变量声明同理
+        let program = Parser::new("int f(void) { struct P { int x; } a, b; return 0; }")
+            .parse()
+            .unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert_eq!(output.matches("int x;").count(), 1, "{}", output);
+    }
+
+    #[test]
+    fn test_rust_typedef_struct_definition() {
+        assert_eq!(
+            to_rust("typedef struct { int x; } Box;"),
+            "#[repr(C)]\npub struct Box {\n    pub x: i32,\n}\n\n"
+        );
+        assert_eq!(
+            to_rust("typedef struct Tag { int x; } Alias;"),
+            "#[repr(C)]\npub struct Tag {\n    pub x: i32,\n}\n\ntype Alias = Tag;\n\n"
+        );
+    }
+
+    #[test]
+    fn test_c_comma_expression() {
+        let program = Parser::new(
+            "int main() { int i; int j; for (i = 0, j = 10; i < j; i++, j--) { } x = (a, b); return 0; }",
+        )
+        .parse()
+        .unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert!(output.contains("    for (i = 0, j = 10; i < j; i++, j--) {\n"));
+        assert!(output.contains("    x = (a, b);\n"));
+    }
+
+    #[test]
+    fn test_c_initializer_lists() {
+        c_roundtrip("int a[3] = {1, 2, 3};");
+        c_roundtrip("struct Point p = {.x = 1, .y = 2};");
+        c_roundtrip("struct Point pts[2] = {{1, 2}, {[0] = 3, .y = 4}};");
+        c_roundtrip("int t[16] = {[0 ... 7] = 1, 2, [N - 1] = 3};");
+        c_roundtrip("struct Outer o = {.a.b = 1, .c[2].d = {4, 5}};");
+    }
+
+    #[test]
+    fn test_rust_initializer_lists() {
+        let output = to_rust(
+            "int main() { int a[3] = {1, 2, 3}; struct Point p = {.x = 1, .y = 2}; return 0; }",
+        );
+        assert!(output.contains("    let mut a: [i32; 3] = [1, 2, 3];\n"));
+        assert!(output.contains("    let mut p: Point = Point { x: 1, y: 2 };\n"));
+    }
+
+    #[test]
+    fn test_compound_literals() {
+        c_roundtrip("int main() {\n    p = (struct Point){.x = 1, .y = 2};\n    f((int[]){1, 2});\n    return 0;\n}");
+        let output = to_rust(
+            "int main() { p = (struct Point){ .x = 1, .y = 2 }; f((int[]){1, 2}); return 0; }",
+        );
+        assert!(output.contains("p = Point { x: 1, y: 2 };"));
+        assert!(output.contains("f([1, 2]);"));
+    }
+
+    #[test]
+    fn test_sizeof_and_statement_expressions() {
+        c_roundtrip("int main() {\n    n = sizeof(arr);\n    m = sizeof(x + 1);\n    y = ({ int t = x; t * 2; });\n    return 0;\n}");
+        let output =
+            to_rust("int main() { n = sizeof arr; y = ({ int t = x; t * 2; }); return 0; }");
+        assert!(output.contains("n = std::mem::size_of_val(&arr);"));
+        assert!(output.contains("y = { let mut t: i32 = x; (t * 2) };"));
+    }
+
+    #[test]
+    fn test_bool_type() {
+        c_roundtrip("_Bool flag;");
+        assert_eq!(rust_type(CType::Bool), "bool");
+        let output = to_rust("bool ready(bool b) { return b; }");
+        assert!(output.contains("fn ready(b: bool) -> bool {"));
+    }
+
+    #[test]
+    fn test_long_double_type() {
+        c_roundtrip("double a;\n\nlong double b;");
+        assert_eq!(rust_type(CType::Double), "f64");
+        assert_eq!(rust_type(CType::LongDouble), "f64");
+    }
+
+    #[test]
+    fn test_long_long_types() {
+        c_roundtrip("long a;\n\nlong long b;\n\nunsigned long long c;");
+        assert_eq!(rust_type(CType::LongLong), "i64");
+        assert_eq!(rust_type(CType::UnsignedLongLong), "u64");
+    }
+
+    #[test]
+    fn test_include_roundtrip() {
+        c_roundtrip("#include <stdio.h>\n#include \"util.h\"\nint x;");
+        let output = to_rust("#include <stdio.h>\nint x;");
+        assert!(output.contains("// #include <stdio.h>\n"));
+    }
+
+    #[test]
+    fn test_define_roundtrip() {
+        c_roundtrip("#define SIZE 16\n#define DEBUG\nint x;");
+        let output = to_rust("#define SIZE 16\nint x;");
+        assert!(output.contains("// #define SIZE 16\n"));
+    }
+
+    #[test]
+    fn test_storage_class_roundtrip() {
+        c_roundtrip("static int counter;\n\nextern int limit;");
+        c_roundtrip("static int next() {\n    static int calls = 0;\n    register int r = 1;\n    return calls + r;\n}");
+    }
+
+    #[test]
+    fn test_pointer_qualifier_roundtrip() {
+        c_roundtrip("const char *a;\n\nchar *const b;\n\nconst char *const c;\n\nchar *d;\n\nint *const *volatile f;");
+        c_roundtrip("int main() {\n    p = (char *const)q;\n    return 0;\n}");
+    }
+
+    #[test]
+    fn test_multiple_and_array_typedefs() {
+        c_roundtrip("typedef int T;\n\ntypedef int *PT;\n\ntypedef int Arr[10];");
+        let program = Parser::new("typedef int T, *PT, Arr[10];").parse().unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert_eq!(
+            output.trim_end(),
+            "typedef int T;\n\ntypedef int *PT;\n\ntypedef int Arr[10];"
+        );
+    }
+
+    #[test]
+    fn test_c_multi_declarator_statements() {
+        c_roundtrip("int main() {\n    int a = 1, *p, arr[2];\n    for (int i = 0, n = 10; i < n; i++, j++) {\n        a = i;\n    }\n    return a;\n}");
+    }
+
+    #[test]
+    fn test_rust_multi_declarator_statements() {
+        let output = to_rust("int main() { int a = 1, b; return a; }");
+        assert!(output.contains("    let mut a: i32 = 1;\n    let mut b: i32;\n"));
+    }
+
+    #[test]
+    fn test_rust_counting_for_loops() {
+        let output = to_rust(
+            "int main() { int s = 0; for (int i = 0; i < n; i++) { s += i; } for (int k = 1; k <= 10; k += 2) { s += k; } return s; }",
+        );
+        assert!(output.contains("    for i in 0..n {\n        s = (s + i);\n    }\n"));
+        assert!(output.contains("    for k in (1..=10).step_by(2 as usize) {\n"));
+    }
+
+    #[test]
+    fn test_rust_for_loop_fallback() {
+        // 循环变量在循环体内被修改，不能改写为区间
+        let output = to_rust("int main() { for (int i = 0; i < 10; i++) { i += 2; } return 0; }");
+        assert!(output.contains(
+            "    {\n        let mut i: i32 = 0;\n        while (i < 10) {\n            i = (i + 2);\n            i += 1;\n        }\n    }\n"
+        ));
+        // 初始化不是声明、条件不是 < / <= 时同样退回 while
+        let output = to_rust("int main() { int i; for (i = 10; i > 0; i--) { } return 0; }");
+        assert!(output.contains("        while (i > 0) {\n"));
+        // 步长为 0、负数或在循环体内被修改时不能用 step_by
+        let output = to_rust(
+            "int f(int k) { for (int i = 0; i < 10; i += 0) { } for (int j = 0; j < 10; j += -1) { } for (int m = 0; m < 10; m += k) { k++; } return 0; }",
+        );
+        assert!(!output.contains("step_by"), "{}", output);
+        assert!(output.contains("        while (i < 10) {\n"), "{}", output);
+        assert!(output.contains("        while (j < 10) {\n"), "{}", output);
+        assert!(output.contains("        while (m < 10) {\n"), "{}", output);
+        // 循环体内不变的变量步长仍然改写为区间
+        let output = to_rust(
+            "int f(int k) { int s = 0; for (int i = 0; i < 10; i += k) { s += i; } return s; }",
+        );
+        assert!(
+            output.contains("    for i in (0..10).step_by(k as usize) {\n"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_rust_switch_three_cases_with_default() {
+        let output = to_rust(
+            "int f(int x) { int y; switch (x) { case 0: y = 1; break; case 1: y = 2; break; case 2: return 3; default: y = 0; } return y; }",
+        );
+        assert!(output.contains(
+            "    match x {\n        0 => {\n            y = 1;\n        }\n        1 => {\n            y = 2;\n        }\n        2 => {\n            return 3;\n        }\n        _ => {\n            y = 0;\n        }\n    }\n"
+        ));
+        assert!(!output.contains("TODO"));
+    }
+
+    #[test]
+    fn test_switch_case_range() {
+        let source = "int f(int x) { switch (x) { case 0: return 0; case 1 ... 5: case 7: return 1; default: return 2; } }";
+        let program = Parser::new(source).parse().unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert!(output.contains(
+            "        case 0:\n            return 0;\n        case 1 ... 5:\n        case 7:\n"
+        ));
+        let output = to_rust(source);
+        assert!(output.contains("        1..=5 | 7 => {\n            return 1;\n        }\n"));
+    }
+
+    #[test]
+    fn test_rust_switch_char_cases() {
+        let output = to_rust(
+            "int f(char c) { switch (c) { case 'a': return 1; case '0' ... '9': case -1: return 2; default: return 0; } }",
+        );
+        assert!(output.contains(
+            "        97 => {\n            return 1;\n        }\n        48..=57 | -1 => {\n            return 2;\n        }\n"
+        ), "{}", output);
+        assert!(!output.contains(" as i8) =>"), "{}", output);
+    }
+
+    #[test]
+    fn test_rust_switch_fallthrough_todo() {
+        let output = to_rust(
+            "int f(int x) { switch (x) { case 1: x = 10; case 2: x = 20; break; } return x; }",
+        );
+        assert!(output.contains(
+            "        1 => {\n            x = 10;\n            // TODO: fallthrough\n        }\n        2 => {\n            x = 20;\n        }\n        _ => {}\n"
+        ));
+    }
+
+    #[test]
+    fn test_rust_enum_with_explicit_and_implicit_values() {
+        let output = to_rust("enum Color { RED, GREEN = 5, BLUE, ALPHA = BLUE << 1 };");
+        assert_eq!(
+            output.trim_end(),
+            "#[repr(C)]\npub enum Color {\n    RED = 0,\n    GREEN = 5,\n    BLUE = 6,\n    ALPHA = 12,\n}"
+        );
+    }
+
+    #[test]
+    fn test_negative_and_char_enum_values() {
+        c_roundtrip("enum E {\n    A = -1,\n    B = 'A',\n    C\n};");
+        let output = to_rust("enum E { A = -1, B = 'A', C };");
+        assert_eq!(
+            output.trim_end(),
+            "#[repr(C)]\npub enum E {\n    A = -1,\n    B = 65,\n    C = 66,\n}"
+        );
+    }
+
+    #[test]
+    fn test_rust_enum_consts_form() {
+        // 匿名枚举输出为常量
+        let output = to_rust("enum { A, B = 4, C };");
+        assert_eq!(
+            output.trim_end(),
+            "pub const A: i32 = 0;\npub const B: i32 = 4;\npub const C: i32 = 5;"
+        );
+        // 取值重复时无法作为 Rust 枚举的判别值
+        let output = to_rust("enum Kind { FIRST = 1, ONE = 1, TWO };");
+        assert_eq!(
+            output.trim_end(),
+            "pub type Kind = i32;\npub const FIRST: i32 = 1;\npub const ONE: i32 = 1;\npub const TWO: i32 = 2;"
+        );
+        // 无法求值的表达式按原样输出，省略的值基于前一项
+        let output = to_rust("enum Size { SMALL = sizeof(int), LARGE };");
+        assert!(output.contains(
+            "pub const SMALL: i32 = std::mem::size_of::<i32>();\npub const LARGE: i32 = SMALL + 1;"
+        ));
+    }
+
+    #[test]
+    fn test_rust_pointer_params_become_references() {
+        let output = to_rust("void f(const int *a, int *b) { *b = *a; }");
+        assert!(output.contains("fn f(a: &i32, b: &mut i32)"), "{}", output);
+        assert!(output.contains("(*b) = (*a);"), "{}", output);
+    }
+
+    #[test]
+    fn test_rust_pointer_params_kept_when_not_only_dereferenced() {
+        // 下标访问、空指针比较和 void* 都需要保留原始指针
+        let output =
+            to_rust("int g(int *p, const int *q, void *v) { if (q == 0) return p[1]; return *q; }");
+        assert!(
+            output.contains("fn g(p: *mut i32, q: *const i32, v: *mut std::ffi::c_void) -> i32"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_rust_str_params_opt_in() {
+        let source = "int len(const char *s) { return *s; }";
+        assert!(!to_rust(source).contains("s: &str"));
+        let program = Parser::new(source).parse().unwrap();
+        let output = CodeGenerator::with_target(TargetLang::Rust)
+            .with_str_params(true)
+            .generate_program(&program);
+        assert!(output.contains("fn len(s: &str) -> i32"), "{}", output);
+    }
+
+    #[test]
+    fn test_rust_sizeof_types() {
+        let output = to_rust(
+            "struct Node { int v; };\n\
+             void f() { n = sizeof(int); n = sizeof(struct Node); n = sizeof(char *); n = sizeof(int[4]); }",
+        );
+        assert!(
+            output.contains("n = std::mem::size_of::<i32>();"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("n = std::mem::size_of::<Node>();"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("n = std::mem::size_of::<*mut i8>();"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("n = std::mem::size_of::<[i32; 4]>();"),
+            "{}",
+            output
+        );
+    }
+
+    fn c_expr(expr: &Expr) -> String {
+        CodeGenerator::new().generate_expr(expr)
+    }
+
+    fn neg(operand: Expr) -> Expr {
+        Expr::Unary {
+            op: UnaryOp::Neg,
+            operand: Box::new(operand),
+        }
+    }
+
+    #[test]
+    fn test_c_unary_operators_do_not_merge() {
+        let x = || Expr::Identifier("x".to_string());
+        let pre_dec = Expr::Unary {
+            op: UnaryOp::PreDecrement,
+            operand: Box::new(x()),
+        };
+        let plus = |operand: Expr| Expr::Unary {
+            op: UnaryOp::Plus,
+            operand: Box::new(operand),
+        };
+        assert_eq!(c_expr(&neg(neg(x()))), "- -x");
+        assert_eq!(c_expr(&neg(pre_dec)), "- --x");
+        assert_eq!(c_expr(&plus(plus(x()))), "+ +x");
+        // 负数字面量前的负号必须与之分开，否则会被重新识别为 --
+        assert_eq!(c_expr(&neg(Expr::IntLiteral(-5))), "- -5");
+    }
+
+    #[test]
+    fn test_c_unary_operators_roundtrip() {
+        for source in [
+            "int f(int x) {\n    return - -x;\n}",
+            "int f(int x) {\n    return - --x;\n}",
+            "int f(int x) {\n    return + +x;\n}",
+        ] {
+            c_roundtrip(source);
+        }
+    }
+
+    #[test]
+    fn test_c_minimal_parentheses() {
+        let cases = [
+            ("a + b * c - d", "a + b * c - d"),
+            ("(a + b) * (c - d)", "(a + b) * (c - d)"),
+            ("a - (b - c)", "a - (b - c)"),
+            ("a || b && c", "a || b && c"),
+            ("(a || b) && c", "(a || b) && c"),
+            ("(a & b) == c", "(a & b) == c"),
+            ("x = y = *p++ + -q[i]", "x = y = *p++ + -q[i]"),
+            ("c ? a : b ? d : e", "c ? a : b ? d : e"),
+            ("(c ? a : b) ? d : e", "(c ? a : b) ? d : e"),
+            ("c ? a : (b = d)", "c ? a : (b = d)"),
+            ("x = c ? a : b", "x = c ? a : b"),
+            ("f((a, b), c)", "f((a, b), c)"),
+            ("(int)(a + b)", "(int)(a + b)"),
+            ("(*p).x", "(*p).x"),
+        ];
+        for (input, expected) in cases {
+            let source = format!("int main() {{\n    {};\n}}", input);
+            let program = Parser::new(&source).parse().unwrap();
+            let output = CodeGenerator::new().generate_program(&program);
+            assert_eq!(
+                output.trim_end(),
+                format!("int main() {{\n    {};\n}}", expected)
+            );
+            // 去掉多余括号后重新解析必须得到相同的语法树
+            assert_eq!(Parser::new(&output).parse().unwrap(), program, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_c_paren_count_reduced() {
+        // 以前每个二元和一元表达式都带一层括号
+        let count = |s: &str| s.matches('(').count();
+        let program = Parser::new("int f() { return a + b * c - d << 1 && !x; }")
+            .parse()
+            .unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert!(output.contains("return a + b * c - d << 1 && !x;"));
+        assert_eq!(count(&output), 1);
+    }
+
+    #[test]
+    fn test_c_string_escapes_roundtrip() {
+        c_roundtrip(
+            "int main() {\n    puts(\"a\\nb\\t\\\"q\\\" \\\\ end\");\n    c = '\\'';\n    d = '\\n';\n    e = '\\0';\n    return 0;\n}",
+        );
+    }
+
+    #[test]
+    fn test_c_string_control_chars_use_octal() {
+        // 控制字符后紧跟数字时不能与之合并成更长的转义
+        let program = Parser::new(r#"int main() { s = "\x01" "9"; c = '\xff'; return 0; }"#)
+            .parse()
+            .unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert!(output.contains(r#"s = "\0019";"#), "{}", output);
+        assert!(output.contains(r#"c = '\377';"#), "{}", output);
+    }
+
+    #[test]
+    fn test_c_string_high_bytes_roundtrip() {
+        // \xff 是单个字节，不能输出为两个字节的 UTF-8 字符
+        c_roundtrip("char *s = \"\\xff\";");
+        // 源码中直接写出的非 ASCII 字符保持原样
+        c_roundtrip("char *s = \"\\x80 \\xe9t\\xc3\\xa9 \u{e9}\u{4e2d}\u{6587}\";");
+        let program = Parser::new(r#"char* s = "\377A" "\xff!";"#)
+            .parse()
+            .unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert_eq!(output, "char *s = \"\\377A\\xff!\";\n\n");
+        assert_eq!(Parser::new(&output).parse().unwrap(), program);
+    }
+
+    #[test]
+    fn test_rust_string_escapes() {
+        let output = to_rust(
+            r#"int main() { s = "tab\there \"q\" back\\slash\n"; c = '\''; printf("say \"%d\"\n", x); return 0; }"#,
+        );
+        assert!(
+            output.contains(r#"s = "tab\there \"q\" back\\slash\n";"#),
+            "{}",
+            output
+        );
+        assert!(output.contains(r#"c = ('\'' as i8);"#), "{}", output);
+        assert!(
+            output.contains(r#"println!("say \"{}\"", x);"#),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_c_assignment_as_value_roundtrip() {
+        c_roundtrip("int main() {\n    a = b = c;\n    x = (y = 5) + 1;\n    if ((p = next) != NULL) {\n        return 1;\n    }\n    return 0;\n}");
+    }
+
+    #[test]
+    fn test_rust_chained_and_nested_assignment() {
+        let output = to_rust(
+            "int main() { a = b = c; x = (y = 5) + 1; if ((p = next) != NULL) { return 1; } return 0; }",
+        );
+        assert!(output.contains("    b = c;\n    a = b;\n"), "{}", output);
+        assert!(
+            output.contains("    x = ({ y = 5; y } + 1);\n"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("    if !{ p = next; p }.is_null() {\n"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_array_size_expressions_codegen() {
+        c_roundtrip(
+            "#define FOO 8\nint a[10];\n\nint b[2 + 3];\n\nint c[FOO];\n\nint d[sizeof(x)];",
+        );
+        let output = to_rust("#define FOO 8\nint b[2 + 3]; int c[FOO]; int d[N];");
+        assert!(output.contains("[i32; 5]"), "{}", output);
+        assert!(output.contains("[i32; 8]"), "{}", output);
+        assert!(output.contains("[i32; N as usize]"), "{}", output);
+    }
+
+    #[test]
+    fn test_inline_and_restrict_codegen() {
+        c_roundtrip("static inline int load(const int *restrict p) {\n    return *p;\n}");
+        let output = to_rust("inline int load(const int *restrict p) { return *p; }");
+        assert!(
+            output.starts_with("#[inline]\nfn load(p: &i32) -> i32 {\n"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_static_inline_specifier_order() {
+        c_roundtrip("static inline int get(void) {\n    return 1;\n}");
+        c_roundtrip("inline int get(void) {\n    return 1;\n}");
+        // 说明符的其他写法与顺序统一输出为 static inline
+        for src in [
+            "inline static int get(void) { return 1; }",
+            "static __inline__ int get(void) { return 1; }",
+            "int static inline get(void) { return 1; }",
+        ] {
+            let program = Parser::new(src).parse().unwrap();
+            assert!(
+                CodeGenerator::new()
+                    .generate_program(&program)
+                    .starts_with("static inline int get(void) {\n"),
+                "{}",
+                src
+            );
+        }
+        let output =
+            to_rust("static inline int get(void) { return 1; } int plain(void) { return 2; }");
+        assert!(
+            output.starts_with("#[inline]\nfn get() -> i32 {\n"),
+            "{}",
+            output
+        );
+        assert_eq!(output.matches("#[inline]").count(), 1);
+    }
+
+    #[test]
+    fn test_abstract_declarators_roundtrip() {
+        c_roundtrip("int f(void *p, int (*)(int), char *[]) {\n    int (*rows)[3] = (int (*)[3])p;\n    return sizeof(int[4]) + sizeof(char*) + sizeof(int (*)(void));\n}");
+    }
+
+    #[test]
+    fn test_pragma_pack() {
+        let src = "#pragma pack(1)\nstruct Header {\n    char tag;\n    int len;\n};\n\n#pragma pack()\nstruct Body {\n    int n;\n};";
+        let program = Parser::new(src).parse().unwrap();
+        assert_eq!(
+            program.declarations[0],
+            Declaration::Pragma("pack(1)".to_string())
+        );
+        c_roundtrip(src);
+        let output = to_rust(src);
+        assert!(
+            output.starts_with("// #pragma pack(1)\n#[repr(C, packed(1))]\npub struct Header {\n"),
+            "{}",
+            output
+        );
+        assert!(output.contains("// #pragma pack()\n#[repr(C)]\npub struct Body {\n"));
+        // push/pop 形式
+        let output = to_rust("#pragma pack(push, 2)\nunion U { int a; char b; };\n#pragma pack(pop)\nstruct S { int x; };");
+        assert!(
+            output.contains("#[repr(C, packed(2))]\npub union U {"),
+            "{}",
+            output
+        );
+        assert!(output.contains("#[repr(C)]\npub struct S {"), "{}", output);
+    }
+
+    #[test]
+    fn test_emit_individual_nodes() {
+        let sum = Expr::Binary {
+            op: BinaryOp::Add,
+            left: Box::new(Expr::Identifier("a".to_string())),
+            right: Box::new(Expr::IntLiteral(1)),
+        };
+        let stmt = Stmt::Return(Some(sum.clone()));
+        let ptr = CType::Pointer(Box::new(CType::Int));
+
+        let mut c = CodeGenerator::new();
+        assert_eq!(sum.emit(&mut c), "a + 1");
+        assert_eq!(c.emit(&ptr), "int*");
+        let mut c = CodeGenerator::new().with_indent(1);
+        assert_eq!(stmt.emit(&mut c), "    return a + 1;\n");
+
+        let mut rust = CodeGenerator::with_target(TargetLang::Rust).with_indent(2);
+        assert_eq!(sum.emit(&mut rust), "(a + 1)");
+        assert_eq!(ptr.emit(&mut rust), "*mut i32");
+        assert_eq!(rust.emit(&stmt), "        return (a + 1);\n");
+    }
+
+    #[test]
+    fn test_emit_declaration_matches_program_output() {
+        let program = Parser::new("int x = 1; int f() { return x; }")
+            .parse()
+            .unwrap();
+        for target in [TargetLang::C, TargetLang::Rust] {
+            let mut generator = CodeGenerator::with_target(target);
+            let pieces: String = program
+                .declarations
+                .iter()
+                .map(|decl| decl.emit(&mut generator))
+                .collect();
+            assert_eq!(
+                pieces,
+                CodeGenerator::with_target(target).generate_program(&program)
+            );
+        }
+    }
+
+    #[test]
+    fn test_c_else_if_chain() {
+        c_roundtrip("int sign(int x) {\n    if (x < 0) {\n        return -1;\n    } else if (x == 0) {\n        return 0;\n    } else {\n        return 1;\n    }\n}");
+        let output = to_rust(
+            "int sign(int x) { if (x < 0) return -1; else if (x == 0) return 0; else return 1; }",
+        );
+        assert!(
+            output.contains("    } else if (x == 0) {\n        return 0;\n    } else {\n        return 1;\n    }\n"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_block_body_is_not_double_braced() {
+        let program = Parser::new("int f(int x) { while (x) { { x--; } } return x; }")
+            .parse()
+            .unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert!(
+            output.contains("    while (x) {\n        x--;\n    }\n"),
+            "{}",
+            output
+        );
+        let output = to_rust("int f(int x) { while (x) { { x--; } } return x; }");
+        assert!(
+            output.contains("    while (x != 0) {\n        x -= 1;\n    }\n"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_call_through_expression() {
+        c_roundtrip("int f(int (*fp)(int, int)) {\n    return (*fp)(1, 2) + tbl[0](3);\n}");
+        let output = to_rust("int f(int i) { return (*fp)(1, 2) + tbl[i](3); }");
+        assert!(
+            output.contains("return (fp(1, 2) + tbl[i as usize](3));"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_call_shapes_roundtrip() {
+        c_roundtrip("void run(int i) {\n    arr[i]();\n    obj->method();\n    (*fp)();\n}");
+        // 被调用者优先级低于后缀运算时加括号
+        c_roundtrip("int pick(int c) {\n    return (c ? f : g)(1);\n}");
+    }
+
+    #[test]
+    fn test_global_declarators_roundtrip() {
+        let program = Parser::new("int a = 1, b = 2;\nint tbl[] = {1, 2, 3};")
+            .parse()
+            .unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert_eq!(
+            output.trim_end(),
+            "int a = 1;\n\nint b = 2;\n\nint tbl[] = {1, 2, 3};"
+        );
+    }
+
+    #[test]
+    fn test_pointer_to_array_vs_array_of_pointers() {
+        c_roundtrip("int f(void) {\n    int (*p)[10];\n    int *a[10];\n    return 0;\n}");
+        let output = to_rust("int f(void) { int (*p)[10]; int *a[10]; return 0; }");
+        assert!(output.contains("let mut p: *mut [i32; 10];"), "{}", output);
+        assert!(output.contains("let mut a: [*mut i32; 10];"), "{}", output);
+    }
+
+    #[test]
+    fn test_single_statement_loop_bodies_roundtrip() {
+        let program = Parser::new(
+            "void f(int x, int y) { while (x) ; while (x) y++; while (x) again: y--; ; }",
+        )
+        .parse()
+        .unwrap();
+        let output = CodeGenerator::new().generate_program(&program);
+        assert_eq!(
+            output.trim_end(),
+            "void f(int x, int y) {\n    while (x) {\n    }\n    while (x) {\n        y++;\n    }\n    while (x) {\n        again:\n        y--;\n    }\n    ;\n}"
+        );
+        assert_eq!(Parser::new(&output).parse().unwrap(), program);
+    }
+
+    #[test]
+    fn test_rust_raw_pointer_deref_is_unsafe() {
+        let output = to_rust(
+            "struct Node { int value; }; int main() { int *p = (int*)malloc(sizeof(int)); *p = 100; struct Node *head = (struct Node*)malloc(sizeof(struct Node)); head->value = 42; return *p; }",
+        );
+        assert!(
+            output.contains("fn main() -> i32 {\n    unsafe {\n        let mut p: *mut i32 = "),
+            "{}",
+            output
+        );
+        assert!(output.contains("        (*p) = 100;\n"), "{}", output);
+        assert!(
+            output.contains("        (*head).value = 42;\n"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("        return (*p);\n    }\n}\n"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_rust_reference_deref_is_safe() {
+        let output = to_rust(
+            "struct Node { int value; }; void set(int *p, struct Node *head) { *p = 100; head->value = 42; }",
+        );
+        assert!(
+            output.contains("fn set(p: &mut i32, head: &mut Node) {\n    (*p) = 100;\n    (*head).value = 42;\n}"),
+            "{}",
+            output
+        );
+        assert!(!output.contains("unsafe"), "{}", output);
+    }
+
+    #[test]
+    fn test_postfix_chains_roundtrip() {
+        c_roundtrip("int f(struct Node *p) {\n    return (*p).next->value;\n}");
+        c_roundtrip("int g(struct S *arr, int i) {\n    arr[i].field = -(*arr).field;\n    return (&arr[i])->field;\n}");
+        c_roundtrip(
+            "typedef struct Node Node;\n\nNode* h(void *x) {\n    return ((Node*)x)->next;\n}",
+        );
+        c_roundtrip("int k(struct Node *p, void *x) {\n    return (*(struct Node**)x)->value + (p + 1)->value + (*p).next[2].value;\n}");
+    }
+
+    #[test]
+    fn test_typeof() {
+        c_roundtrip("int f(int x) {\n    typeof(x) y = x;\n    const typeof(x + 1) z = 0;\n    return (typeof(x))y;\n}");
+        let output = to_rust("int f(int x) { typeof(x) y = x; typeof(1.5f) z = 2; typeof((long)x) w = x; return y; }");
+        assert!(output.contains("let mut y: _ = x;"), "{}", output);
+        assert!(output.contains("let mut z: f32 = 2;"), "{}", output);
+        assert!(output.contains("let mut w: i64 = x;"), "{}", output);
+    }
+
+    #[test]
+    fn test_pointer_return_types_roundtrip() {
+        c_roundtrip("int* get(void) {\n    return 0;\n}");
+        c_roundtrip("struct Node* head(void) {\n    return 0;\n}");
+        c_roundtrip("int (*pick(int n))(int) {\n    return 0;\n}");
+        c_roundtrip("int (*rows(int n))[4] {\n    return 0;\n}");
+    }
+
+    #[test]
+    fn test_void_and_empty_parameter_lists() {
+        c_roundtrip("int f(void) {\n    return 0;\n}\n\nint g() {\n    return 1;\n}");
+        let output = to_rust("int f(void) { return 0; } int g() { return 1; }");
+        assert!(output.contains("fn f() -> i32 {"), "{}", output);
+        assert!(output.contains("fn g() -> i32 {"), "{}", output);
+    }
+}
@@ -0,0 +1,123 @@
+/// 测试类型大小、对齐与 sizeof 常量求值
+use c_to_rust_tool::ast::*;
+use c_to_rust_tool::parse_str;
+use c_to_rust_tool::parser::Parser;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 解析 int x = <expr>; 并取出初始化表达式
+    fn init_expr(expr: &str) -> Expr {
+        let program = parse_str(&format!("int x = {};", expr)).unwrap();
+        match &program.declarations[0] {
+            Declaration::GlobalVar {
+                init: Some(init), ..
+            } => init.clone(),
+            other => panic!("Expected initialized global, got {:?}", other),
+        }
+    }
+
+    fn sizeof_in(expr: &str, model: DataModel) -> Option<i128> {
+        const_int_value_in(&init_expr(expr), model, &|_| None)
+    }
+
+    #[test]
+    fn test_sizeof_int() {
+        assert_eq!(sizeof_in("sizeof(int)", DataModel::LP64), Some(4));
+        assert_eq!(sizeof_in("sizeof(int)", DataModel::ILP32), Some(4));
+        assert_eq!(
+            sizeof_in("sizeof(int) * 4 + sizeof(char)", DataModel::LP64),
+            Some(17)
+        );
+        // 不指定数据模型时 sizeof 不参与求值
+        assert_eq!(const_int_value(&init_expr("sizeof(int)"), &|_| None), None);
+    }
+
+    #[test]
+    fn test_sizeof_long_and_pointers_per_model() {
+        assert_eq!(sizeof_in("sizeof(long)", DataModel::LP64), Some(8));
+        assert_eq!(sizeof_in("sizeof(long)", DataModel::ILP32), Some(4));
+        assert_eq!(sizeof_in("sizeof(char*)", DataModel::LP64), Some(8));
+        assert_eq!(sizeof_in("sizeof(char*)", DataModel::ILP32), Some(4));
+        assert_eq!(sizeof_in("sizeof(long long)", DataModel::ILP32), Some(8));
+        assert_eq!(DataModel::default(), DataModel::LP64);
+    }
+
+    #[test]
+    fn test_sizeof_arrays() {
+        assert_eq!(sizeof_in("sizeof(char[10])", DataModel::LP64), Some(10));
+        assert_eq!(sizeof_in("sizeof(int[2][3])", DataModel::LP64), Some(24));
+        assert_eq!(sizeof_in("sizeof(long *[4])", DataModel::ILP32), Some(16));
+        let unsized_array = CType::Array {
+            element_type: Box::new(CType::Int),
+            size: None,
+            size_expr: None,
+        };
+        assert_eq!(unsized_array.size_of(DataModel::LP64), None);
+        assert_eq!(unsized_array.align_of(DataModel::LP64), Some(4));
+    }
+
+    #[test]
+    fn test_unknown_layouts() {
+        for typ in [
+            CType::Struct("Point".to_string()),
+            CType::Union("U".to_string()),
+            CType::Typedef("size_t".to_string()),
+            CType::Void,
+        ] {
+            assert_eq!(typ.size_of(DataModel::LP64), None, "{:?}", typ);
+            assert_eq!(typ.align_of(DataModel::LP64), None, "{:?}", typ);
+        }
+        assert_eq!(sizeof_in("sizeof(struct Point)", DataModel::LP64), None);
+    }
+
+    // 解析 source 中第一个全局变量的类型
+    fn global_type(source: &str) -> CType {
+        match &parse_str(source).unwrap().declarations[0] {
+            Declaration::GlobalVar { typ, .. } => typ.clone(),
+            other => panic!("Expected global, got {:?}", other),
+        }
+    }
+
+    fn array_size(typ: &CType) -> Option<usize> {
+        match typ {
+            CType::Array { size, .. } => *size,
+            other => panic!("Expected array, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parser_folds_sizeof_in_array_sizes() {
+        let typ = global_type("int a[sizeof(int)];");
+        assert_eq!(array_size(&typ), Some(4));
+        // 原表达式保留，输出 C 时原样写回
+        assert!(matches!(
+            &typ,
+            CType::Array { size_expr: Some(expr), .. } if **expr == Expr::SizeOf(CType::Int)
+        ));
+        let source = "enum { N = sizeof(long) * 2 }; char buf[N + sizeof(void*)];";
+        let lp64 = Parser::new(source).parse().unwrap();
+        let ilp32 = Parser::new(source)
+            .with_data_model(DataModel::ILP32)
+            .parse()
+            .unwrap();
+        for (program, expected) in [(lp64, 24), (ilp32, 12)] {
+            match &program.declarations[1] {
+                Declaration::GlobalVar { typ, .. } => assert_eq!(array_size(typ), Some(expected)),
+                other => panic!("Expected global, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_align_of() {
+        let const_double = CType::Const(Box::new(CType::Double));
+        assert_eq!(const_double.size_of(DataModel::ILP32), Some(8));
+        assert_eq!(const_double.align_of(DataModel::LP64), Some(8));
+        assert_eq!(const_double.align_of(DataModel::ILP32), Some(4));
+        assert_eq!(CType::LongDouble.size_of(DataModel::LP64), Some(16));
+        assert_eq!(CType::LongDouble.align_of(DataModel::ILP32), Some(4));
+        assert_eq!(CType::Short.align_of(DataModel::ILP32), Some(2));
+    }
+}
@@ -0,0 +1,1056 @@
+/// 测试顶层声明解析功能
+use c_to_rust_tool::ast::*;
+use c_to_rust_tool::parser::Parser;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_decls(input: &str) -> Vec<Declaration> {
+        Parser::new(input).parse().unwrap().declarations
+    }
+
+    fn param(typ: CType, name: &str) -> Param {
+        Param {
+            typ,
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_function_param_names() {
+        let decls = parse_decls("int add(int a, int b) { return a + b; }");
+        let Declaration::Function(func) = &decls[0] else {
+            panic!("Expected function, got {:?}", decls[0]);
+        };
+        assert_eq!(
+            func.params,
+            vec![param(CType::Int, "a"), param(CType::Int, "b")]
+        );
+    }
+
+    #[test]
+    fn test_prototype_unnamed_params() {
+        let decls = parse_decls("int cmp(int, char *);");
+        let Declaration::Function(func) = &decls[0] else {
+            panic!("Expected function, got {:?}", decls[0]);
+        };
+        assert!(func.body.is_empty());
+        assert_eq!(
+            func.params,
+            vec![
+                param(CType::Int, ""),
+                param(CType::Pointer(Box::new(CType::Char)), "")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_array_param() {
+        let decls = parse_decls("int main(int argc, char *argv[]) { return 0; }");
+        let Declaration::Function(func) = &decls[0] else {
+            panic!("Expected function, got {:?}", decls[0]);
+        };
+        assert_eq!(
+            func.params[1],
+            param(
+                CType::Array {
+                    element_type: Box::new(CType::Pointer(Box::new(CType::Char))),
+                    size: None,
+                    size_expr: None,
+                },
+                "argv"
+            )
+        );
+    }
+
+    fn cmp_type() -> CType {
+        // const void *：const 修饰指向的 void
+        let const_void_ptr = CType::Pointer(Box::new(CType::Const(Box::new(CType::Void))));
+        CType::Pointer(Box::new(CType::Function {
+            return_type: Box::new(CType::Int),
+            params: vec![const_void_ptr.clone(), const_void_ptr],
+            variadic: false,
+        }))
+    }
+
+    #[test]
+    fn test_function_pointer_typedef() {
+        let decls = parse_decls("typedef int (*cmp)(const void*, const void*);");
+        assert_eq!(
+            decls[0],
+            Declaration::Typedef(TypedefDef {
+                name: "cmp".to_string(),
+                target_type: cmp_type(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_function_pointer_param() {
+        let decls =
+            parse_decls("void sort(void *base, int (*compar)(const void*, const void*)) { }");
+        let Declaration::Function(func) = &decls[0] else {
+            panic!("Expected function, got {:?}", decls[0]);
+        };
+        assert_eq!(func.params[1], param(cmp_type(), "compar"));
+    }
+
+    #[test]
+    fn test_pointer_to_array() {
+        let decls = parse_decls("int (*rows)[4];");
+        assert_eq!(
+            decls[0],
+            Declaration::GlobalVar {
+                storage: None,
+                typ: CType::Pointer(Box::new(CType::Array {
+                    element_type: Box::new(CType::Int),
+                    size: Some(4),
+                    size_expr: None,
+                })),
+                name: "rows".to_string(),
+                init: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_pointer_to_array_vs_array_of_pointers() {
+        let int_array = |size| CType::Array {
+            element_type: Box::new(CType::Int),
+            size: Some(size),
+            size_expr: None,
+        };
+        let decls = parse_decls("int (*p)[10]; int *a[10]; int (*g(void))[4];");
+        let Declaration::GlobalVar { typ: p, .. } = &decls[0] else {
+            panic!("Expected global, got {:?}", decls[0]);
+        };
+        let Declaration::GlobalVar { typ: a, .. } = &decls[1] else {
+            panic!("Expected global, got {:?}", decls[1]);
+        };
+        assert_eq!(*p, CType::Pointer(Box::new(int_array(10))));
+        assert_eq!(
+            *a,
+            CType::Array {
+                element_type: Box::new(CType::Pointer(Box::new(CType::Int))),
+                size: Some(10),
+                size_expr: None,
+            }
+        );
+        assert_ne!(p, a);
+        // 返回指向数组的指针的函数
+        let Declaration::Function(g) = &decls[2] else {
+            panic!("Expected function, got {:?}", decls[2]);
+        };
+        assert_eq!(g.return_type, CType::Pointer(Box::new(int_array(4))));
+    }
+
+    #[test]
+    fn test_enum_value_expressions() {
+        let decls = parse_decls("enum Flags { A = 1 << 3, B = A + 1, C = 'z', D, E };");
+        let Declaration::Enum(enum_def) = &decls[0] else {
+            panic!("Expected enum, got {:?}", decls[0]);
+        };
+        let values: Vec<_> = enum_def.variants.iter().map(|v| &v.value).collect();
+        assert_eq!(
+            values[0],
+            &Some(Expr::Binary {
+                op: BinaryOp::LeftShift,
+                left: Box::new(Expr::IntLiteral(1)),
+                right: Box::new(Expr::IntLiteral(3)),
+            })
+        );
+        assert_eq!(
+            values[1],
+            &Some(Expr::Binary {
+                op: BinaryOp::Add,
+                left: Box::new(Expr::Identifier("A".to_string())),
+                right: Box::new(Expr::IntLiteral(1)),
+            })
+        );
+        assert_eq!(values[2], &Some(Expr::CharLiteral('z')));
+        // 未给出值的枚举项在前一项基础上自增
+        assert_eq!(values[3], &None);
+        assert_eq!(values[4], &None);
+    }
+
+    #[test]
+    fn test_negative_and_char_enum_values() {
+        let decls = parse_decls("enum E { A = -1, B = 'A', C, D = -'a' }; int t[C];");
+        let Declaration::Enum(enum_def) = &decls[0] else {
+            panic!("Expected enum, got {:?}", decls[0]);
+        };
+        let value = |i: usize| {
+            enum_def.variants[i]
+                .value
+                .as_ref()
+                .and_then(|expr| const_int_value(expr, &|_| None))
+        };
+        assert_eq!(value(0), Some(-1));
+        assert_eq!(value(1), Some(65));
+        assert_eq!(value(3), Some(-97));
+        // 后续声明可以使用基于字符常量自增得到的值
+        assert!(matches!(
+            &decls[1],
+            Declaration::GlobalVar {
+                typ: CType::Array { size: Some(66), .. },
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_specifiers_in_any_order() {
+        let decls = parse_decls(
+            "int static x; const static int y; long static unsigned z; int extern const w;",
+        );
+        let globals: Vec<_> = decls
+            .iter()
+            .map(|decl| match decl {
+                Declaration::GlobalVar { storage, typ, .. } => (storage.clone(), typ.clone()),
+                other => panic!("Expected global variable, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            globals,
+            vec![
+                (Some(StorageClass::Static), CType::Int),
+                (
+                    Some(StorageClass::Static),
+                    CType::Const(Box::new(CType::Int))
+                ),
+                (Some(StorageClass::Static), CType::UnsignedLong),
+                (
+                    Some(StorageClass::Extern),
+                    CType::Const(Box::new(CType::Int))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_thread_local_is_dropped() {
+        let decls = parse_decls(
+            "_Thread_local int a; static thread_local int b; extern __thread int c; void f() { static _Thread_local int k; }",
+        );
+        assert!(matches!(
+            &decls[0],
+            Declaration::GlobalVar {
+                storage: None,
+                typ: CType::Int,
+                ..
+            }
+        ));
+        assert!(matches!(
+            &decls[1],
+            Declaration::GlobalVar {
+                storage: Some(StorageClass::Static),
+                ..
+            }
+        ));
+        assert!(matches!(
+            &decls[2],
+            Declaration::GlobalVar {
+                storage: Some(StorageClass::Extern),
+                ..
+            }
+        ));
+        let Declaration::Function(func) = &decls[3] else {
+            panic!("Expected function, got {:?}", decls[3]);
+        };
+        assert!(matches!(
+            &func.body[0],
+            Stmt::VarDecl {
+                storage: Some(StorageClass::Static),
+                typ: CType::Int,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_incomplete_struct_declaration() {
+        let decls = parse_decls("struct Node; union Value; struct Node { int v; };");
+        assert_eq!(decls.len(), 3);
+        assert_eq!(
+            decls[0],
+            Declaration::ForwardDecl(CType::Struct("Node".to_string()))
+        );
+        assert_eq!(
+            decls[1],
+            Declaration::ForwardDecl(CType::Union("Value".to_string()))
+        );
+        assert!(matches!(&decls[2], Declaration::Struct(def) if def.name == "Node"));
+        let err = Parser::new("int;").parse().unwrap_err();
+        assert_eq!(err.message, "Declaration does not declare anything");
+    }
+
+    #[test]
+    fn test_struct_bitfields() {
+        let decls = parse_decls(
+            "struct Flags { unsigned int ready : 1; unsigned int : 3; int count; unsigned kind : 4; };",
+        );
+        let Declaration::Struct(struct_def) = &decls[0] else {
+            panic!("Expected struct, got {:?}", decls[0]);
+        };
+        let fields: Vec<_> = struct_def
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.bit_width))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("ready", Some(1)),
+                ("", Some(3)),
+                ("count", None),
+                ("kind", Some(4))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_union_bitfield() {
+        let decls = parse_decls("union U { int whole; unsigned int low : 16; };");
+        let Declaration::Union(union_def) = &decls[0] else {
+            panic!("Expected union, got {:?}", decls[0]);
+        };
+        assert_eq!(union_def.fields[1].bit_width, Some(16));
+    }
+
+    #[test]
+    fn test_bitfield_width_constant_expression() {
+        let decls = parse_decls(
+            "enum { KIND_BITS = 3 }; struct Op { unsigned kind : KIND_BITS; unsigned arg : KIND_BITS * 2 + 1; };",
+        );
+        let Declaration::Struct(struct_def) = &decls[1] else {
+            panic!("Expected struct, got {:?}", decls[1]);
+        };
+        let widths: Vec<_> = struct_def.fields.iter().map(|f| f.bit_width).collect();
+        assert_eq!(widths, vec![Some(3), Some(7)]);
+        let err = Parser::new("struct S { int x : n; };").parse().unwrap_err();
+        assert_eq!(err.message, "Expected integer constant for bit-field width");
+    }
+
+    #[test]
+    fn test_anonymous_union_member() {
+        let decls = parse_decls("struct Value { union { int a; float b; }; int tag; };");
+        let Declaration::Struct(struct_def) = &decls[0] else {
+            panic!("Expected struct, got {:?}", decls[0]);
+        };
+        assert_eq!(struct_def.fields.len(), 2);
+        assert!(struct_def.fields[0].name.is_empty());
+        let CType::UnionDef(inner) = &struct_def.fields[0].typ else {
+            panic!("Expected inline union, got {:?}", struct_def.fields[0].typ);
+        };
+        assert_eq!(inner.fields.len(), 2);
+        assert_eq!(struct_def.fields[1].name, "tag");
+    }
+
+    #[test]
+    fn test_anonymous_struct_member_in_union() {
+        let decls = parse_decls("union U { struct { int lo; int hi; }; long whole; };");
+        let Declaration::Union(union_def) = &decls[0] else {
+            panic!("Expected union, got {:?}", decls[0]);
+        };
+        assert!(union_def.fields[0].name.is_empty());
+        let CType::StructDef(inner) = &union_def.fields[0].typ else {
+            panic!("Expected inline struct, got {:?}", union_def.fields[0].typ);
+        };
+        assert_eq!(inner.fields[1].name, "hi");
+        assert_eq!(union_def.fields[1].name, "whole");
+    }
+
+    fn field(typ: CType, name: &str) -> StructField {
+        StructField {
+            typ,
+            name: name.to_string(),
+            bit_width: None,
+        }
+    }
+
+    #[test]
+    fn test_struct_definition_with_declarator() {
+        let decls = parse_decls("struct Point { int x; int y; } origin;");
+        assert_eq!(
+            decls[0],
+            Declaration::GlobalVar {
+                storage: None,
+                typ: CType::StructDef(Box::new(StructDef {
+                    name: "Point".to_string(),
+                    fields: vec![field(CType::Int, "x"), field(CType::Int, "y")],
+                })),
+                name: "origin".to_string(),
+                init: None,
+            }
+        );
+    }
+
+    fn global(typ: CType, name: &str, init: Option<Expr>) -> Declaration {
+        Declaration::GlobalVar {
+            storage: None,
+            typ,
+            name: name.to_string(),
+            init,
+        }
+    }
+
+    #[test]
+    fn test_global_multiple_declarators() {
+        let decls = parse_decls("int a = 1, *b, c[2] = {3, 4};");
+        assert_eq!(decls.len(), 3);
+        assert_eq!(decls[0], global(CType::Int, "a", Some(Expr::IntLiteral(1))));
+        assert_eq!(
+            decls[1],
+            global(CType::Pointer(Box::new(CType::Int)), "b", None)
+        );
+        let item = |n| InitItem {
+            designators: vec![],
+            value: Expr::IntLiteral(n),
+        };
+        assert_eq!(
+            decls[2],
+            global(
+                CType::Array {
+                    element_type: Box::new(CType::Int),
+                    size: Some(2),
+                    size_expr: None,
+                },
+                "c",
+                Some(Expr::InitList(vec![item(3), item(4)])),
+            )
+        );
+    }
+
+    #[test]
+    fn test_struct_definition_with_multiple_declarators() {
+        let decls = parse_decls("static struct Point { int x; } p, *q;");
+        assert_eq!(decls.len(), 2);
+        assert!(matches!(
+            &decls[0],
+            Declaration::GlobalVar {
+                storage: Some(StorageClass::Static),
+                typ: CType::StructDef(_),
+                ..
+            }
+        ));
+        // 结构体定义只保留在第一个声明符上
+        assert_eq!(
+            decls[1],
+            Declaration::GlobalVar {
+                storage: Some(StorageClass::Static),
+                typ: CType::Pointer(Box::new(CType::Struct("Point".to_string()))),
+                name: "q".to_string(),
+                init: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_typedef_anonymous_struct() {
+        let decls = parse_decls("typedef struct { int x; } Box; Box b;");
+        assert_eq!(
+            decls[0],
+            Declaration::Typedef(TypedefDef {
+                name: "Box".to_string(),
+                target_type: CType::StructDef(Box::new(StructDef {
+                    name: String::new(),
+                    fields: vec![field(CType::Int, "x")],
+                })),
+            })
+        );
+        assert!(
+            matches!(&decls[1], Declaration::GlobalVar { typ: CType::Typedef(n), .. } if n == "Box")
+        );
+    }
+
+    #[test]
+    fn test_function_returning_struct_pointer() {
+        let decls = parse_decls("struct Node *new_node(int v) { return 0; }");
+        let Declaration::Function(func) = &decls[0] else {
+            panic!("Expected function, got {:?}", decls[0]);
+        };
+        assert_eq!(
+            func.return_type,
+            CType::Pointer(Box::new(CType::Struct("Node".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_function_returning_pointers() {
+        let decls = parse_decls("int *get(void); int (*pick(int n))(int);");
+        let Declaration::Function(get) = &decls[0] else {
+            panic!("Expected function, got {:?}", decls[0]);
+        };
+        assert_eq!(get.return_type, CType::Pointer(Box::new(CType::Int)));
+        let Declaration::Function(pick) = &decls[1] else {
+            panic!("Expected function, got {:?}", decls[1]);
+        };
+        assert_eq!(pick.name, "pick");
+        assert_eq!(
+            pick.return_type,
+            CType::Pointer(Box::new(CType::Function {
+                return_type: Box::new(CType::Int),
+                params: vec![CType::Int],
+                variadic: false,
+            }))
+        );
+        // 括号内的声明符也保留参数名
+        assert_eq!(pick.params, vec![param(CType::Int, "n")]);
+    }
+
+    #[test]
+    fn test_void_and_empty_parameter_lists() {
+        let decls = parse_decls("int f(void); int g(); int h(void *p);");
+        let functions: Vec<&Function> = decls
+            .iter()
+            .map(|decl| match decl {
+                Declaration::Function(func) => func,
+                other => panic!("Expected function, got {:?}", other),
+            })
+            .collect();
+        assert!(functions[0].params.is_empty());
+        assert!(functions[0].explicit_void);
+        assert!(functions[1].params.is_empty());
+        assert!(!functions[1].explicit_void);
+        // void* 参数不是空参数列表
+        assert_eq!(functions[2].params.len(), 1);
+        assert!(!functions[2].explicit_void);
+    }
+
+    #[test]
+    fn test_typedef_redeclares_tag_name() {
+        let decls = parse_decls("typedef struct Type Type; typedef struct Type Type;");
+        assert_eq!(decls.len(), 2);
+        assert_eq!(decls[0], decls[1]);
+    }
+
+    fn global_init(decl: &Declaration) -> &Expr {
+        match decl {
+            Declaration::GlobalVar {
+                init: Some(init), ..
+            } => init,
+            other => panic!("Expected initialized global, got {:?}", other),
+        }
+    }
+
+    fn item(designators: Vec<Designator>, value: Expr) -> InitItem {
+        InitItem { designators, value }
+    }
+
+    #[test]
+    fn test_positional_initializer() {
+        let decls = parse_decls("int a[] = {1, 2, 3,};");
+        assert_eq!(
+            global_init(&decls[0]),
+            &Expr::InitList(vec![
+                item(vec![], Expr::IntLiteral(1)),
+                item(vec![], Expr::IntLiteral(2)),
+                item(vec![], Expr::IntLiteral(3)),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_designated_initializer() {
+        let decls = parse_decls("struct Point p = {.x = 1, .y = 2}; int t[4] = {[2] = 5};");
+        assert_eq!(
+            global_init(&decls[0]),
+            &Expr::InitList(vec![
+                item(
+                    vec![Designator::Field("x".to_string())],
+                    Expr::IntLiteral(1)
+                ),
+                item(
+                    vec![Designator::Field("y".to_string())],
+                    Expr::IntLiteral(2)
+                ),
+            ])
+        );
+        assert_eq!(
+            global_init(&decls[1]),
+            &Expr::InitList(vec![item(
+                vec![Designator::Index(Expr::IntLiteral(2))],
+                Expr::IntLiteral(5)
+            )])
+        );
+    }
+
+    #[test]
+    fn test_range_and_nested_designators() {
+        let decls = parse_decls("int t[16] = {[0 ... 7] = 1, 2, [N - 1] = 3}; struct Outer o = {.a.b = 1, .c[2].d = 4};");
+        assert_eq!(
+            global_init(&decls[0]),
+            &Expr::InitList(vec![
+                item(
+                    vec![Designator::Range(Expr::IntLiteral(0), Expr::IntLiteral(7))],
+                    Expr::IntLiteral(1)
+                ),
+                item(vec![], Expr::IntLiteral(2)),
+                item(
+                    vec![Designator::Index(Expr::Binary {
+                        op: BinaryOp::Sub,
+                        left: Box::new(Expr::Identifier("N".to_string())),
+                        right: Box::new(Expr::IntLiteral(1)),
+                    })],
+                    Expr::IntLiteral(3)
+                ),
+            ])
+        );
+        assert_eq!(
+            global_init(&decls[1]),
+            &Expr::InitList(vec![
+                item(
+                    vec![
+                        Designator::Field("a".to_string()),
+                        Designator::Field("b".to_string())
+                    ],
+                    Expr::IntLiteral(1)
+                ),
+                item(
+                    vec![
+                        Designator::Field("c".to_string()),
+                        Designator::Index(Expr::IntLiteral(2)),
+                        Designator::Field("d".to_string())
+                    ],
+                    Expr::IntLiteral(4)
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_range_designator_missing_upper_bound() {
+        let err = Parser::new("int t[4] = {[0 ...] = 1};")
+            .parse()
+            .unwrap_err();
+        assert_eq!(
+            err.message,
+            "Expected upper bound after '...' in range designator"
+        );
+    }
+
+    #[test]
+    fn test_nested_initializer() {
+        let decls = parse_decls("struct Point pts[] = {{1, 2}, {.x = 3}};");
+        let Expr::InitList(items) = global_init(&decls[0]) else {
+            panic!("Expected init list");
+        };
+        assert_eq!(items.len(), 2);
+        assert!(matches!(&items[0].value, Expr::InitList(inner) if inner.len() == 2));
+        assert!(
+            matches!(&items[1].value, Expr::InitList(inner) if inner[0].designators.len() == 1)
+        );
+    }
+
+    fn global_type(decl: &Declaration) -> &CType {
+        match decl {
+            Declaration::GlobalVar { typ, .. } => typ,
+            other => panic!("Expected global variable, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_bool_type() {
+        let decls =
+            parse_decls("_Bool flag; bool done = 0; int f(bool b) { bool t = b; return t; }");
+        assert_eq!(global_type(&decls[0]), &CType::Bool);
+        assert_eq!(global_type(&decls[1]), &CType::Bool);
+        let Declaration::Function(func) = &decls[2] else {
+            panic!("Expected function, got {:?}", decls[2]);
+        };
+        assert_eq!(func.params, vec![param(CType::Bool, "b")]);
+        assert!(matches!(
+            &func.body[0],
+            Stmt::VarDecl {
+                typ: CType::Bool,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_long_double_is_distinct() {
+        let decls = parse_decls("double a; long double b; double long c; long d;");
+        assert_eq!(global_type(&decls[0]), &CType::Double);
+        assert_eq!(global_type(&decls[1]), &CType::LongDouble);
+        assert_eq!(global_type(&decls[2]), &CType::LongDouble);
+        assert_eq!(global_type(&decls[3]), &CType::Long);
+    }
+
+    #[test]
+    fn test_long_long_is_distinct() {
+        let decls = parse_decls(
+            "long a; long long b; unsigned long long c; long long int d; unsigned long e;",
+        );
+        assert_eq!(global_type(&decls[0]), &CType::Long);
+        assert_eq!(global_type(&decls[1]), &CType::LongLong);
+        assert_eq!(global_type(&decls[2]), &CType::UnsignedLongLong);
+        assert_eq!(global_type(&decls[3]), &CType::LongLong);
+        assert_eq!(global_type(&decls[4]), &CType::UnsignedLong);
+    }
+
+    #[test]
+    fn test_include_declarations() {
+        let decls = parse_decls("#include <stdio.h>\n#include \"chibicc.h\"\nint x;");
+        assert_eq!(decls[0], Declaration::Include("<stdio.h>".to_string()));
+        assert_eq!(decls[1], Declaration::Include("\"chibicc.h\"".to_string()));
+        assert_eq!(global_type(&decls[2]), &CType::Int);
+    }
+
+    #[test]
+    fn test_define_declarations() {
+        let decls = parse_decls("#define SIZE 16\n#define ID(x) x\nint buf;");
+        assert_eq!(
+            decls[0],
+            Declaration::Define {
+                name: "SIZE".to_string(),
+                value: "16".to_string(),
+            }
+        );
+        assert_eq!(decls.len(), 2);
+        assert_eq!(global_type(&decls[1]), &CType::Int);
+    }
+
+    #[test]
+    fn test_recovering_parse_skips_bad_declarations() {
+        let input = r#"
+int a;
+int f() { return 1 @ 2; }
+struct S { int x; } s;
+int g(int x) { if (x) { return 1; } return 0; }
+int = 3;
+int b;
+"#;
+        let (program, errors) = Parser::new(input).parse_program_recovering();
+        let names: Vec<String> = program
+            .declarations
+            .iter()
+            .map(|decl| match decl {
+                Declaration::Function(func) => func.name.clone(),
+                Declaration::GlobalVar { name, .. } => name.clone(),
+                other => panic!("Unexpected declaration {:?}", other),
+            })
+            .collect();
+        assert_eq!(names, vec!["a", "s", "g", "b"]);
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert_eq!((errors[0].line, errors[0].col), (3, 20));
+        assert_eq!(errors[1].line, 6);
+    }
+
+    #[test]
+    fn test_recovering_parse_without_errors() {
+        let input = "int a; int main() { return a; }";
+        let (program, errors) = Parser::new(input).parse_program_recovering();
+        assert!(errors.is_empty());
+        assert_eq!(program, Parser::new(input).parse().unwrap());
+    }
+
+    #[test]
+    fn test_kr_function_definition() {
+        let decls = parse_decls("int add(a, b) int a; char *b; { return a; }");
+        let Declaration::Function(func) = &decls[0] else {
+            panic!("Expected function, got {:?}", decls[0]);
+        };
+        assert_eq!(
+            func.params,
+            vec![
+                param(CType::Int, "a"),
+                param(CType::Pointer(Box::new(CType::Char)), "b")
+            ]
+        );
+        assert_eq!(
+            func.body,
+            vec![Stmt::Return(Some(Expr::Identifier("a".to_string())))]
+        );
+    }
+
+    #[test]
+    fn test_kr_params_default_to_int() {
+        let decls = parse_decls("int f(x, y) long y; { return x; }");
+        let Declaration::Function(func) = &decls[0] else {
+            panic!("Expected function, got {:?}", decls[0]);
+        };
+        assert_eq!(
+            func.params,
+            vec![param(CType::Int, "x"), param(CType::Long, "y")]
+        );
+    }
+
+    #[test]
+    fn test_prototype_identifiers_are_types() {
+        // 只有函数定义才可能使用标识符列表，原型中的未知标识符是类型名
+        let decls =
+            parse_decls("void f(size_t); void g(size_t n, FILE *out); int h(a) { return a; }");
+        let functions: Vec<&Function> = decls
+            .iter()
+            .map(|d| match d {
+                Declaration::Function(func) => func,
+                other => panic!("Expected function, got {:?}", other),
+            })
+            .collect();
+        let size_t = CType::Typedef("size_t".to_string());
+        assert_eq!(functions[0].params, vec![param(size_t.clone(), "")]);
+        assert_eq!(
+            functions[1].params,
+            vec![
+                param(size_t, "n"),
+                param(
+                    CType::Pointer(Box::new(CType::Typedef("FILE".to_string()))),
+                    "out"
+                )
+            ]
+        );
+        assert_eq!(functions[2].params, vec![param(CType::Int, "a")]);
+    }
+
+    #[test]
+    fn test_kr_unknown_parameter_is_error() {
+        let err = Parser::new("int f(a) int b; { return 0; }")
+            .parse()
+            .unwrap_err();
+        assert_eq!(
+            err.message,
+            "Declaration for parameter 'b' but no such parameter"
+        );
+        assert_eq!((err.line, err.col), (1, 14));
+    }
+
+    #[test]
+    fn test_storage_classes() {
+        let decls = parse_decls(
+            "static int counter; extern const char *name; static int next(void) { register int r = 1; auto int a; static int calls; return r; }",
+        );
+        assert!(matches!(
+            &decls[0],
+            Declaration::GlobalVar {
+                storage: Some(StorageClass::Static),
+                ..
+            }
+        ));
+        assert!(matches!(
+            &decls[1],
+            Declaration::GlobalVar {
+                storage: Some(StorageClass::Extern),
+                ..
+            }
+        ));
+        let Declaration::Function(func) = &decls[2] else {
+            panic!("Expected function, got {:?}", decls[2]);
+        };
+        assert_eq!(func.storage, Some(StorageClass::Static));
+        let storages: Vec<_> = func
+            .body
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::VarDecl { storage, .. } => Some(storage.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            storages,
+            vec![
+                Some(StorageClass::Register),
+                Some(StorageClass::Auto),
+                Some(StorageClass::Static)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pointer_qualifier_positions() {
+        let ptr = |t: CType| CType::Pointer(Box::new(t));
+        let konst = |t: CType| CType::Const(Box::new(t));
+        let decls = parse_decls(
+            "const char *a; char *const b; const char *const c; char *d; char const *e; int *const *volatile f;",
+        );
+        assert_eq!(global_type(&decls[0]), &ptr(konst(CType::Char)));
+        assert_eq!(global_type(&decls[1]), &konst(ptr(CType::Char)));
+        assert_eq!(global_type(&decls[2]), &konst(ptr(konst(CType::Char))));
+        assert_eq!(global_type(&decls[3]), &ptr(CType::Char));
+        assert_eq!(global_type(&decls[4]), &ptr(konst(CType::Char)));
+        assert_eq!(
+            global_type(&decls[5]),
+            &CType::Volatile(Box::new(ptr(konst(ptr(CType::Int)))))
+        );
+    }
+
+    #[test]
+    fn test_typedef_multiple_names() {
+        let decls = parse_decls("typedef int T, *PT, Arr[10]; T a; PT b; Arr c;");
+        let typedef = |name: &str, target_type: CType| {
+            Declaration::Typedef(TypedefDef {
+                name: name.to_string(),
+                target_type,
+            })
+        };
+        assert_eq!(
+            decls[..3],
+            [
+                typedef("T", CType::Int),
+                typedef("PT", CType::Pointer(Box::new(CType::Int))),
+                typedef(
+                    "Arr",
+                    CType::Array {
+                        element_type: Box::new(CType::Int),
+                        size: Some(10),
+                        size_expr: None,
+                    }
+                ),
+            ]
+        );
+        assert_eq!(global_type(&decls[5]), &CType::Typedef("Arr".to_string()));
+    }
+
+    // 返回数组类型的 (size, size_expr)
+    fn array_size(decl: &Declaration) -> (Option<usize>, Option<Expr>) {
+        match global_type(decl) {
+            CType::Array {
+                size, size_expr, ..
+            } => (*size, size_expr.as_deref().cloned()),
+            other => panic!("Expected array type, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_array_size_constant_expressions() {
+        let decls = parse_decls(
+            "#define FOO 8\n\
+             enum { N = 3, M };\n\
+             int a[10]; int b[2+3]; int c[FOO]; int d[M * 2]; int e[UNKNOWN];",
+        );
+        assert_eq!(array_size(&decls[2]), (Some(10), None));
+        assert_eq!(
+            array_size(&decls[3]),
+            (
+                Some(5),
+                Some(Expr::Binary {
+                    op: BinaryOp::Add,
+                    left: Box::new(Expr::IntLiteral(2)),
+                    right: Box::new(Expr::IntLiteral(3)),
+                })
+            )
+        );
+        assert_eq!(
+            array_size(&decls[4]),
+            (Some(8), Some(Expr::Identifier("FOO".to_string())))
+        );
+        assert_eq!(array_size(&decls[5]).0, Some(8));
+        // 无法求值时只保留表达式
+        assert_eq!(
+            array_size(&decls[6]),
+            (None, Some(Expr::Identifier("UNKNOWN".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_inline_and_restrict() {
+        let decls = parse_decls("static inline int load(const int *restrict p) { return *p; }");
+        let Declaration::Function(func) = &decls[0] else {
+            panic!("Expected function");
+        };
+        assert!(func.is_inline);
+        assert_eq!(func.storage, Some(StorageClass::Static));
+        assert_eq!(
+            func.params,
+            vec![param(
+                CType::Restrict(Box::new(CType::Pointer(Box::new(CType::Const(Box::new(
+                    CType::Int
+                )))))),
+                "p"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_atomic_and_alignas_are_skipped() {
+        let decls = parse_decls("_Atomic int a; _Atomic(long) b; _Alignas(16) char buf[4];");
+        assert_eq!(global_type(&decls[0]), &CType::Int);
+        assert_eq!(global_type(&decls[1]), &CType::Long);
+        assert_eq!(array_size(&decls[2]), (Some(4), None));
+    }
+
+    #[test]
+    fn test_known_typedefs() {
+        let known = ["usize".to_string()].into_iter().collect();
+        let program = Parser::new("usize n; int f(usize x) { return x; }")
+            .with_known_typedefs(known)
+            .parse()
+            .unwrap();
+        assert_eq!(
+            global_type(&program.declarations[0]),
+            &CType::Typedef("usize".to_string())
+        );
+        assert!(Parser::new("usize n;").parse().is_err());
+    }
+
+    #[test]
+    fn test_repeated_typedef() {
+        let decls =
+            parse_decls("typedef unsigned long size_t; typedef unsigned long size_t; size_t n;");
+        assert_eq!(decls.len(), 3);
+        assert_eq!(
+            decls[1],
+            Declaration::Typedef(TypedefDef {
+                name: "size_t".to_string(),
+                target_type: CType::UnsignedLong,
+            })
+        );
+        assert_eq!(
+            global_type(&decls[2]),
+            &CType::Typedef("size_t".to_string())
+        );
+    }
+
+    #[test]
+    fn test_inline_specifier_positions() {
+        for src in [
+            "static inline int f(void) { return 0; }",
+            "inline static int f(void) { return 0; }",
+            "int static inline f(void) { return 0; }",
+        ] {
+            let Declaration::Function(func) = &parse_decls(src)[0] else {
+                panic!("Expected function: {}", src);
+            };
+            assert!(func.is_inline, "{}", src);
+            assert_eq!(func.storage, Some(StorageClass::Static), "{}", src);
+        }
+        let Declaration::Function(func) = &parse_decls("inline int g(void) { return 0; }")[0]
+        else {
+            panic!("Expected function");
+        };
+        assert!(func.is_inline);
+        assert_eq!(func.storage, None);
+    }
+
+    #[test]
+    fn test_unnamed_function_pointer_params() {
+        let Declaration::Function(func) =
+            &parse_decls("int apply(int (*)(int), int (*)[4], int);")[0]
+        else {
+            panic!("Expected function");
+        };
+        assert_eq!(
+            func.params,
+            vec![
+                param(
+                    CType::Pointer(Box::new(CType::Function {
+                        return_type: Box::new(CType::Int),
+                        params: vec![CType::Int],
+                        variadic: false,
+                    })),
+                    ""
+                ),
+                param(
+                    CType::Pointer(Box::new(CType::Array {
+                        element_type: Box::new(CType::Int),
+                        size: Some(4),
+                        size_expr: None,
+                    })),
+                    ""
+                ),
+                param(CType::Int, ""),
+            ]
+        );
+    }
+}
@@ -1,4 +1,5 @@
 /// 测试表达式解析功能
+use c_to_rust_tool::ast::*;
 use c_to_rust_tool::parser::Parser;
 
 #[cfg(test)]
@@ -233,4 +234,407 @@ mod tests {
             result.err()
         );
     }
+
+    #[test]
+    fn test_parse_alias() {
+        let input = r#"
+        int main() {
+            int x = (1 + 2) * 3;
+            return x;
+        }
+        "#;
+
+        let mut parser = Parser::new(input);
+        let result = parser.parse();
+        assert!(
+            result.is_ok(),
+            "Failed to parse via Parser::parse: {:?}",
+            result.err()
+        );
+        assert_eq!(result, Parser::new(input).parse_program());
+    }
+
+    // 解析函数体中的第一条表达式语句
+    fn parse_first_expr(body: &str) -> Expr {
+        let input = format!("int main() {{ {} }}", body);
+        let program = Parser::new(&input).parse().unwrap();
+        let Declaration::Function(func) = &program.declarations[0] else {
+            panic!("Expected function");
+        };
+        match &func.body[0] {
+            Stmt::Expr(expr) => expr.clone(),
+            other => panic!("Expected expression statement, got {:?}", other),
+        }
+    }
+
+    fn ident(name: &str) -> Expr {
+        Expr::Identifier(name.to_string())
+    }
+
+    #[test]
+    fn test_comma_operator() {
+        let expr = parse_first_expr("x = (a, b, c);");
+        assert_eq!(
+            expr,
+            Expr::Assignment {
+                target: Box::new(ident("x")),
+                value: Box::new(Expr::Comma(vec![ident("a"), ident("b"), ident("c")])),
+            }
+        );
+    }
+
+    #[test]
+    fn test_call_arguments_are_not_comma_expressions() {
+        let expr = parse_first_expr("f(a, (b, c));");
+        assert_eq!(
+            expr,
+            Expr::Call {
+                func: "f".to_string(),
+                args: vec![ident("a"), Expr::Comma(vec![ident("b"), ident("c")])],
+            }
+        );
+    }
+
+    #[test]
+    fn test_call_through_function_pointer() {
+        let expr = parse_first_expr("(*fp)(1, 2);");
+        assert_eq!(
+            expr,
+            Expr::CallExpr {
+                callee: Box::new(Expr::Unary {
+                    op: UnaryOp::Deref,
+                    operand: Box::new(ident("fp")),
+                }),
+                args: vec![Expr::IntLiteral(1), Expr::IntLiteral(2)],
+            }
+        );
+    }
+
+    #[test]
+    fn test_call_on_postfix_expressions() {
+        let expr = parse_first_expr("tbl[i](x);");
+        assert_eq!(
+            expr,
+            Expr::CallExpr {
+                callee: Box::new(Expr::ArrayAccess {
+                    array: Box::new(ident("tbl")),
+                    index: Box::new(ident("i")),
+                }),
+                args: vec![ident("x")],
+            }
+        );
+
+        let expr = parse_first_expr("ops->handler(a)(b);");
+        assert_eq!(
+            expr,
+            Expr::CallExpr {
+                callee: Box::new(Expr::CallExpr {
+                    callee: Box::new(Expr::PointerMemberAccess {
+                        object: Box::new(ident("ops")),
+                        member: "handler".to_string(),
+                    }),
+                    args: vec![ident("a")],
+                }),
+                args: vec![ident("b")],
+            }
+        );
+    }
+
+    #[test]
+    fn test_for_with_comma_update() {
+        let input = r#"
+        int main() {
+            int i; int j;
+            for (i = 0, j = 10; i < j; i++, j--) { }
+            return 0;
+        }
+        "#;
+        let program = Parser::new(input).parse().unwrap();
+        let Declaration::Function(func) = &program.declarations[0] else {
+            panic!("Expected function");
+        };
+        let Stmt::For { init, update, .. } = &func.body[2] else {
+            panic!("Expected for loop, got {:?}", func.body[2]);
+        };
+        assert!(matches!(init.as_deref(), Some(Stmt::Expr(Expr::Comma(v))) if v.len() == 2));
+        assert!(matches!(update, Some(Expr::Comma(v)) if v.len() == 2));
+    }
+
+    #[test]
+    fn test_compound_literals() {
+        assert_eq!(
+            parse_first_expr("p = (struct Point){ .x = 1 };"),
+            Expr::Assignment {
+                target: Box::new(ident("p")),
+                value: Box::new(Expr::CompoundLiteral {
+                    typ: CType::Struct("Point".to_string()),
+                    init: vec![InitItem {
+                        designators: vec![Designator::Field("x".to_string())],
+                        value: Expr::IntLiteral(1),
+                    }],
+                }),
+            }
+        );
+        assert_eq!(
+            parse_first_expr("f((int[]){1, 2});"),
+            Expr::Call {
+                func: "f".to_string(),
+                args: vec![Expr::CompoundLiteral {
+                    typ: CType::Array {
+                        element_type: Box::new(CType::Int),
+                        size: None,
+                        size_expr: None,
+                    },
+                    init: vec![
+                        InitItem {
+                            designators: vec![],
+                            value: Expr::IntLiteral(1),
+                        },
+                        InitItem {
+                            designators: vec![],
+                            value: Expr::IntLiteral(2),
+                        },
+                    ],
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn test_sizeof_expression_keeps_operand() {
+        assert_eq!(
+            parse_first_expr("n = sizeof arr;"),
+            Expr::Assignment {
+                target: Box::new(ident("n")),
+                value: Box::new(Expr::SizeOfExpr(Box::new(ident("arr")))),
+            }
+        );
+        assert_eq!(
+            parse_first_expr("n = sizeof(x + 1);"),
+            Expr::Assignment {
+                target: Box::new(ident("n")),
+                value: Box::new(Expr::SizeOfExpr(Box::new(Expr::Binary {
+                    op: BinaryOp::Add,
+                    left: Box::new(ident("x")),
+                    right: Box::new(Expr::IntLiteral(1)),
+                }))),
+            }
+        );
+    }
+
+    #[test]
+    fn test_sizeof_array_type() {
+        assert_eq!(
+            parse_first_expr("n = sizeof(int[4]);"),
+            Expr::Assignment {
+                target: Box::new(ident("n")),
+                value: Box::new(Expr::SizeOf(CType::Array {
+                    element_type: Box::new(CType::Int),
+                    size: Some(4),
+                    size_expr: None,
+                })),
+            }
+        );
+    }
+
+    #[test]
+    fn test_logical_and_binds_tighter_than_or() {
+        assert_eq!(
+            parse_first_expr("a || b && c;"),
+            Expr::Binary {
+                op: BinaryOp::Or,
+                left: Box::new(ident("a")),
+                right: Box::new(Expr::Binary {
+                    op: BinaryOp::And,
+                    left: Box::new(ident("b")),
+                    right: Box::new(ident("c")),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn test_relational_binds_tighter_than_equality() {
+        assert_eq!(
+            parse_first_expr("a == b < c;"),
+            Expr::Binary {
+                op: BinaryOp::Eq,
+                left: Box::new(ident("a")),
+                right: Box::new(Expr::Binary {
+                    op: BinaryOp::Lt,
+                    left: Box::new(ident("b")),
+                    right: Box::new(ident("c")),
+                }),
+            }
+        );
+    }
+
+    fn ternary(cond: Expr, then_expr: Expr, else_expr: Expr) -> Expr {
+        Expr::Ternary {
+            cond: Box::new(cond),
+            then_expr: Box::new(then_expr),
+            else_expr: Box::new(else_expr),
+        }
+    }
+
+    fn assign(target: Expr, value: Expr) -> Expr {
+        Expr::Assignment {
+            target: Box::new(target),
+            value: Box::new(value),
+        }
+    }
+
+    #[test]
+    fn test_ternary_else_takes_assignment() {
+        assert_eq!(
+            parse_first_expr("cond ? x : y = z;"),
+            ternary(ident("cond"), ident("x"), assign(ident("y"), ident("z")))
+        );
+    }
+
+    #[test]
+    fn test_nested_ternary_is_right_associative() {
+        assert_eq!(
+            parse_first_expr("a ? b : c ? d : e;"),
+            ternary(
+                ident("a"),
+                ident("b"),
+                ternary(ident("c"), ident("d"), ident("e"))
+            )
+        );
+        assert_eq!(
+            parse_first_expr("a ? b ? c : d : e;"),
+            ternary(
+                ident("a"),
+                ternary(ident("b"), ident("c"), ident("d")),
+                ident("e")
+            )
+        );
+    }
+
+    #[test]
+    fn test_assignment_of_ternary() {
+        assert_eq!(
+            parse_first_expr("x = a ? b : c;"),
+            assign(ident("x"), ternary(ident("a"), ident("b"), ident("c")))
+        );
+        // 中间分支是完整表达式，可以包含逗号
+        assert_eq!(
+            parse_first_expr("a ? b, c : d;"),
+            ternary(
+                ident("a"),
+                Expr::Comma(vec![ident("b"), ident("c")]),
+                ident("d")
+            )
+        );
+    }
+
+    #[test]
+    fn test_statement_expression() {
+        assert_eq!(
+            parse_first_expr("y = ({ int t = x; t * 2; });"),
+            Expr::Assignment {
+                target: Box::new(ident("y")),
+                value: Box::new(Expr::StmtExpr(vec![
+                    Stmt::VarDecl {
+                        storage: None,
+                        typ: CType::Int,
+                        name: "t".to_string(),
+                        init: Some(ident("x")),
+                    },
+                    Stmt::Expr(Expr::Binary {
+                        op: BinaryOp::Mul,
+                        left: Box::new(ident("t")),
+                        right: Box::new(Expr::IntLiteral(2)),
+                    }),
+                ])),
+            }
+        );
+    }
+
+    #[test]
+    fn test_paren_expression_after_failed_type_name() {
+        // (foo) 不是类型名时按括号表达式解析，其后的 - 1 是减法
+        assert_eq!(
+            parse_first_expr("(foo) - 1;"),
+            Expr::Binary {
+                op: BinaryOp::Sub,
+                left: Box::new(ident("foo")),
+                right: Box::new(Expr::IntLiteral(1)),
+            }
+        );
+        // foo 是 typedef 名时是对 -1 的类型转换
+        let program = Parser::new("typedef int foo; int main() { (foo) - 1; (foo * 2); }")
+            .parse()
+            .unwrap();
+        let Declaration::Function(func) = &program.declarations[1] else {
+            panic!("Expected function");
+        };
+        assert_eq!(
+            func.body[0],
+            Stmt::Expr(Expr::Cast {
+                typ: CType::Typedef("foo".to_string()),
+                expr: Box::new(Expr::Unary {
+                    op: UnaryOp::Neg,
+                    operand: Box::new(Expr::IntLiteral(1)),
+                }),
+            })
+        );
+        // 试探解析已经读过 foo * 后失败，回溯后整个括号按表达式重新解析，不丢失 token
+        assert_eq!(
+            func.body[1],
+            Stmt::Expr(Expr::Binary {
+                op: BinaryOp::Mul,
+                left: Box::new(ident("foo")),
+                right: Box::new(Expr::IntLiteral(2)),
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_type_name_in_parens_reports_type_error() {
+        let err = Parser::new("int main() { return (int x) 1; }")
+            .parse()
+            .unwrap_err();
+        assert_eq!(err.message, "Expected ')', got 'x'");
+    }
+
+    #[test]
+    fn test_abstract_declarators_in_sizeof_and_casts() {
+        let array_of = |element: CType, size| CType::Array {
+            element_type: Box::new(element),
+            size: Some(size),
+            size_expr: None,
+        };
+        assert_eq!(
+            parse_first_expr("sizeof(int[4]);"),
+            Expr::SizeOf(array_of(CType::Int, 4))
+        );
+        assert_eq!(
+            parse_first_expr("sizeof(char*);"),
+            Expr::SizeOf(CType::Pointer(Box::new(CType::Char)))
+        );
+        assert_eq!(
+            parse_first_expr("(int (*)[3])p;"),
+            Expr::Cast {
+                typ: CType::Pointer(Box::new(array_of(CType::Int, 3))),
+                expr: Box::new(ident("p")),
+            }
+        );
+        assert_eq!(
+            parse_first_expr("sizeof(int *[3]);"),
+            Expr::SizeOf(array_of(CType::Pointer(Box::new(CType::Int)), 3))
+        );
+        assert_eq!(
+            parse_first_expr("(int (*)(char))f;"),
+            Expr::Cast {
+                typ: CType::Pointer(Box::new(CType::Function {
+                    return_type: Box::new(CType::Int),
+                    params: vec![CType::Char],
+                    variadic: false,
+                })),
+                expr: Box::new(ident("f")),
+            }
+        );
+    }
 }
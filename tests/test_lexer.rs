@@ -0,0 +1,680 @@
+/// 测试词法分析功能
+use c_to_rust_tool::ast::{char_raw_byte, raw_byte_char};
+use c_to_rust_tool::codegen::CodeGenerator;
+use c_to_rust_tool::lexer::{Lexer, SpannedToken, Token};
+use c_to_rust_tool::parser::Parser;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_char_does_not_truncate() {
+        let mut lexer = Lexer::new("a @ b $ c;");
+        let tokens = lexer.tokenize();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::Unknown('@'),
+                Token::Identifier("b".to_string()),
+                Token::Unknown('$'),
+                Token::Identifier("c".to_string()),
+                Token::Semicolon,
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_char_reported_by_parser() {
+        let input = r#"
+        int main() {
+            int x = 1 @ 2;
+            return x;
+        }
+        "#;
+
+        let mut parser = Parser::new(input);
+        let result = parser.parse_program();
+        let err = result.expect_err("stray '@' should be rejected");
+        assert!(
+            err.message.contains('@'),
+            "error should mention '@': {}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_hex_and_octal_literals() {
+        let mut lexer = Lexer::new("0xFF 0x1a2b 0X10 0777 0 010");
+        let tokens = lexer.tokenize();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::IntLiteral(255),
+                Token::IntLiteral(0x1a2b),
+                Token::IntLiteral(16),
+                Token::IntLiteral(0o777),
+                Token::IntLiteral(0),
+                Token::IntLiteral(8),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_leading_zero_float_stays_decimal() {
+        let mut lexer = Lexer::new("0.5 012.5");
+        let tokens = lexer.tokenize();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::FloatLiteral(0.5),
+                Token::FloatLiteral(12.5),
+                Token::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn test_binary_literals() {
+        let mut lexer = Lexer::new("0b1010 0B11 0b0");
+        let tokens = lexer.tokenize();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::IntLiteral(10),
+                Token::IntLiteral(3),
+                Token::IntLiteral(0),
+                Token::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn test_binary_literal_without_digits_is_error() {
+        let mut lexer = Lexer::new("0b;");
+        let tokens = lexer.tokenize();
+        assert!(matches!(tokens[0], Token::Error(_)), "got {:?}", tokens[0]);
+        assert_eq!(tokens[1], Token::Semicolon);
+
+        let mut parser = Parser::new("int x = 0b2;");
+        let err = parser.parse_program().expect_err("0b2 should be rejected");
+        assert!(err.message.contains("binary"), "unexpected error: {}", err);
+    }
+
+    #[test]
+    fn test_integer_suffixes() {
+        let mut lexer = Lexer::new("10U 0L 100ULL 7llu 0x10ul 42");
+        let tokens = lexer.tokenize();
+        let suffixed = |value, unsigned, long_count| Token::IntLiteralSuffixed {
+            value,
+            unsigned,
+            long_count,
+        };
+        assert_eq!(
+            tokens,
+            vec![
+                suffixed(10, true, 0),
+                suffixed(0, false, 1),
+                suffixed(100, true, 2),
+                suffixed(7, true, 2),
+                suffixed(16, true, 1),
+                Token::IntLiteral(42),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_integer_suffix() {
+        let mut lexer = Lexer::new("1lL 2uu 3lul");
+        let tokens = lexer.tokenize();
+        assert!(matches!(tokens[0], Token::Error(_)), "got {:?}", tokens[0]);
+        assert!(matches!(tokens[1], Token::Error(_)), "got {:?}", tokens[1]);
+        assert!(matches!(tokens[2], Token::Error(_)), "got {:?}", tokens[2]);
+    }
+
+    #[test]
+    fn test_suffixed_literal_round_trip() {
+        let input = r#"
+        int main() {
+            unsigned long x = 100UL;
+            return 0;
+        }
+        "#;
+
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program().expect("parse failed");
+        let generated = CodeGenerator::new().generate_program(&program);
+        assert!(generated.contains("100UL"), "generated: {}", generated);
+    }
+
+    #[test]
+    fn test_large_integer_literals() {
+        let mut lexer = Lexer::new("2147483648 4294967295 0xFFFFFFFFFFFFFFFF 0xcbf29ce484222325");
+        let tokens = lexer.tokenize();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::IntLiteral(2147483648),
+                Token::IntLiteral(4294967295),
+                Token::IntLiteral(u64::MAX as i128),
+                Token::IntLiteral(0xcbf29ce484222325),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_out_of_range_integer_literal_is_error() {
+        let mut lexer = Lexer::new("0x1FFFFFFFFFFFFFFFF 99999999999999999999");
+        let tokens = lexer.tokenize();
+        assert!(matches!(tokens[0], Token::Error(_)), "got {:?}", tokens[0]);
+        assert!(matches!(tokens[1], Token::Error(_)), "got {:?}", tokens[1]);
+    }
+
+    #[test]
+    fn test_large_literal_codegen() {
+        let input = r#"
+        int main() {
+            long a = 4294967295;
+            unsigned long h = 0xcbf29ce484222325;
+            return 0;
+        }
+        "#;
+
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program().expect("parse failed");
+        let generated = CodeGenerator::new().generate_program(&program);
+        assert!(generated.contains("4294967295"), "generated: {}", generated);
+        assert!(
+            generated.contains("0xCBF29CE484222325"),
+            "generated: {}",
+            generated
+        );
+    }
+
+    #[test]
+    fn test_scientific_and_suffixed_floats() {
+        let mut lexer = Lexer::new("1.5e10 2.5f 2.0E-3 1e9 .5 1.0L 6e+2F");
+        let tokens = lexer.tokenize();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::FloatLiteral(1.5e10),
+                Token::FloatLiteralSuffixed {
+                    value: 2.5,
+                    suffix: 'f'
+                },
+                Token::FloatLiteral(2.0e-3),
+                Token::FloatLiteral(1e9),
+                Token::FloatLiteral(0.5),
+                Token::FloatLiteralSuffixed {
+                    value: 1.0,
+                    suffix: 'l'
+                },
+                Token::FloatLiteralSuffixed {
+                    value: 600.0,
+                    suffix: 'f'
+                },
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_float_missing_exponent_digits() {
+        let mut lexer = Lexer::new("1e+;");
+        let tokens = lexer.tokenize();
+        assert!(matches!(tokens[0], Token::Error(_)), "got {:?}", tokens[0]);
+        assert_eq!(tokens[1], Token::Semicolon);
+    }
+
+    #[test]
+    fn test_hex_floats() {
+        let mut lexer = Lexer::new("0x1.8p3 0x.8P1f 0X10p-2 0xAp+0L 0x1F");
+        let tokens = lexer.tokenize();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::FloatLiteral(12.0),
+                Token::FloatLiteralSuffixed {
+                    value: 1.0,
+                    suffix: 'f'
+                },
+                Token::FloatLiteral(4.0),
+                Token::FloatLiteralSuffixed {
+                    value: 10.0,
+                    suffix: 'l'
+                },
+                Token::IntLiteral(31),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hex_float_errors() {
+        // 十六进制浮点数必须带 p 指数
+        for input in ["0x1.8;", "0x1.8p;", "0x.p1;"] {
+            let tokens = Lexer::new(input).tokenize();
+            assert!(
+                matches!(tokens[0], Token::Error(_)),
+                "{}: {:?}",
+                input,
+                tokens
+            );
+            assert_eq!(tokens[1], Token::Semicolon, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_float_literal_codegen() {
+        let input = r#"
+        int main() {
+            float a = 2.5f;
+            double b = 2.0;
+            double c = 1e9;
+            return 0;
+        }
+        "#;
+
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program().expect("parse failed");
+        let generated = CodeGenerator::new().generate_program(&program);
+        assert!(generated.contains("2.5f"), "generated: {}", generated);
+        assert!(generated.contains("b = 2.0;"), "generated: {}", generated);
+        assert!(
+            generated.contains("c = 1000000000.0;"),
+            "generated: {}",
+            generated
+        );
+    }
+
+    #[test]
+    fn test_comments_are_skipped() {
+        let input = "a // line comment\nb /* block\n comment */ c";
+        let mut lexer = Lexer::new(input);
+        assert_eq!(
+            lexer.tokenize(),
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::Identifier("b".to_string()),
+                Token::Identifier("c".to_string()),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_retained_comments() {
+        let mut lexer = Lexer::new("// header\nint /* a */ x; /* b\n c */").with_comments(true);
+        assert_eq!(lexer.next_token(), Token::Int);
+        assert_eq!(lexer.take_comments(), vec!["// header"]);
+        assert_eq!(lexer.next_token(), Token::Identifier("x".to_string()));
+        assert_eq!(lexer.take_comments(), vec!["/* a */"]);
+        assert_eq!(lexer.next_token(), Token::Semicolon);
+        assert_eq!(lexer.next_token(), Token::Eof);
+        assert_eq!(lexer.take_comments(), vec!["/* b\n c */"]);
+
+        // 默认不保留
+        let mut lexer = Lexer::new("// header\nint x;");
+        assert_eq!(lexer.next_token(), Token::Int);
+        assert!(lexer.take_comments().is_empty());
+    }
+
+    #[test]
+    fn test_block_comments_do_not_nest() {
+        let mut lexer = Lexer::new("/* /* */ x */");
+        assert_eq!(
+            lexer.tokenize(),
+            vec![
+                Token::Identifier("x".to_string()),
+                Token::Star,
+                Token::Slash,
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_slash_without_comment() {
+        let mut lexer = Lexer::new("a / b; a /= 2; a/*c*/b");
+        assert_eq!(
+            lexer.tokenize(),
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::Slash,
+                Token::Identifier("b".to_string()),
+                Token::Semicolon,
+                Token::Identifier("a".to_string()),
+                Token::SlashAssign,
+                Token::IntLiteral(2),
+                Token::Semicolon,
+                Token::Identifier("a".to_string()),
+                Token::Identifier("b".to_string()),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_char_literal_escapes() {
+        let mut lexer = Lexer::new(r"'\n' '\0' '\t' '\\' '\'' 'a' '\r'");
+        assert_eq!(
+            lexer.tokenize(),
+            vec![
+                Token::CharLiteral('\n'),
+                Token::CharLiteral('\0'),
+                Token::CharLiteral('\t'),
+                Token::CharLiteral('\\'),
+                Token::CharLiteral('\''),
+                Token::CharLiteral('a'),
+                Token::CharLiteral('\r'),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_and_unterminated_char_literals() {
+        let mut lexer = Lexer::new("'' x");
+        let tokens = lexer.tokenize();
+        assert!(matches!(tokens[0], Token::Error(_)), "got {:?}", tokens[0]);
+        assert_eq!(tokens[1], Token::Identifier("x".to_string()));
+
+        let mut lexer = Lexer::new("'a");
+        let tokens = lexer.tokenize();
+        assert!(matches!(tokens[0], Token::Error(_)), "got {:?}", tokens[0]);
+        assert_eq!(tokens[1], Token::Eof);
+    }
+
+    #[test]
+    fn test_unterminated_string_literal() {
+        let mut lexer = Lexer::new("x = \"abc");
+        let tokens = lexer.tokenize_spanned();
+        assert_eq!(
+            tokens[2],
+            SpannedToken {
+                token: Token::Error("Unterminated string literal".to_string()),
+                line: 1,
+                col: 5,
+            }
+        );
+        assert_eq!(tokens[3].token, Token::Eof);
+
+        // 字符串不能跨行，后面的代码不会被吞掉
+        let mut lexer = Lexer::new("\"abc\nint y;");
+        let tokens = lexer.tokenize();
+        assert!(matches!(tokens[0], Token::Error(_)), "got {:?}", tokens[0]);
+        assert_eq!(tokens[1], Token::Int);
+    }
+
+    #[test]
+    fn test_unterminated_literal_error_location() {
+        for (input, message) in [
+            ("char *s = \"abc", "Unterminated string literal"),
+            ("char *c = 'a", "Unterminated character literal"),
+        ] {
+            let err = Parser::new(input).parse().unwrap_err();
+            assert_eq!((err.line, err.col), (1, 11), "{}", input);
+            assert_eq!(err.message, message);
+        }
+    }
+
+    #[test]
+    fn test_hex_and_octal_escapes() {
+        let mut lexer = Lexer::new(r#""\x41\102" "\033[0m" '\x7f' '\101' "\r\v\f\a\b""#);
+        assert_eq!(
+            lexer.tokenize(),
+            vec![
+                Token::StringLiteral("AB".to_string()),
+                Token::StringLiteral("\x1b[0m".to_string()),
+                Token::CharLiteral('\x7f'),
+                Token::CharLiteral('A'),
+                Token::StringLiteral("\r\x0b\x0c\x07\x08".to_string()),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_high_byte_escapes_in_strings() {
+        // 高位字节转义与直接写出的 ÿ 不同，字符常量仍按数值存放
+        let mut lexer = Lexer::new("\"\\xff\\377\u{ff}\" '\\xff'");
+        assert_eq!(
+            lexer.tokenize(),
+            vec![
+                Token::StringLiteral(format!("{0}{0}\u{ff}", raw_byte_char(0xff))),
+                Token::CharLiteral('\u{ff}'),
+                Token::Eof,
+            ]
+        );
+        assert_eq!(char_raw_byte(raw_byte_char(0x80)), Some(0x80));
+        assert_eq!(char_raw_byte('\u{ff}'), None);
+    }
+
+    #[test]
+    fn test_octal_escape_stops_after_three_digits() {
+        let mut lexer = Lexer::new(r#""\1234" "\0abc""#);
+        assert_eq!(
+            lexer.tokenize(),
+            vec![
+                Token::StringLiteral("S4".to_string()),
+                Token::StringLiteral("\0abc".to_string()),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dots_and_ellipsis() {
+        let mut lexer = Lexer::new("a.b .. ... ....");
+        assert_eq!(
+            lexer.tokenize(),
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::Dot,
+                Token::Identifier("b".to_string()),
+                Token::Dot,
+                Token::Dot,
+                Token::Ellipsis,
+                Token::Ellipsis,
+                Token::Dot,
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_variadic_prototype_parses() {
+        let mut parser = Parser::new("int printf(const char* fmt, ...);");
+        let result = parser.parse_program();
+        assert!(
+            result.is_ok(),
+            "Failed to parse variadic prototype: {:?}",
+            result.err()
+        );
+    }
+
+    #[test]
+    fn test_token_positions() {
+        let mut lexer = Lexer::new("int x;\n  /* c */ x = 1;\n");
+        let tokens = lexer.tokenize_spanned();
+        let at = |token, line, col| SpannedToken { token, line, col };
+        assert_eq!(
+            tokens,
+            vec![
+                at(Token::Int, 1, 1),
+                at(Token::Identifier("x".to_string()), 1, 5),
+                at(Token::Semicolon, 1, 6),
+                at(Token::Identifier("x".to_string()), 2, 11),
+                at(Token::Assign, 2, 13),
+                at(Token::IntLiteral(1), 2, 15),
+                at(Token::Semicolon, 2, 16),
+                at(Token::Eof, 3, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parser_error_has_location() {
+        let input = "int main() {\n    int x = 1;\n    return x\n}\n";
+        let mut parser = Parser::new(input);
+        let err = parser.parse_program().expect_err("missing ';' should fail");
+        assert_eq!(err.to_string(), "4:1: Expected ';', got '}'");
+    }
+
+    #[test]
+    fn test_parser_error_at_eof() {
+        let mut parser = Parser::new("int main() {\n    return 0;\n");
+        let err = parser.parse_program().expect_err("missing '}' should fail");
+        assert_eq!(err.to_string(), "3:1: Expected '}', got 'end of input'");
+    }
+
+    #[test]
+    fn test_lexer_iterator() {
+        let lexer = Lexer::new("x = y + 1;");
+        let tokens: Vec<Token> = lexer.collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Identifier("x".to_string()),
+                Token::Assign,
+                Token::Identifier("y".to_string()),
+                Token::Plus,
+                Token::IntLiteral(1),
+                Token::Semicolon,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parser_from_lexer() {
+        let input = r#"
+        int add(int a, int b) {
+            return a + b;
+        }
+        "#;
+
+        let mut streaming = Parser::from_lexer(Lexer::new(input));
+        let mut eager = Parser::new(input);
+        assert_eq!(streaming.parse_program(), eager.parse_program());
+    }
+
+    #[test]
+    fn test_qualifier_and_specifier_keywords() {
+        let tokens =
+            Lexer::new("restrict __restrict inline __inline__ _Atomic _Alignas").tokenize();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Restrict,
+                Token::Restrict,
+                Token::Inline,
+                Token::Inline,
+                Token::Atomic,
+                Token::Alignas,
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_typeof_keywords() {
+        let tokens = Lexer::new("typeof __typeof__ __typeof typeof_").tokenize();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Typeof,
+                Token::Typeof,
+                Token::Typeof,
+                Token::Identifier("typeof_".to_string()),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bool_keywords() {
+        let tokens = Lexer::new("_Bool bool Bool").tokenize();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Bool,
+                Token::Bool,
+                Token::Identifier("Bool".to_string()),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_include_directives() {
+        let tokens =
+            Lexer::new("#include <stdio.h>  \n  # include \"util.h\"\t// local\nint x;").tokenize();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Include("<stdio.h>".to_string()),
+                Token::Include("\"util.h\"".to_string()),
+                Token::Int,
+                Token::Identifier("x".to_string()),
+                Token::Semicolon,
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_malformed_include_is_error() {
+        let tokens = Lexer::new("#include stdio.h\n#include <stdio.h\n").tokenize();
+        assert!(matches!(&tokens[0], Token::Error(msg) if msg.contains("#include")));
+        assert!(matches!(&tokens[1], Token::Error(msg) if msg.contains("Unterminated")));
+    }
+
+    #[test]
+    fn test_pragma_directives() {
+        let tokens = Lexer::new("#pragma once\n#pragma pack(push, 1) // note\nint y;").tokenize();
+        assert_eq!(
+            tokens[..2],
+            [
+                Token::Pragma("once".to_string()),
+                Token::Pragma("pack(push, 1)".to_string()),
+            ]
+        );
+        assert_eq!(tokens[2], Token::Int);
+    }
+
+    #[test]
+    fn test_define_directives() {
+        let src = "#define MAX 100\n#define EMPTY\n#define MSG \"a // b\" // note\n#define SUM 1 + \\\n    2\n#define SQ(x) ((x) * (x))\nint y;";
+        let tokens = Lexer::new(src).tokenize();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Define("MAX".to_string(), "100".to_string()),
+                Token::Define("EMPTY".to_string(), String::new()),
+                Token::Define("MSG".to_string(), "\"a // b\"".to_string()),
+                Token::Define("SUM".to_string(), "1 +      2".to_string()),
+                Token::Int,
+                Token::Identifier("y".to_string()),
+                Token::Semicolon,
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parser_error_render() {
+        let input = "int main() {\n\tint x = 1 @ 2;\n}\n";
+        let err = Parser::new(input).parse_program().unwrap_err();
+        assert_eq!((err.line, err.col), (2, 12));
+        assert_eq!(
+            err.render(input),
+            "2:12: Expected ';', got '@'\n  |\n2 | \tint x = 1 @ 2;\n  | \t          ^"
+        );
+    }
+}
@@ -0,0 +1,110 @@
+/// 测试库级入口函数
+use c_to_rust_tool::ast::*;
+use c_to_rust_tool::{parse_dir, parse_dir_with, parse_str, translate_to_c, typedef_names};
+use std::path::Path;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_str() {
+        let program = parse_str("int x = 1;").unwrap();
+        assert_eq!(
+            program.declarations,
+            vec![Declaration::GlobalVar {
+                storage: None,
+                typ: CType::Int,
+                name: "x".to_string(),
+                init: Some(Expr::IntLiteral(1)),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_str_reports_errors() {
+        let err = parse_str("int main() { return 0 }").unwrap_err();
+        assert_eq!((err.line, err.col), (1, 23));
+        assert_eq!(err.message, "Expected ';', got '}'");
+    }
+
+    #[test]
+    fn test_translate_to_c() {
+        let output = translate_to_c("int main(){return 1+2;}").unwrap();
+        assert_eq!(output.trim_end(), "int main() {\n    return 1 + 2;\n}");
+        assert!(translate_to_c("int main( {").is_err());
+    }
+
+    const FIXTURE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/parse_dir");
+
+    #[test]
+    fn test_parse_dir() {
+        let report = parse_dir(Path::new(FIXTURE_DIR)).unwrap();
+        // notes.txt 不是 .c 文件，不参与统计；文件按路径排序
+        assert_eq!(report.total_files, 2);
+        assert_eq!((report.succeeded, report.failed), (1, 1));
+        assert_eq!(report.files[0].path.file_name().unwrap(), "bad.c");
+        assert_eq!(report.files[0].declarations, 1);
+        assert_eq!(report.files[0].errors.len(), 1);
+        // 没有 prelude 时行号与源文件一致
+        assert!(
+            report.files[0].errors[0].starts_with("5:13: "),
+            "{}",
+            report.files[0].errors[0]
+        );
+        assert!(report.files[1].errors.is_empty());
+        assert_eq!(report.files[1].declarations, 2);
+        assert_eq!((report.declarations_ok, report.declarations_failed), (3, 1));
+    }
+
+    #[test]
+    fn test_parse_dir_with_prelude_and_sanitize() {
+        let prelude = "typedef unsigned long size_t;";
+        let sanitize = |src: &str| src.replace("broken( {", "broken() {");
+        let report = parse_dir_with(Path::new(FIXTURE_DIR), prelude, &sanitize).unwrap();
+        assert_eq!((report.succeeded, report.failed), (2, 0));
+        // prelude 中的 typedef 也计入声明数
+        assert_eq!(report.files[0].declarations, 3);
+        assert_eq!(report.files[1].declarations, 3);
+    }
+
+    #[test]
+    fn test_parse_dir_shares_typedefs_across_files() {
+        // list.c 使用 types.c 中定义的 Node 与 usize，且按路径排序在它之前
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/multi_unit");
+        let report = parse_dir(Path::new(dir)).unwrap();
+        assert_eq!(report.files[0].path.file_name().unwrap(), "list.c");
+        assert!(
+            report.files[0].errors.is_empty(),
+            "{:?}",
+            report.files[0].errors
+        );
+        assert_eq!((report.succeeded, report.failed), (2, 0));
+        // 单独解析 list.c 时这些名字未知
+        let list = std::fs::read_to_string(&report.files[0].path).unwrap();
+        assert!(parse_str(&list).is_err());
+    }
+
+    #[test]
+    fn test_typedef_names() {
+        let program = parse_str("typedef int a, *b; struct S { int x; }; int c;").unwrap();
+        let mut names: Vec<String> = typedef_names(&program).into_iter().collect();
+        names.sort();
+        assert_eq!(names, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_parse_dir_missing_directory() {
+        assert!(parse_dir(Path::new("tests/fixtures/does_not_exist")).is_err());
+    }
+
+    #[test]
+    fn test_non_ascii_source() {
+        let src = "// 注释：héllo €\nint f(void) {\n    char *s = \"héllo €\";\n    int c = '€';\n    /* ünïcode */ return 0;\n}\n";
+        let output = translate_to_c(src).unwrap();
+        assert_eq!(
+            output.trim_end(),
+            "int f(void) {\n    char *s = \"héllo €\";\n    int c = '€';\n    return 0;\n}"
+        );
+    }
+}
@@ -0,0 +1,142 @@
+/// 测试解析前的源码清理
+use c_to_rust_tool::preprocess::{
+    remove_attribute_blocks, replace_word, sanitize, SanitizeOptions,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attribute_with_nested_parens() {
+        let output =
+            remove_attribute_blocks("int buf[4] __attribute__((aligned(16)));", "__attribute__");
+        assert_eq!(output, "int buf[4] ;");
+    }
+
+    #[test]
+    fn test_multiple_attributes() {
+        let output = remove_attribute_blocks(
+            "void f(void) __attribute__ ((noreturn)) __attribute__((format(printf, 1, 2)));",
+            "__attribute__",
+        );
+        assert_eq!(output, "void f(void)  ;");
+    }
+
+    #[test]
+    fn test_attribute_with_parens_in_string() {
+        let output = remove_attribute_blocks(
+            r#"int x __attribute__((section("(.text)"))) = 1;"#,
+            "__attribute__",
+        );
+        assert_eq!(output, "int x  = 1;");
+
+        let output = remove_attribute_blocks(
+            r#"void f(void) __attribute__((deprecated("use g() \" instead )"))); int y;"#,
+            "__attribute__",
+        );
+        assert_eq!(output, "void f(void) ; int y;");
+    }
+
+    #[test]
+    fn test_attribute_with_parens_in_char_literal() {
+        let output = remove_attribute_blocks(
+            "int x __attribute__((marker(')', '\\'', '('))); int y;",
+            "__attribute__",
+        );
+        assert_eq!(output, "int x ; int y;");
+    }
+
+    #[test]
+    fn test_attribute_with_unterminated_string_keeps_rest() {
+        let output =
+            remove_attribute_blocks(r#"int x __attribute__((section(")));"#, "__attribute__");
+        assert_eq!(output, r#"int x ((section(")));"#);
+    }
+
+    #[test]
+    fn test_unbalanced_attribute_keeps_rest() {
+        // 括号不配平时只删除 __attribute__，其后的代码保留下来
+        let output =
+            remove_attribute_blocks("int x __attribute__((packed);\nint y;", "__attribute__");
+        assert_eq!(output, "int x ((packed);\nint y;");
+    }
+
+    #[test]
+    fn test_attribute_without_parens() {
+        let output = remove_attribute_blocks("int __attribute__ x; f(a);", "__attribute__");
+        assert_eq!(output, "int  x; f(a);");
+    }
+
+    #[test]
+    fn test_replace_word_skips_identifier_substrings() {
+        assert_eq!(
+            replace_word("noreturn void f(); int noreturn_count;", "noreturn", ""),
+            " void f(); int noreturn_count;"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_defaults() {
+        let src = "#include <stdio.h>\n#define MAX(a, b) \\\n    ((a) > (b) ? (a) : (b))\n_Noreturn void die(void) __attribute__((cold));\n";
+        let output = sanitize(src, &SanitizeOptions::default());
+        assert_eq!(output, "#include <stdio.h>\n void die(void) ;");
+    }
+
+    #[test]
+    fn test_sanitize_keeps_pragmas_by_default() {
+        let src = "#pragma pack(1)\n#ifdef X\nstruct S { char c; };\n#endif";
+        assert_eq!(
+            sanitize(src, &SanitizeOptions::default()),
+            "#pragma pack(1)\nstruct S { char c; };"
+        );
+        let opts = SanitizeOptions {
+            strip_pragmas: true,
+            ..SanitizeOptions::default()
+        };
+        assert_eq!(sanitize(src, &opts), "struct S { char c; };");
+    }
+
+    #[test]
+    fn test_sanitize_options() {
+        let src = "#include <stdio.h>\n#define N 4\nint a[N] __attribute__((aligned(16)));";
+        let opts = SanitizeOptions {
+            strip_includes: true,
+            remove_attributes: false,
+            ..SanitizeOptions::default()
+        };
+        assert_eq!(
+            sanitize(src, &opts),
+            "int a[N] __attribute__((aligned(16)));"
+        );
+
+        let opts = SanitizeOptions {
+            strip_directives: false,
+            removed_keywords: vec![],
+            ..SanitizeOptions::default()
+        };
+        assert_eq!(
+            sanitize(src, &opts),
+            "#include <stdio.h>\n#define N 4\nint a[N] ;"
+        );
+    }
+
+    #[test]
+    fn test_non_ascii_source() {
+        // 多字节字符紧挨着要删除的内容时不能按字节切分
+        let src = "// 注释：héllo €\n_Noreturn void f(void) __attribute__((section(\"é(\")));\nchar *s = \"€__attribute__\";";
+        let output = sanitize(src, &SanitizeOptions::default());
+        assert_eq!(
+            output,
+            "// 注释：héllo €\n void f(void) ;\nchar *s = \"€\";"
+        );
+        assert_eq!(
+            replace_word("é_Noreturn €_Noreturn", "_Noreturn", ""),
+            "é_Noreturn €"
+        );
+        assert_eq!(
+            remove_attribute_blocks("int x __attribute__((é", "__attribute__"),
+            "int x ((é"
+        );
+    }
+}
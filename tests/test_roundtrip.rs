@@ -0,0 +1,179 @@
+/// 测试生成的 C 代码重新解析后得到相同的 AST
+use c_to_rust_tool::ast::{Declaration, Program};
+use c_to_rust_tool::codegen::CodeGenerator;
+use c_to_rust_tool::parse_str;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 去掉已知会在生成时丢失的结构：AST 不区分函数原型与空函数体，
+    // C 代码生成只输出有函数体的函数
+    fn without_lossy(program: &Program) -> Program {
+        let declarations = program
+            .declarations
+            .iter()
+            .filter(|decl| !matches!(decl, Declaration::Function(func) if func.body.is_empty()))
+            .cloned()
+            .collect();
+        Program { declarations }
+    }
+
+    // 解析 src，生成 C 代码后再次解析，两次得到的 Program 必须相同
+    fn assert_roundtrip(src: &str) {
+        let program = parse_str(src).unwrap_or_else(|e| panic!("{}\n源码:\n{}", e, src));
+        let output = CodeGenerator::new().generate_program(&program);
+        let reparsed = parse_str(&output)
+            .unwrap_or_else(|e| panic!("生成的代码无法解析: {}\n输出:\n{}", e, output));
+        assert_eq!(
+            reparsed,
+            without_lossy(&program),
+            "重新解析后 AST 不一致\n输出:\n{}",
+            output
+        );
+    }
+
+    // main.rs 演示用的示例程序
+    const SAMPLES: &[&str] = &[
+        r#"
+int main() {
+    int* p = (int*)malloc(sizeof(int));
+    *p = 42;
+    return 0;
+}
+"#,
+        r#"
+int main() {
+    int arr[10];
+    arr[0] = 1;
+    arr[1] = 2;
+    int sum = arr[0] + arr[1];
+    return sum;
+}
+"#,
+        r#"
+struct Point {
+    int x;
+    int y;
+};
+
+int main() {
+    struct Point p;
+    p.x = 10;
+    p.y = 20;
+    return p.x + p.y;
+}
+"#,
+        r#"
+struct Point {
+    int x;
+    int y;
+};
+
+int main() {
+    struct Point* p;
+    p->x = 10;
+    p->y = 20;
+    return p->x + p->y;
+}
+"#,
+        r#"
+int main() {
+    int i = 0;
+    i++;
+    ++i;
+    i--;
+    --i;
+    return i;
+}
+"#,
+        r#"
+int main() {
+    int a = 5;
+    int b = 3;
+    int c = a & b;
+    int d = a | b;
+    int e = a ^ b;
+    int f = ~a;
+    int g = a << 2;
+    int h = a >> 1;
+    return 0;
+}
+"#,
+        r#"
+int main() {
+    int a = 5;
+    int b = 10;
+    int max = (a > b) ? a : b;
+    return max;
+}
+"#,
+        r#"
+int main() {
+    int x = 42;
+    int* p = &x;
+    int y = *p;
+    *p = 100;
+    return y;
+}
+"#,
+        r#"
+struct Node {
+    int value;
+    struct Node* next;
+};
+
+int main() {
+    struct Node* head = (struct Node*)malloc(sizeof(struct Node));
+    head->value = 42;
+    int arr[10];
+    arr[0] = head->value;
+    int result = (arr[0] > 0) ? arr[0] * 2 : 0;
+    return result;
+}
+"#,
+    ];
+
+    #[test]
+    fn test_demo_samples_roundtrip() {
+        for src in SAMPLES {
+            assert_roundtrip(src);
+        }
+    }
+
+    #[test]
+    fn test_mixed_program_roundtrip() {
+        assert_roundtrip(
+            r#"
+typedef struct Node Node;
+struct Node { int value; Node *next; };
+static int counter = 0;
+extern const char *names[3];
+int (*handler)(int, char *);
+enum Kind { A, B = 4, C };
+int apply(int (*fn)(int), int x) { return fn(x); }
+void *alloc(unsigned long n);
+int main(int argc, char **argv) {
+    int i, j = 1, *p = NULL;
+    char buf[16] = "hi";
+    for (i = 0; i < 10; i++) {
+        if (i % 2 == 0) continue; else j += i;
+    }
+    while (j > 0) j--;
+    do { j++; } while (j < 3);
+    switch (j) { case 1: case 2 ... 5: j = 0; break; default: break; }
+    goto done;
+done:
+    p = (int *)alloc(sizeof(int) * 4);
+    p[0] = -1;
+    j = p ? *p : 0;
+    j = (i, j);
+    unsigned long long big = 10ULL;
+    float f = 1.5f;
+    char c = '\n';
+    return !j && ~i || sizeof p;
+}
+"#,
+        );
+    }
+}
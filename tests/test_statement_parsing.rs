@@ -0,0 +1,391 @@
+/// 测试语句解析功能
+use c_to_rust_tool::ast::*;
+use c_to_rust_tool::parser::Parser;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 解析单个函数并返回其函数体
+    fn parse_body(input: &str) -> Vec<Stmt> {
+        let program = Parser::new(input).parse().unwrap();
+        match program.declarations.into_iter().next() {
+            Some(Declaration::Function(func)) => func.body,
+            other => panic!("Expected function, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_switch_cases() {
+        let body = parse_body(
+            r#"
+            int f(int x) {
+                switch (x) {
+                    case 1:
+                        x = 10;
+                        break;
+                    default:
+                        x = 0;
+                }
+                return x;
+            }
+            "#,
+        );
+        let Stmt::Switch { expr, cases } = &body[0] else {
+            panic!("Expected switch, got {:?}", body[0]);
+        };
+        assert_eq!(*expr, Expr::Identifier("x".to_string()));
+        assert_eq!(cases.len(), 2);
+        assert_eq!(cases[0].value, Some(Expr::IntLiteral(1)));
+        assert_eq!(cases[0].stmts.len(), 2);
+        assert!(matches!(cases[0].stmts[1], Stmt::Break(_)));
+        assert_eq!(cases[1].value, None);
+        assert_eq!(cases[1].stmts.len(), 1);
+    }
+
+    #[test]
+    fn test_switch_fallthrough_and_default_first() {
+        let body = parse_body(
+            r#"
+            int f(int x) {
+                switch (x) {
+                    default:
+                        return -1;
+                    case 1:
+                    case 2:
+                        return 2;
+                }
+            }
+            "#,
+        );
+        let Stmt::Switch { cases, .. } = &body[0] else {
+            panic!("Expected switch, got {:?}", body[0]);
+        };
+        assert_eq!(cases.len(), 3);
+        assert_eq!(cases[0].value, None);
+        assert_eq!(cases[1].value, Some(Expr::IntLiteral(1)));
+        assert!(cases[1].stmts.is_empty());
+        assert_eq!(cases[2].value, Some(Expr::IntLiteral(2)));
+        assert_eq!(cases[2].stmts.len(), 1);
+    }
+
+    #[test]
+    fn test_switch_case_range() {
+        let body = parse_body(
+            "int f(int x) { switch (x) { case 0: return 0; case 1 ... 5: return 1; case 'a' ... 'z': return 2; } return -1; }",
+        );
+        let Stmt::Switch { cases, .. } = &body[0] else {
+            panic!("Expected switch, got {:?}", body[0]);
+        };
+        assert_eq!(cases.len(), 3);
+        assert_eq!(cases[0].value, Some(Expr::IntLiteral(0)));
+        assert_eq!(cases[0].range_end, None);
+        assert_eq!(cases[1].value, Some(Expr::IntLiteral(1)));
+        assert_eq!(cases[1].range_end, Some(Expr::IntLiteral(5)));
+        assert_eq!(cases[2].value, Some(Expr::CharLiteral('a')));
+        assert_eq!(cases[2].range_end, Some(Expr::CharLiteral('z')));
+    }
+
+    #[test]
+    fn test_switch_case_range_missing_upper_bound() {
+        let err = Parser::new("int f(int x) { switch (x) { case 1 ...: return 1; } }")
+            .parse()
+            .unwrap_err();
+        assert!(err.message.contains("upper bound"), "{}", err.message);
+    }
+
+    #[test]
+    fn test_switch_requires_label() {
+        let result = Parser::new("int f(int x) { switch (x) { x = 1; } }").parse();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_single_statement_loop_bodies() {
+        let body =
+            parse_body("void f(int x, int y) { while (x) ; while (x) y++; do ; while (x); }");
+        assert_eq!(
+            body[0],
+            Stmt::While {
+                cond: Expr::Identifier("x".to_string()),
+                body: vec![],
+            }
+        );
+        let Stmt::While { body: inner, .. } = &body[1] else {
+            panic!("Expected while, got {:?}", body[1]);
+        };
+        assert_eq!(inner.len(), 1);
+        assert!(matches!(body[2], Stmt::DoWhile { ref body, .. } if body.is_empty()));
+    }
+
+    #[test]
+    fn test_empty_for_body() {
+        let body = parse_body("void f(void) { for (;;) ; }");
+        assert_eq!(
+            body,
+            vec![Stmt::For {
+                init: None,
+                cond: None,
+                update: None,
+                body: vec![],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_stray_semicolons_at_file_scope() {
+        let program = Parser::new("int x;;\nvoid f(void) { ; };\n;")
+            .parse()
+            .unwrap();
+        assert_eq!(program.declarations.len(), 2);
+        assert!(matches!(
+            &program.declarations[1],
+            Declaration::Function(func) if func.body == vec![Stmt::Empty]
+        ));
+    }
+
+    #[test]
+    fn test_labeled_loop_body() {
+        // 标签标注的是其后的语句，两者都属于循环体
+        let body = parse_body("void f(int x, int y) { while (x) again: y--; return; }");
+        assert_eq!(body.len(), 2);
+        let Stmt::While { body: inner, .. } = &body[0] else {
+            panic!("Expected while, got {:?}", body[0]);
+        };
+        assert_eq!(inner[0], Stmt::Label("again".to_string()));
+        assert!(matches!(inner[1], Stmt::Expr(_)));
+        assert_eq!(body[1], Stmt::Return(None));
+    }
+
+    #[test]
+    fn test_empty_statement_in_block() {
+        let body = parse_body("void f(void) { ; { ; } }");
+        assert_eq!(body, vec![Stmt::Empty, Stmt::Block(vec![Stmt::Empty])]);
+    }
+
+    #[test]
+    fn test_typeof_declarations() {
+        let body = parse_body("int f(int x) { typeof(x) y = x; __typeof__(int *) p; return y; }");
+        assert_eq!(
+            body[0],
+            Stmt::VarDecl {
+                storage: None,
+                typ: CType::TypeOf(Box::new(Expr::Identifier("x".to_string()))),
+                name: "y".to_string(),
+                init: Some(Expr::Identifier("x".to_string())),
+            }
+        );
+        // typeof(类型) 直接得到其中的类型
+        assert_eq!(
+            body[1],
+            Stmt::VarDecl {
+                storage: None,
+                typ: CType::Pointer(Box::new(CType::Int)),
+                name: "p".to_string(),
+                init: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_label_and_goto() {
+        let body = parse_body("int f() { int i = 0; loop: i++; goto loop; }");
+        assert_eq!(body[1], Stmt::Label("loop".to_string()));
+        assert!(matches!(body[2], Stmt::Expr(_)));
+        assert_eq!(body[3], Stmt::Goto("loop".to_string()));
+    }
+
+    #[test]
+    fn test_label_not_confused_with_ternary_or_case() {
+        let body =
+            parse_body("int f(int x) { x = x ? a : b; switch (x) { case A: x = 1; } return x; }");
+        assert!(matches!(body[0], Stmt::Expr(Expr::Assignment { .. })));
+        let Stmt::Switch { cases, .. } = &body[1] else {
+            panic!("Expected switch, got {:?}", body[1]);
+        };
+        assert_eq!(cases[0].value, Some(Expr::Identifier("A".to_string())));
+        assert_eq!(cases[0].stmts.len(), 1);
+    }
+
+    #[test]
+    fn test_multi_dimensional_array_declarations() {
+        let body = parse_body("int f() { int a[3][4]; char b[2][3][4]; return 0; }");
+        let array = |element_type: CType, size: usize| CType::Array {
+            element_type: Box::new(element_type),
+            size: Some(size),
+            size_expr: None,
+        };
+        assert_eq!(
+            body[0],
+            Stmt::VarDecl {
+                storage: None,
+                typ: array(array(CType::Int, 4), 3),
+                name: "a".to_string(),
+                init: None,
+            }
+        );
+        assert_eq!(
+            body[1],
+            Stmt::VarDecl {
+                storage: None,
+                typ: array(array(array(CType::Char, 4), 3), 2),
+                name: "b".to_string(),
+                init: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_pointer_star_binds_to_first_declarator() {
+        let body = parse_body("int main() { int *a, b; return 0; }");
+        let Stmt::DeclList(decls) = &body[0] else {
+            panic!("Expected declaration block, got {:?}", body[0]);
+        };
+        assert!(
+            matches!(&decls[0], Stmt::VarDecl { typ: CType::Pointer(_), name, .. } if name == "a")
+        );
+        assert!(matches!(&decls[1], Stmt::VarDecl { typ: CType::Int, name, .. } if name == "b"));
+    }
+
+    #[test]
+    fn test_for_init_with_multiple_declarators() {
+        let body =
+            parse_body("int main() { for (int i = 0, n = 10; i < n; i++, j++) { } return 0; }");
+        let Stmt::For { init, update, .. } = &body[0] else {
+            panic!("Expected for loop, got {:?}", body[0]);
+        };
+        let Some(Stmt::DeclList(decls)) = init.as_deref() else {
+            panic!("Expected declaration list, got {:?}", init);
+        };
+        assert_eq!(decls.len(), 2);
+        assert!(matches!(&decls[1], Stmt::VarDecl { name, init: Some(_), .. } if name == "n"));
+        assert!(matches!(update, Some(Expr::Comma(exprs)) if exprs.len() == 2));
+    }
+
+    #[test]
+    fn test_inline_asm_statements() {
+        let body = parse_body(
+            r#"
+            int f(int y) {
+                int x;
+                asm volatile("nop");
+                __asm__ __volatile__("mov %1, %0" : "=r" (x) : "r" (y) : "cc", "memory");
+                asm("" ::: "memory");
+                return x;
+            }
+            "#,
+        );
+        assert_eq!(
+            body[1],
+            Stmt::InlineAsm("asm volatile(\"nop\")".to_string())
+        );
+        assert_eq!(
+            body[2],
+            Stmt::InlineAsm(
+                "asm volatile(\"mov %1, %0\" : \"=r\"(x) : \"r\"(y) : \"cc\", \"memory\")"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            body[3],
+            Stmt::InlineAsm("asm(\"\" : : : \"memory\")".to_string())
+        );
+        assert_eq!(
+            body[4],
+            Stmt::Return(Some(Expr::Identifier("x".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_unterminated_inline_asm() {
+        let err = Parser::new("void f() { asm volatile(\"nop\"; }")
+            .parse()
+            .unwrap_err();
+        assert_eq!(err.message, "Unterminated asm statement");
+    }
+
+    #[test]
+    fn test_local_specifiers_in_any_order() {
+        let body = parse_body("void f() { int static x; const static int y = 2; int register r; }");
+        assert!(matches!(
+            &body[0],
+            Stmt::VarDecl {
+                storage: Some(StorageClass::Static),
+                typ: CType::Int,
+                ..
+            }
+        ));
+        assert!(matches!(
+            &body[1],
+            Stmt::VarDecl {
+                storage: Some(StorageClass::Static),
+                typ: CType::Const(_),
+                init: Some(_),
+                ..
+            }
+        ));
+        assert!(matches!(
+            &body[2],
+            Stmt::VarDecl {
+                storage: Some(StorageClass::Register),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_star_statement_depends_on_typedef() {
+        // foo 不是类型名时 foo * bar; 是乘法表达式
+        let body = parse_body("int f(int foo, int bar) { foo * bar; return 0; }");
+        assert_eq!(
+            body[0],
+            Stmt::Expr(Expr::Binary {
+                op: BinaryOp::Mul,
+                left: Box::new(Expr::Identifier("foo".to_string())),
+                right: Box::new(Expr::Identifier("bar".to_string())),
+            })
+        );
+        // foo 是 typedef 名时是指针变量声明
+        let program = Parser::new("typedef int foo; int f(void) { foo * bar; return 0; }")
+            .parse()
+            .unwrap();
+        let Some(Declaration::Function(func)) = program.declarations.get(1) else {
+            panic!("Expected function");
+        };
+        assert_eq!(
+            func.body[0],
+            Stmt::VarDecl {
+                storage: None,
+                typ: CType::Pointer(Box::new(CType::Typedef("foo".to_string()))),
+                name: "bar".to_string(),
+                init: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_unknown_type_name_declarations() {
+        let body =
+            parse_body("int f(void) { time_t now = 0; FILE *out = 0; time_t a, b; return now; }");
+        assert_eq!(
+            body[0],
+            Stmt::VarDecl {
+                storage: None,
+                typ: CType::Typedef("time_t".to_string()),
+                name: "now".to_string(),
+                init: Some(Expr::IntLiteral(0)),
+            }
+        );
+        assert!(matches!(
+            &body[1],
+            Stmt::VarDecl { typ: CType::Pointer(inner), name, .. }
+                if **inner == CType::Typedef("FILE".to_string()) && name == "out"
+        ));
+        assert!(matches!(&body[2], Stmt::DeclList(decls) if decls.len() == 2));
+        // 不能作为声明时报告声明的错误
+        let err = Parser::new("int f(void) { foo bar baz; }")
+            .parse()
+            .unwrap_err();
+        assert!(err.message.contains("Expected ';'"), "{}", err);
+    }
+}